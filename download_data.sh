@@ -10,6 +10,6 @@ cd data
 rm stackoverflow.com-Posts.jsonl.gz
 
 for filename in *.gz; do
+    # Los .jsonl.gz se procesan directamente, no hace falta descomprimirlos
     curl -o $filename -L https://huggingface.co/datasets/flax-sentence-embeddings/stackexchange_title_body_jsonl/resolve/main/$filename 
-    gunzip -v $filename
 done
\ No newline at end of file
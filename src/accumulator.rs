@@ -0,0 +1,234 @@
+use std::{collections::HashMap, io, mem, path::Path};
+
+use crate::{
+    cooccurrence::TagPairs,
+    error::{ErrorPolicy, ProcessingError, SiteErrors},
+    line::{Line, LineError},
+    options::ProcessOptions,
+    processed_sites::{ProcessedSites, PADRON},
+    question::Question,
+    site::Site,
+    tag::Tag,
+};
+
+/// Resultado parcial del procesamiento. Cada thread de Rayon va agregando sus líneas a su propio acumulador (fold), y recién al final se unen los acumuladores de a pares (reduce).
+/// Así no se crea una estructura por línea, y los Tags totales se calculan una sola vez a partir de los Sites.
+#[derive(Debug, Default)]
+pub struct SitesAccumulator {
+    pub sites: HashMap<String, Site>,
+    pub errors: HashMap<String, SiteErrors>,
+    pub skipped_lines: usize,
+    pub unreadable_files: usize,
+}
+
+impl SitesAccumulator {
+    /// Agrega al acumulador la línea número `number` del archivo en `path`, que pertenece al Site `sitename`.
+    /// Si la línea no se puede leer o parsear, se devuelve el error o se la saltea según la política de errores de las opciones. Las preguntas excluidas en las opciones no se suman.
+    pub fn add_line(
+        mut self,
+        path: &Path,
+        sitename: &str,
+        number: usize,
+        line: io::Result<String>,
+        options: &ProcessOptions,
+    ) -> Result<Self, ProcessingError> {
+        if options.is_excluded(path, number) {
+            return Ok(self);
+        }
+        match parse_line(path, number, line) {
+            Ok(line_data) => self.add_question(sitename, line_data, options),
+            Err(error) => self.skip_line(error, sitename, options.error_policy)?,
+        }
+        Ok(self)
+    }
+
+    /// Suma una pregunta al Site indicado, creándolo si todavía no estaba en este acumulador.
+    fn add_question(&mut self, sitename: &str, line_data: Line, options: &ProcessOptions) {
+        let mut question = Question::from_line(&line_data, options.tokenizer);
+        if options.languages {
+            question = question.with_language(&line_data);
+        }
+        if options.vocabulary.is_some() || options.top_words.is_some() {
+            question = question.with_tokens(&line_data, options.tokenizer);
+        }
+        if options.readability {
+            question = question.with_readability(&line_data);
+        }
+        match self.sites.get_mut(sitename) {
+            Some(site) => site.add_question(&question, line_data.tags),
+            None => {
+                let mut site = Site::empty(options);
+                site.add_question(&question, line_data.tags);
+                self.sites.insert(sitename.to_string(), site);
+            }
+        }
+    }
+
+    /// Aplica la política de errores a una línea inválida del Site `sitename`: devuelve el error, o cuenta la línea salteada en el reporte de errores.
+    /// Si el error cortó la lectura del archivo, se cuenta como un archivo que no se pudo leer completo en vez de como una línea salteada.
+    fn skip_line(
+        &mut self,
+        error: ProcessingError,
+        sitename: &str,
+        error_policy: ErrorPolicy,
+    ) -> Result<(), ProcessingError> {
+        match error_policy {
+            ErrorPolicy::FailFast => return Err(error),
+            ErrorPolicy::SkipAndLog => eprintln!("[WARN] Se saltea una línea. {}", error),
+            ErrorPolicy::SkipAndCount => {}
+        }
+        if error.is_file_error() {
+            self.unreadable_files += 1;
+        } else {
+            self.skipped_lines += 1;
+        }
+        self.errors
+            .entry(sitename.to_string())
+            .or_default()
+            .add(&SiteErrors::from_error(&error));
+        Ok(())
+    }
+
+    /// Une dos acumuladores, moviendo los Sites y errores del más chico al más grande sin clonarlos.
+    pub fn merge(mut self, mut other: SitesAccumulator) -> SitesAccumulator {
+        if self.sites.len() < other.sites.len() {
+            mem::swap(&mut self, &mut other);
+        }
+        self.skipped_lines += other.skipped_lines;
+        self.unreadable_files += other.unreadable_files;
+        other.sites.into_iter().for_each(|(site_name, site)| {
+            match self.sites.get_mut(&site_name) {
+                Some(s) => s.merge(site),
+                None => {
+                    self.sites.insert(site_name, site);
+                }
+            }
+        });
+        other
+            .errors
+            .into_iter()
+            .for_each(|(site_name, site_errors)| {
+                self.errors.entry(site_name).or_default().add(&site_errors);
+            });
+        self
+    }
+
+    /// Convierte el acumulador en un ProcessedSites, calculando los Tags (y pares de tags) totales a partir de los de cada Site.
+    pub fn into_processed_sites(self) -> ProcessedSites {
+        let mut tags: HashMap<String, Tag> = HashMap::new();
+        self.sites.values().for_each(|site| {
+            site.tags
+                .iter()
+                .for_each(|(tag_name, tag)| match tags.get_mut(tag_name) {
+                    Some(t) => t.add(tag),
+                    None => {
+                        tags.insert(tag_name.to_string(), tag.clone());
+                    }
+                });
+        });
+        let cooccurrence = self
+            .sites
+            .values()
+            .filter_map(|site| site.cooccurrence.as_ref())
+            .fold(None, |total: Option<TagPairs>, pairs| {
+                let mut total = total.unwrap_or_default();
+                total.add(pairs);
+                Some(total)
+            });
+        let mut processed_sites =
+            ProcessedSites::new(PADRON.to_string(), self.sites, tags, HashMap::new());
+        processed_sites.cooccurrence = cooccurrence;
+        processed_sites.skipped_lines = self.skipped_lines;
+        processed_sites.unreadable_files = self.unreadable_files;
+        processed_sites.errors = Some(self.errors);
+        processed_sites
+    }
+}
+
+/// Parsea la línea número `number` del archivo en `path` a un Line, convirtiendo los errores de lectura o de parseo en ProcessingError.
+fn parse_line(
+    path: &Path,
+    number: usize,
+    line: io::Result<String>,
+) -> Result<Line, ProcessingError> {
+    let line = line.map_err(|source| ProcessingError::Io {
+        path: path.to_path_buf(),
+        line: Some(number),
+        source,
+    })?;
+    Line::parse(&line).map_err(|e| match e {
+        LineError::Json(source) => ProcessingError::Json {
+            path: path.to_path_buf(),
+            line: number,
+            source,
+        },
+        LineError::MissingField(field) => ProcessingError::MissingField {
+            path: path.to_path_buf(),
+            line: number,
+            field,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE_1: &str = "{\"texts\": [\"uno dos\", \"tres\"], \"tags\": [\"a\", \"b\"]}";
+    const LINE_2: &str = "{\"texts\": [\"uno\"], \"tags\": [\"b\"]}";
+
+    fn add(
+        accumulator: SitesAccumulator,
+        sitename: &str,
+        number: usize,
+        line: &str,
+    ) -> SitesAccumulator {
+        accumulator
+            .add_line(
+                Path::new("site.jsonl"),
+                sitename,
+                number,
+                Ok(line.to_string()),
+                &ProcessOptions {
+                    error_policy: ErrorPolicy::SkipAndCount,
+                    ..ProcessOptions::default()
+                },
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn adds_lines_in_place() {
+        let accumulator = SitesAccumulator::default();
+        let accumulator = add(accumulator, "site1", 1, LINE_1);
+        let accumulator = add(accumulator, "site1", 2, LINE_2);
+        let accumulator = add(accumulator, "site1", 3, "no es json");
+
+        let site = accumulator.sites.get("site1").unwrap();
+        assert_eq!(site.questions, 2);
+        assert_eq!(site.words, 4);
+        assert_eq!(site.tags.get("a").unwrap().questions, 1);
+        assert_eq!(site.tags.get("a").unwrap().words, 3);
+        assert_eq!(site.tags.get("b").unwrap().questions, 2);
+        assert_eq!(site.tags.get("b").unwrap().words, 4);
+        assert_eq!(accumulator.skipped_lines, 1);
+        assert_eq!(accumulator.errors.get("site1").unwrap().parse_errors, 1);
+    }
+
+    #[test]
+    fn merges_accumulators() {
+        let accumulator1 = add(SitesAccumulator::default(), "site1", 1, LINE_1);
+        let accumulator2 = add(SitesAccumulator::default(), "site1", 2, LINE_2);
+        let accumulator2 = add(accumulator2, "site2", 1, LINE_2);
+
+        let processed_sites = accumulator1.merge(accumulator2).into_processed_sites();
+
+        let site1 = processed_sites.sites.get("site1").unwrap();
+        assert_eq!(site1.questions, 2);
+        assert_eq!(site1.words, 4);
+        assert_eq!(processed_sites.sites.get("site2").unwrap().questions, 1);
+        assert_eq!(processed_sites.tags.get("a").unwrap().questions, 1);
+        assert_eq!(processed_sites.tags.get("b").unwrap().questions, 3);
+        assert_eq!(processed_sites.tags.get("b").unwrap().words, 5);
+    }
+}
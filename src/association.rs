@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use crate::{cooccurrence::TagPairs, processed_sites::ProcessedSites, tag::Tag};
+
+/// Medida de asociación entre dos tags `a` y `b`, a partir de la cantidad de preguntas `n`, las preguntas de cada tag `n_a` y `n_b`, y las preguntas en las que aparecen juntos `n_ab`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AssociationMeasure {
+    /// Información mutua puntual: `ln(n_ab * n / (n_a * n_b))`.
+    #[default]
+    Pmi,
+    /// Lift: `n_ab * n / (n_a * n_b)`. Ordena igual que PMI, pero en escala lineal.
+    Lift,
+    /// Similitud de Jaccard entre las preguntas de cada tag: `n_ab / (n_a + n_b - n_ab)`.
+    Jaccard,
+}
+
+impl AssociationMeasure {
+    /// Calcula la medida para un par de tags.
+    pub fn value(
+        &self,
+        questions: usize,
+        questions_a: usize,
+        questions_b: usize,
+        pair: usize,
+    ) -> f64 {
+        let lift = (pair * questions) as f64 / (questions_a * questions_b) as f64;
+        match self {
+            AssociationMeasure::Pmi => lift.ln(),
+            AssociationMeasure::Lift => lift,
+            AssociationMeasure::Jaccard => pair as f64 / (questions_a + questions_b - pair) as f64,
+        }
+    }
+}
+
+/// Tag asociado a otro, con la cantidad de preguntas en las que aparecen juntos y el valor de la medida.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Association {
+    pub tag: String,
+    pub pair_questions: usize,
+    pub score: f64,
+}
+
+impl ProcessedSites {
+    /// Devuelve los `top_k` tags más asociados a `tag` según la medida indicada, de mayor a menor, en el Site indicado o en todos los Sites.
+    /// Devuelve None si no se contaron los pares de tags o si el Site no existe. Si el tag no aparece junto a ningún otro, devuelve un vector vacío.
+    pub fn associated_tags(
+        &self,
+        tag: &str,
+        measure: AssociationMeasure,
+        site: Option<&str>,
+        top_k: usize,
+    ) -> Option<Vec<Association>> {
+        let (pairs, tags, questions) = match site {
+            None => (
+                self.cooccurrence.as_ref()?,
+                &self.tags,
+                self.sites.values().map(|site| site.questions).sum(),
+            ),
+            Some(site_name) => {
+                let site = self.sites.get(site_name)?;
+                (site.cooccurrence.as_ref()?, &site.tags, site.questions)
+            }
+        };
+        Some(rank_associations(
+            pairs, tags, questions, tag, measure, top_k,
+        ))
+    }
+}
+
+/// Ordena los vecinos de `tag` según la medida. Los empates se ordenan por nombre.
+fn rank_associations(
+    pairs: &TagPairs,
+    tags: &HashMap<String, Tag>,
+    questions: usize,
+    tag: &str,
+    measure: AssociationMeasure,
+    top_k: usize,
+) -> Vec<Association> {
+    let Some(tag_questions) = tags.get(tag).map(|t| t.questions) else {
+        return vec![];
+    };
+    let mut associations: Vec<Association> = pairs
+        .neighbors(tag)
+        .into_iter()
+        .filter_map(|(neighbor, pair_questions)| {
+            let neighbor_questions = tags.get(neighbor)?.questions;
+            Some(Association {
+                tag: neighbor.to_string(),
+                pair_questions,
+                score: measure.value(questions, tag_questions, neighbor_questions, pair_questions),
+            })
+        })
+        .collect();
+    associations.sort_by(|association_1, association_2| {
+        association_2
+            .score
+            .total_cmp(&association_1.score)
+            .then(association_1.tag.cmp(&association_2.tag))
+    });
+    associations.truncate(top_k);
+    associations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        options::ProcessOptions, processed_sites::PADRON, question::Question, site::Site,
+        words::WordCounts,
+    };
+
+    fn add_questions(site: &mut Site, times: usize, tags: &[&str]) {
+        let question: Question = WordCounts::new(1, 1).into();
+        (0..times).for_each(|_| {
+            site.add_question(&question, tags.iter().map(|tag| tag.to_string()).collect())
+        });
+    }
+
+    /// Un Site con 100 preguntas: `rust` aparece 10 veces con `cargo` (que solo aparece con `rust`) y 10 con `linux`, que es popular (50 preguntas).
+    fn processed_sites() -> ProcessedSites {
+        let options = ProcessOptions {
+            cooccurrence: true,
+            ..ProcessOptions::default()
+        };
+        let mut site = Site::empty(&options);
+        add_questions(&mut site, 10, &["rust", "cargo"]);
+        add_questions(&mut site, 10, &["rust", "linux"]);
+        add_questions(&mut site, 40, &["linux"]);
+        add_questions(&mut site, 40, &["other"]);
+        let mut processed_sites = ProcessedSites::new(
+            PADRON.to_string(),
+            HashMap::from([("site".to_string(), site.clone())]),
+            site.tags.clone(),
+            HashMap::new(),
+        );
+        processed_sites.cooccurrence = site.cooccurrence;
+        processed_sites
+    }
+
+    #[test]
+    fn computes_each_measure() {
+        assert_eq!(AssociationMeasure::Lift.value(100, 20, 10, 10), 5.0);
+        assert_eq!(AssociationMeasure::Pmi.value(100, 20, 10, 10), 5.0_f64.ln());
+        assert_eq!(AssociationMeasure::Jaccard.value(100, 20, 10, 10), 0.5);
+    }
+
+    #[test]
+    fn ranks_specific_tags_above_popular_ones() {
+        let processed_sites = processed_sites();
+
+        for measure in [
+            AssociationMeasure::Pmi,
+            AssociationMeasure::Lift,
+            AssociationMeasure::Jaccard,
+        ] {
+            let associations = processed_sites
+                .associated_tags("rust", measure, None, 10)
+                .unwrap();
+            let tags: Vec<&str> = associations.iter().map(|a| a.tag.as_str()).collect();
+            assert_eq!(tags, ["cargo", "linux"]);
+            assert_eq!(associations[0].pair_questions, 10);
+        }
+        let associations = processed_sites
+            .associated_tags("linux", AssociationMeasure::Lift, Some("site"), 1)
+            .unwrap();
+        assert_eq!(associations.len(), 1);
+        assert_eq!(associations[0].tag, "rust");
+        assert_eq!(associations[0].score, 10.0 * 100.0 / (50.0 * 20.0));
+    }
+
+    #[test]
+    fn missing_pairs_or_tags() {
+        let mut processed_sites = processed_sites();
+
+        assert_eq!(
+            processed_sites.associated_tags("other", AssociationMeasure::Pmi, None, 10),
+            Some(vec![])
+        );
+        assert_eq!(
+            processed_sites.associated_tags("missing", AssociationMeasure::Pmi, None, 10),
+            Some(vec![])
+        );
+        assert_eq!(
+            processed_sites.associated_tags("rust", AssociationMeasure::Pmi, Some("missing"), 10),
+            None
+        );
+        processed_sites.cooccurrence = None;
+        assert_eq!(
+            processed_sites.associated_tags("rust", AssociationMeasure::Pmi, None, 10),
+            None
+        );
+    }
+}
@@ -0,0 +1,643 @@
+use std::sync::Arc;
+
+use crate::{
+    chatty::ChattyConfig,
+    dedup::DedupScope,
+    distinctive_terms::DistinctiveTermsConfig,
+    error::{ErrorPolicy, ProcessingError},
+    near_duplicates::{find_near_duplicates, NearDuplicatesConfig},
+    options::ProcessOptions,
+    processed_sites::{get_json_paths, process_sites, ProcessedSites, PADRON},
+    readability::ReadabilityConfig,
+    similarity::{SimilarityMeasure, DEFAULT_NEAREST_SITES},
+    tokenizer::Tokenizer,
+    top_words::TopWordsConfig,
+    vocabulary::VocabularyMode,
+};
+
+/// Builder para procesar un conjunto de entradas y obtener el ProcessedSites resultante, con los chattys ya calculados.
+///
+/// ```no_run
+/// use tp1alejofabregas::{ErrorPolicy, ProcessedSites};
+///
+/// let processed_sites = ProcessedSites::builder()
+///     .input("data")
+///     .recursive(true)
+///     .threads(4)
+///     .top_n(20)
+///     .error_policy(ErrorPolicy::SkipAndLog)
+///     .build()
+///     .expect("No se pudieron procesar las entradas");
+/// println!("{}", processed_sites.sites.len());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProcessedSitesBuilder {
+    inputs: Vec<String>,
+    recursive: bool,
+    threads: Option<usize>,
+    chatty: ChattyConfig,
+    padron: String,
+    options: ProcessOptions,
+    error_report: bool,
+    similarity: Option<SimilarityMeasure>,
+    nearest_sites: usize,
+    distinctive_terms: Option<DistinctiveTermsConfig>,
+    near_duplicates: Option<NearDuplicatesConfig>,
+    readability: Option<ReadabilityConfig>,
+}
+
+impl Default for ProcessedSitesBuilder {
+    fn default() -> Self {
+        ProcessedSitesBuilder {
+            inputs: vec![],
+            recursive: false,
+            threads: None,
+            chatty: ChattyConfig::default(),
+            padron: PADRON.to_string(),
+            options: ProcessOptions::default(),
+            error_report: false,
+            similarity: None,
+            nearest_sites: DEFAULT_NEAREST_SITES,
+            distinctive_terms: None,
+            near_duplicates: None,
+            readability: None,
+        }
+    }
+}
+
+impl ProcessedSitesBuilder {
+    /// Crea un builder sin entradas y con los valores por defecto.
+    pub fn new() -> ProcessedSitesBuilder {
+        ProcessedSitesBuilder::default()
+    }
+
+    /// Agrega una entrada a procesar: un directorio, un archivo o un patrón glob.
+    pub fn input(mut self, input: impl Into<String>) -> Self {
+        self.inputs.push(input.into());
+        self
+    }
+
+    /// Agrega varias entradas a procesar.
+    pub fn inputs<I, S>(mut self, inputs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.inputs.extend(inputs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Indica si los directorios de entrada se recorren recursivamente.
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Procesa en un pool propio con la cantidad de threads indicada, en vez de usar el pool de Rayon actual.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Cantidad de elementos de todas las listas de chattys.
+    pub fn top_n(mut self, top_n: usize) -> Self {
+        self.chatty.chatty_sites = top_n;
+        self.chatty.chatty_tags = top_n;
+        self.chatty.site_chatty_tags = top_n;
+        self
+    }
+
+    /// Cantidad mínima de preguntas que tiene que tener un Site o Tag para aparecer en los rankings.
+    pub fn min_questions(mut self, min_questions: usize) -> Self {
+        self.chatty.thresholds.min_questions = min_questions;
+        self
+    }
+
+    /// Suaviza los valores por pregunta de los rankings hacia el valor de la población, con el peso indicado.
+    pub fn smoothing(mut self, weight: f64) -> Self {
+        self.chatty.thresholds.smoothing = Some(weight);
+        self
+    }
+
+    /// Configuración completa de los rankings: métrica, tamaño de cada lista chatty y terse, y umbrales.
+    pub fn chatty_config(mut self, chatty: ChattyConfig) -> Self {
+        self.chatty = chatty;
+        self
+    }
+
+    /// Padrón que se incluye como identificador en el resultado.
+    pub fn padron(mut self, padron: impl Into<String>) -> Self {
+        self.padron = padron.into();
+        self
+    }
+
+    /// Qué hacer con las líneas que no se pueden leer o parsear. Por defecto se corta el procesamiento.
+    pub fn error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+        self.options.error_policy = error_policy;
+        self
+    }
+
+    /// Tamaño aproximado en bytes de los rangos en los que se dividen los archivos sin comprimir para procesarlos en paralelo.
+    pub fn chunk_size(mut self, chunk_size: u64) -> Self {
+        self.options.chunk_size = chunk_size;
+        self
+    }
+
+    /// Si es true, cada Site y Tag del resultado incluye estadísticas detalladas de palabras por pregunta.
+    pub fn length_stats(mut self, length_stats: bool) -> Self {
+        self.options.length_stats = length_stats;
+        self
+    }
+
+    /// Forma de contar las palabras de cada text. Por defecto se separan por espacios.
+    pub fn tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.options.tokenizer = tokenizer;
+        self
+    }
+
+    /// Si es true, cuenta los pares de tags que aparecen juntos, por Site y en total.
+    pub fn cooccurrence(mut self, cooccurrence: bool) -> Self {
+        self.options.cooccurrence = cooccurrence;
+        self
+    }
+
+    /// Cantidad mínima de preguntas en las que tiene que aparecer un par de tags para quedar en el resultado.
+    pub fn min_pair_count(mut self, min_pair_count: usize) -> Self {
+        self.options.min_pair_count = min_pair_count;
+        self
+    }
+
+    /// Cuenta las palabras distintas de cada Site y Tag en el modo indicado: estimadas con un sketch o exactas.
+    pub fn vocabulary(mut self, mode: VocabularyMode) -> Self {
+        self.options.vocabulary = Some(mode);
+        self
+    }
+
+    /// Cuenta las palabras más frecuentes de cada Site y Tag, sin las stopwords, según la configuración.
+    pub fn top_words(mut self, config: TopWordsConfig) -> Self {
+        self.options.top_words = Some(config);
+        self
+    }
+
+    /// Indica si el resultado incluye la sección `errors` con los errores encontrados en cada Site.
+    pub fn error_report(mut self, error_report: bool) -> Self {
+        self.error_report = error_report;
+        self
+    }
+
+    /// Calcula la similitud entre todos los pares de Sites y lista en cada Site los más parecidos según la medida indicada.
+    pub fn site_similarity(mut self, measure: SimilarityMeasure) -> Self {
+        self.similarity = Some(measure);
+        self
+    }
+
+    /// Cantidad de Sites más parecidos que se listan en cada Site, si se calcula la similitud.
+    pub fn nearest_sites(mut self, nearest_sites: usize) -> Self {
+        self.nearest_sites = nearest_sites;
+        self
+    }
+
+    /// Hace una segunda pasada por las entradas para guardar en cada Tag (o Site) sus términos más distintivos por TF-IDF según la configuración.
+    pub fn distinctive_terms(mut self, config: DistinctiveTermsConfig) -> Self {
+        self.distinctive_terms = Some(config);
+        self
+    }
+
+    /// Cuenta las oraciones, palabras y sílabas de cada Site y Tag para calcular su legibilidad, y genera los rankings de los más difíciles de leer según la configuración.
+    pub fn readability(mut self, config: ReadabilityConfig) -> Self {
+        self.options.readability = true;
+        self.readability = Some(config);
+        self
+    }
+
+    /// Si es true, detecta el idioma de cada pregunta, desglosa cada Site por idioma y mide las preguntas en idiomas CJK en caracteres en vez de palabras.
+    pub fn languages(mut self, languages: bool) -> Self {
+        self.options.languages = languages;
+        self
+    }
+
+    /// Descarta las preguntas con los mismos texts normalizados que otra ya vista, en el mismo Site o en cualquiera según `scope`, y cuenta en cada Site cuántas se descartaron.
+    pub fn dedup(mut self, scope: DedupScope) -> Self {
+        self.options.dedup = Some(scope);
+        self
+    }
+
+    /// Busca antes del procesamiento las preguntas casi duplicadas con MinHash y LSH, y las incluye en el resultado como clusters.
+    /// Si la configuración pide deduplicar, de cada cluster solo se suma la primera pregunta a los Sites y Tags.
+    pub fn near_duplicates(mut self, config: NearDuplicatesConfig) -> Self {
+        self.near_duplicates = Some(config);
+        self
+    }
+
+    /// Procesa las entradas y devuelve el ProcessedSites con los chattys calculados.
+    pub fn build(self) -> Result<ProcessedSites, ProcessingError> {
+        match self.threads {
+            Some(threads) => rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()?
+                .install(|| self.run()),
+            None => self.run(),
+        }
+    }
+
+    /// Ejecuta el procesamiento en el pool de Rayon actual.
+    fn run(mut self) -> Result<ProcessedSites, ProcessingError> {
+        let json_paths = get_json_paths(&self.inputs, self.recursive)?;
+        let near_duplicates = match &self.near_duplicates {
+            Some(config) => Some(find_near_duplicates(
+                &json_paths,
+                self.options.chunk_size,
+                self.options.tokenizer,
+                config,
+            )?),
+            None => None,
+        };
+        let near_excluded = near_duplicates
+            .as_ref()
+            .filter(|n| n.deduplicated)
+            .map(|near_duplicates| Arc::new(near_duplicates.excluded_questions()));
+        if let Some(excluded) = &near_excluded {
+            self.options.excluded_questions = Some(Arc::clone(excluded));
+        }
+        let mut processed_sites = process_sites(json_paths.clone(), &self.options)?;
+        if let Some(excluded) = &near_excluded {
+            processed_sites.add_removed_near_duplicates(&json_paths, excluded, &self.options);
+        }
+        processed_sites.near_duplicates = near_duplicates;
+        processed_sites.padron = self.padron;
+        if !self.error_report {
+            processed_sites.errors = None;
+        }
+        processed_sites.process_chatty(&self.chatty);
+        if let Some(config) = &self.readability {
+            processed_sites.process_readability(config);
+        }
+        if let Some(measure) = self.similarity {
+            processed_sites.process_similarity(measure, self.nearest_sites);
+        }
+        if let Some(config) = &self.distinctive_terms {
+            processed_sites.process_distinctive_terms(&json_paths, &self.options, config)?;
+        }
+        Ok(processed_sites)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{distinctive_terms::TermsScope, readability::Readability};
+
+    const TEST_DATA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test_data");
+
+    #[test]
+    fn builds_processed_sites_from_inputs() {
+        let processed_sites = ProcessedSites::builder()
+            .input(TEST_DATA_PATH)
+            .threads(2)
+            .top_n(2)
+            .padron("123456")
+            .build()
+            .unwrap();
+
+        assert_eq!(processed_sites.padron, "123456");
+        assert_eq!(processed_sites.sites.len(), 3);
+        assert_eq!(processed_sites.totals.get("chatty_sites").unwrap().len(), 2);
+        assert_eq!(processed_sites.totals.get("chatty_tags").unwrap().len(), 2);
+        processed_sites
+            .sites
+            .values()
+            .for_each(|site| assert!(site.chatty_tags.len() <= 2));
+    }
+
+    #[test]
+    fn builds_with_several_inputs() {
+        let processed_sites = ProcessedSitesBuilder::new()
+            .inputs([
+                format!("{}/academia.stackexchange.com.jsonl", TEST_DATA_PATH),
+                format!("{}/anime.stackexchange.com.jsonl", TEST_DATA_PATH),
+            ])
+            .build()
+            .unwrap();
+
+        assert_eq!(processed_sites.sites.len(), 2);
+        assert_eq!(processed_sites.padron, PADRON);
+        assert!(processed_sites
+            .sites
+            .contains_key("academia.stackexchange.com"));
+    }
+
+    #[test]
+    fn thresholds_are_applied_and_recorded() {
+        let processed_sites = ProcessedSites::builder()
+            .input(TEST_DATA_PATH)
+            .min_questions(5)
+            .smoothing(20.0)
+            .build()
+            .unwrap();
+
+        processed_sites.totals["chatty_tags"]
+            .iter()
+            .for_each(|tag| assert!(processed_sites.tags[tag].questions >= 5));
+        assert_eq!(
+            processed_sites.thresholds,
+            Some(crate::ChattyThresholds {
+                min_questions: 5,
+                smoothing: Some(20.0)
+            })
+        );
+    }
+
+    #[test]
+    fn length_stats_are_optional() {
+        let processed_sites = ProcessedSites::builder()
+            .input(TEST_DATA_PATH)
+            .build()
+            .unwrap();
+        assert!(processed_sites
+            .sites
+            .values()
+            .all(|site| site.lengths.summary().is_none()));
+
+        let processed_sites = ProcessedSites::builder()
+            .input(TEST_DATA_PATH)
+            .length_stats(true)
+            .chunk_size(4096)
+            .build()
+            .unwrap();
+        processed_sites.sites.values().for_each(|site| {
+            let summary = site.lengths.summary().unwrap();
+            assert_eq!(summary.count, site.questions as u64);
+            assert!((summary.mean - site.words as f64 / site.questions as f64).abs() < 1e-6);
+            assert!(summary.min as f64 <= summary.p50.unwrap());
+            assert!(summary.p99.unwrap() <= summary.max as f64);
+        });
+        processed_sites.tags.values().for_each(|tag| {
+            assert_eq!(tag.lengths.summary().unwrap().count, tag.questions as u64);
+        });
+    }
+
+    #[test]
+    fn site_similarity_is_optional() {
+        let processed_sites = ProcessedSites::builder()
+            .input(TEST_DATA_PATH)
+            .build()
+            .unwrap();
+        assert_eq!(processed_sites.similarity, None);
+        let json = serde_json::to_value(&processed_sites).unwrap();
+        assert!(json.get("similarity").is_none());
+
+        let processed_sites = ProcessedSites::builder()
+            .input(TEST_DATA_PATH)
+            .threads(2)
+            .site_similarity(SimilarityMeasure::Cosine)
+            .nearest_sites(1)
+            .build()
+            .unwrap();
+        let similarity = processed_sites.similarity.as_ref().unwrap();
+        assert_eq!(similarity.measure, SimilarityMeasure::Cosine);
+        assert_eq!(similarity.sites.len(), 3);
+        processed_sites.sites.iter().for_each(|(name, site)| {
+            assert_eq!(site.nearest_sites.len(), 1);
+            assert_ne!(&site.nearest_sites[0], name);
+            let value = similarity.get(name, name, SimilarityMeasure::Jaccard);
+            assert_eq!(value, Some(1.0));
+        });
+    }
+
+    #[test]
+    fn distinctive_terms_by_scope() {
+        let processed_sites = ProcessedSites::builder()
+            .input(TEST_DATA_PATH)
+            .threads(2)
+            .distinctive_terms(DistinctiveTermsConfig::new(3, TermsScope::Tag))
+            .build()
+            .unwrap();
+        let tags_with_terms = processed_sites
+            .sites
+            .values()
+            .flat_map(|site| site.tags.values())
+            .filter(|tag| !tag.distinctive_terms.is_empty())
+            .count();
+        assert!(tags_with_terms > 0);
+        processed_sites.sites.values().for_each(|site| {
+            assert!(site.distinctive_terms.is_empty());
+            site.tags.values().for_each(|tag| {
+                assert!(tag.distinctive_terms.len() <= 3);
+                assert!(tag
+                    .distinctive_terms
+                    .windows(2)
+                    .all(|terms| terms[0].score >= terms[1].score));
+            });
+        });
+        assert!(processed_sites
+            .tags
+            .values()
+            .all(|tag| tag.distinctive_terms.is_empty()));
+
+        let processed_sites = ProcessedSites::builder()
+            .input(TEST_DATA_PATH)
+            .distinctive_terms(DistinctiveTermsConfig::new(3, TermsScope::Site))
+            .build()
+            .unwrap();
+        processed_sites.sites.values().for_each(|site| {
+            assert_eq!(site.distinctive_terms.len(), 3);
+            assert!(site
+                .tags
+                .values()
+                .all(|tag| tag.distinctive_terms.is_empty()));
+        });
+    }
+
+    #[test]
+    fn dedup_keeps_the_same_copy_in_every_pass() {
+        let dir = tempfile::tempdir().unwrap();
+        let line = |text: &str, tag: &str| {
+            format!("{{\"texts\": [\"{}\"], \"tags\": [\"{}\"]}}", text, tag)
+        };
+        std::fs::write(
+            dir.path().join("a.stackexchange.com.jsonl"),
+            [
+                line("rust borrow checker", "rust"),
+                line("python list comprehension", "python"),
+                line("rust lifetimes question", "rust"),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("b.stackexchange.com.jsonl"),
+            [
+                line("RUST borrow  checker", "golang"),
+                line("go channels select", "golang"),
+                line("python decorators explained", "python"),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        for threads in [1, 2, 4] {
+            let processed_sites = ProcessedSites::builder()
+                .input(dir.path().to_str().unwrap())
+                .threads(threads)
+                .chunk_size(16)
+                .dedup(DedupScope::Global)
+                .distinctive_terms(DistinctiveTermsConfig {
+                    min_questions: 1,
+                    ..DistinctiveTermsConfig::new(5, TermsScope::Tag)
+                })
+                .build()
+                .unwrap();
+
+            let site_b = &processed_sites.sites["b.stackexchange.com"];
+            assert_eq!((site_b.questions, site_b.removed_duplicates), (2, Some(1)));
+            assert_eq!(processed_sites.tags["rust"].questions, 2);
+            assert_eq!(processed_sites.tags["golang"].questions, 1);
+            let mut terms: Vec<&str> = site_b.tags["golang"]
+                .distinctive_terms
+                .iter()
+                .map(|term| term.term.as_str())
+                .collect();
+            terms.sort_unstable();
+            assert_eq!(terms, ["channels", "go", "select"]);
+        }
+    }
+
+    #[test]
+    fn near_duplicates_can_be_deduplicated() {
+        let dir = tempfile::tempdir().unwrap();
+        let line =
+            "{\"texts\": [\"how do i read a file line by line in rust\"], \"tags\": [\"rust\"]}";
+        let other =
+            "{\"texts\": [\"what is the best anime of the season\"], \"tags\": [\"anime\"]}";
+        std::fs::write(
+            dir.path().join("a.stackexchange.com.jsonl"),
+            [line, other, line].join("\n"),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("b.stackexchange.com.jsonl"), line).unwrap();
+        let builder = ProcessedSites::builder().input(dir.path().to_str().unwrap());
+
+        let processed_sites = builder.clone().build().unwrap();
+        assert!(processed_sites.near_duplicates.is_none());
+
+        let processed_sites = builder
+            .clone()
+            .near_duplicates(NearDuplicatesConfig::default())
+            .build()
+            .unwrap();
+        let near_duplicates = processed_sites.near_duplicates.as_ref().unwrap();
+        assert_eq!(near_duplicates.clusters.len(), 1);
+        assert_eq!(near_duplicates.duplicate_questions, 2);
+        assert!(!near_duplicates.deduplicated);
+        assert_eq!(processed_sites.sites["a.stackexchange.com"].questions, 3);
+        assert_eq!(processed_sites.tags["rust"].questions, 3);
+
+        let processed_sites = builder
+            .near_duplicates(NearDuplicatesConfig {
+                dedup: true,
+                ..NearDuplicatesConfig::default()
+            })
+            .build()
+            .unwrap();
+        assert!(
+            processed_sites
+                .near_duplicates
+                .as_ref()
+                .unwrap()
+                .deduplicated
+        );
+        let (site_a, site_b) = (
+            &processed_sites.sites["a.stackexchange.com"],
+            &processed_sites.sites["b.stackexchange.com"],
+        );
+        assert_eq!(
+            (site_a.questions, site_a.removed_near_duplicates),
+            (2, Some(1))
+        );
+        assert_eq!(
+            (site_b.questions, site_b.removed_near_duplicates),
+            (0, Some(1))
+        );
+        assert!(
+            !processed_sites.totals["chatty_sites"].contains(&"b.stackexchange.com".to_string())
+        );
+        assert_eq!(processed_sites.tags["rust"].questions, 1);
+        assert_eq!(processed_sites.tags["anime"].questions, 1);
+    }
+
+    #[test]
+    fn readability_rankings_like_chatty() {
+        let processed_sites = ProcessedSites::builder()
+            .input(TEST_DATA_PATH)
+            .build()
+            .unwrap();
+        assert!(!processed_sites.totals.contains_key("hardest_sites"));
+        assert!(processed_sites
+            .sites
+            .values()
+            .all(|site| site.readability.is_none() && site.hardest_tags.is_empty()));
+
+        let processed_sites = ProcessedSites::builder()
+            .input(TEST_DATA_PATH)
+            .threads(2)
+            .readability(ReadabilityConfig {
+                hardest_tags: 0,
+                ..ReadabilityConfig::with_top_n(2)
+            })
+            .build()
+            .unwrap();
+        let hardest_sites = &processed_sites.totals["hardest_sites"];
+        assert_eq!(hardest_sites.len(), 2);
+        let grade = |name: &String| {
+            processed_sites.sites[name]
+                .readability
+                .unwrap()
+                .flesch_kincaid_grade()
+        };
+        assert!(grade(&hardest_sites[0]) >= grade(&hardest_sites[1]));
+        assert!(!processed_sites.totals.contains_key("hardest_tags"));
+
+        let mut total = Readability::default();
+        processed_sites.sites.values().for_each(|site| {
+            let readability = site.readability.unwrap();
+            assert!(readability.sentences > 0 && readability.syllables >= readability.words);
+            assert_eq!(site.hardest_tags.len(), 2);
+            total.add(&readability);
+        });
+        let mut from_tags = Readability::default();
+        processed_sites
+            .tags
+            .values()
+            .for_each(|tag| from_tags.add(&tag.readability.unwrap()));
+        // Las preguntas con varios tags suman a cada uno
+        assert!(from_tags.words >= total.words);
+    }
+
+    #[test]
+    fn error_report_is_optional() {
+        let builder = ProcessedSitesBuilder::new()
+            .input(TEST_DATA_PATH)
+            .error_policy(ErrorPolicy::SkipAndCount);
+
+        assert!(builder.clone().build().unwrap().errors.is_none());
+        assert_eq!(
+            builder
+                .error_report(true)
+                .build()
+                .unwrap()
+                .errors
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn build_returns_errors() {
+        let result = ProcessedSitesBuilder::new()
+            .input(format!("{}/no_existe", TEST_DATA_PATH))
+            .build();
+
+        assert!(matches!(result, Err(ProcessingError::Directory { .. })));
+    }
+}
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{processed_sites::DEFAULT_TOP_N, sketch::QuantileSketch, words::WordCounts};
+
+/// Métrica con la que se ordenan los Sites y Tags en los rankings de chattys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChattyMetric {
+    /// Promedio de palabras por pregunta (words / questions).
+    #[default]
+    MeanWords,
+    /// Mediana estimada de palabras por pregunta. Si no hay sketch de palabras (por ejemplo al construir Tags a mano), se usa el promedio.
+    MedianWords,
+    /// Promedio de palabras del título por pregunta (title_words / questions).
+    MeanTitleWords,
+    /// Promedio de palabras del cuerpo por pregunta (body_words / questions).
+    MeanBodyWords,
+    /// Total de palabras.
+    TotalWords,
+    /// Cantidad de preguntas.
+    Questions,
+}
+
+impl ChattyMetric {
+    /// Calcula la métrica a partir de las preguntas, las palabras totales, las de títulos y cuerpos, y el sketch de palabras por pregunta.
+    /// Las métricas por pregunta valen 0 si no hay preguntas.
+    pub fn value(
+        &self,
+        questions: usize,
+        words: usize,
+        title_body: WordCounts,
+        lengths: &QuantileSketch,
+    ) -> f64 {
+        let per_question = |count: usize| {
+            if questions == 0 {
+                0.0
+            } else {
+                count as f64 / questions as f64
+            }
+        };
+        match self {
+            ChattyMetric::MeanWords => per_question(words),
+            ChattyMetric::MedianWords => lengths.quantile(0.5).unwrap_or(per_question(words)),
+            ChattyMetric::MeanTitleWords => per_question(title_body.title),
+            ChattyMetric::MeanBodyWords => per_question(title_body.body),
+            ChattyMetric::TotalWords => words as f64,
+            ChattyMetric::Questions => questions as f64,
+        }
+    }
+
+    /// Indica si la métrica es un valor por pregunta, y por lo tanto se puede suavizar hacia el de toda la población.
+    pub fn is_per_question(&self) -> bool {
+        !matches!(self, ChattyMetric::TotalWords | ChattyMetric::Questions)
+    }
+}
+
+/// Umbrales que se aplican a los rankings, para que un Tag o Site con muy pocas preguntas no quede primero.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct ChattyThresholds {
+    /// Cantidad mínima de preguntas que tiene que tener un Site o Tag para aparecer en los rankings.
+    pub min_questions: usize,
+    /// Peso `m` del suavizado bayesiano: el valor de la métrica se reemplaza por `(n * valor + m * previo) / (n + m)`, donde `n` son las preguntas y `previo` el valor de toda la población.
+    /// Solo se aplica a las métricas por pregunta.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smoothing: Option<f64>,
+}
+
+impl ChattyThresholds {
+    /// Indica si hay algún umbral que modifique los rankings.
+    pub fn is_applied(&self) -> bool {
+        self.min_questions > 1 || self.smoothing.is_some()
+    }
+
+    /// Valor con el que se ordena un Site o Tag con `questions` preguntas y valor `value` de la métrica, suavizado hacia `prior` si corresponde.
+    /// Devuelve None si no alcanza la cantidad mínima de preguntas o si no tiene ninguna, como un Site cuyas preguntas se descartaron todas por repetidas.
+    pub fn score(
+        &self,
+        metric: ChattyMetric,
+        questions: usize,
+        value: f64,
+        prior: f64,
+    ) -> Option<f64> {
+        if questions == 0 || questions < self.min_questions {
+            return None;
+        }
+        match self.smoothing {
+            Some(weight) if metric.is_per_question() => {
+                Some((questions as f64 * value + weight * prior) / (questions as f64 + weight))
+            }
+            _ => Some(value),
+        }
+    }
+}
+
+/// Configuración de los rankings: la métrica, y cuántos elementos tiene cada lista.
+/// Las listas chatty tienen los de mayor valor de la métrica y las terse los de menor valor. Una lista de tamaño 0 no se genera.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChattyConfig {
+    pub metric: ChattyMetric,
+    pub chatty_sites: usize,
+    pub chatty_tags: usize,
+    pub site_chatty_tags: usize,
+    pub terse_sites: usize,
+    pub terse_tags: usize,
+    pub site_terse_tags: usize,
+    pub thresholds: ChattyThresholds,
+}
+
+impl ChattyConfig {
+    /// Crea una configuración con la métrica por defecto, `top_n` elementos en cada lista chatty y sin listas terse.
+    pub fn with_top_n(top_n: usize) -> ChattyConfig {
+        ChattyConfig {
+            metric: ChattyMetric::default(),
+            chatty_sites: top_n,
+            chatty_tags: top_n,
+            site_chatty_tags: top_n,
+            terse_sites: 0,
+            terse_tags: 0,
+            site_terse_tags: 0,
+            thresholds: ChattyThresholds::default(),
+        }
+    }
+}
+
+impl Default for ChattyConfig {
+    fn default() -> Self {
+        ChattyConfig::with_top_n(DEFAULT_TOP_N)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_each_metric() {
+        let mut lengths = QuantileSketch::default();
+        [1, 2, 30].iter().for_each(|words| lengths.add(*words));
+
+        let title_body = WordCounts::new(9, 24);
+
+        assert_eq!(
+            ChattyMetric::MeanWords.value(3, 33, title_body, &lengths),
+            11.0
+        );
+        assert!((ChattyMetric::MedianWords.value(3, 33, title_body, &lengths) - 2.0).abs() <= 0.02);
+        assert_eq!(
+            ChattyMetric::MeanTitleWords.value(3, 33, title_body, &lengths),
+            3.0
+        );
+        assert_eq!(
+            ChattyMetric::MeanBodyWords.value(3, 33, title_body, &lengths),
+            8.0
+        );
+        assert_eq!(
+            ChattyMetric::TotalWords.value(3, 33, title_body, &lengths),
+            33.0
+        );
+        assert_eq!(
+            ChattyMetric::Questions.value(3, 33, title_body, &lengths),
+            3.0
+        );
+    }
+
+    #[test]
+    fn thresholds_filter_and_smooth() {
+        let thresholds = ChattyThresholds {
+            min_questions: 2,
+            smoothing: Some(3.0),
+        };
+
+        assert_eq!(
+            thresholds.score(ChattyMetric::MeanWords, 1, 2000.0, 100.0),
+            None
+        );
+        assert_eq!(
+            thresholds.score(ChattyMetric::MeanWords, 2, 200.0, 100.0),
+            Some(140.0)
+        );
+        assert_eq!(
+            thresholds.score(ChattyMetric::Questions, 2, 2.0, 100.0),
+            Some(2.0)
+        );
+        assert!(thresholds.is_applied());
+        assert!(!ChattyThresholds::default().is_applied());
+    }
+
+    #[test]
+    fn median_falls_back_to_mean_without_lengths() {
+        assert_eq!(
+            ChattyMetric::MedianWords.value(
+                2,
+                10,
+                WordCounts::default(),
+                &QuantileSketch::default()
+            ),
+            5.0
+        );
+    }
+}
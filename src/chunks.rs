@@ -0,0 +1,223 @@
+use std::{
+    fs::{metadata, File},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
+};
+
+use crate::{
+    error::ProcessingError,
+    input::{open_reader, Compression},
+};
+
+/// Tamaño aproximado por defecto, en bytes, de los rangos en los que se divide cada archivo.
+pub const DEFAULT_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Unidad de trabajo del procesamiento: un rango de bytes alineado a líneas de un archivo sin comprimir, o un archivo comprimido completo, que solo se puede leer como stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// Índice del archivo en el vector de paths.
+    pub file: usize,
+    /// Rango de bytes `[start, end)` del archivo, o None si es un stream comprimido.
+    pub range: Option<(u64, u64)>,
+    /// Número de la primera línea del rango dentro del archivo (empezando en 1).
+    pub first_line: usize,
+    /// Tamaño en bytes del rango, o del archivo comprimido. Se usa para ordenar el trabajo.
+    pub size: u64,
+}
+
+impl Chunk {
+    /// Abre el chunk y devuelve un reader que lee solamente sus líneas.
+    pub fn open(&self, path: &Path) -> io::Result<Box<dyn BufRead + Send>> {
+        match self.range {
+            None => open_reader(path),
+            Some((start, end)) => {
+                let mut file = File::open(path)?;
+                file.seek(SeekFrom::Start(start))?;
+                Ok(Box::new(BufReader::new(file.take(end - start))))
+            }
+        }
+    }
+}
+
+/// Divide los archivos en chunks de aproximadamente `chunk_size` bytes, para que varios threads puedan procesar un mismo archivo a la vez.
+/// Los archivos comprimidos no se dividen. Los chunks se devuelven ordenados de mayor a menor, para empezar por el trabajo más largo.
+pub fn split_files(json_paths: &[PathBuf], chunk_size: u64) -> Result<Vec<Chunk>, ProcessingError> {
+    let files_chunks: Vec<Vec<Chunk>> = json_paths
+        .par_iter()
+        .enumerate()
+        .map(|(file, path)| {
+            split_file(file, path, chunk_size).map_err(|source| ProcessingError::Io {
+                path: path.to_path_buf(),
+                line: None,
+                source,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    let mut chunks: Vec<Chunk> = files_chunks.into_iter().flatten().collect();
+    chunks.sort_by(|chunk_1, chunk_2| {
+        chunk_2
+            .size
+            .cmp(&chunk_1.size)
+            .then(chunk_1.file.cmp(&chunk_2.file))
+            .then(chunk_1.first_line.cmp(&chunk_2.first_line))
+    });
+    Ok(chunks)
+}
+
+/// Divide un archivo en rangos alineados al comienzo de una línea, y calcula en paralelo el número de la primera línea de cada uno.
+fn split_file(file: usize, path: &Path, chunk_size: u64) -> io::Result<Vec<Chunk>> {
+    let size = metadata(path)?.len();
+    if Compression::from_path(path) != Compression::None {
+        return Ok(vec![Chunk {
+            file,
+            range: None,
+            first_line: 1,
+            size,
+        }]);
+    }
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut boundaries = vec![0];
+    let mut next = chunk_size.max(1);
+    while next < size {
+        // Se busca desde el byte anterior, para que una línea que empieza justo en `next` quede en el rango siguiente
+        reader.seek(SeekFrom::Start(next - 1))?;
+        let boundary = next - 1 + reader.skip_until(b'\n')? as u64;
+        if boundary >= size {
+            break;
+        }
+        boundaries.push(boundary);
+        next = boundary + chunk_size.max(1);
+    }
+    boundaries.push(size);
+    let ranges: Vec<(u64, u64)> = boundaries.windows(2).map(|w| (w[0], w[1])).collect();
+
+    let newlines: Vec<usize> = ranges
+        .clone()
+        .into_par_iter()
+        .map(|(start, end)| count_newlines(path, start, end))
+        .collect::<io::Result<_>>()?;
+    let mut first_line = 1;
+    Ok(ranges
+        .into_iter()
+        .zip(newlines)
+        .map(|((start, end), newlines)| {
+            let chunk = Chunk {
+                file,
+                range: Some((start, end)),
+                first_line,
+                size: end - start,
+            };
+            first_line += newlines;
+            chunk
+        })
+        .collect())
+}
+
+/// Cuenta los saltos de línea del rango `[start, end)` del archivo.
+fn count_newlines(path: &Path, start: u64, end: u64) -> io::Result<usize> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut reader = BufReader::new(file.take(end - start));
+    let mut newlines = 0;
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(newlines);
+        }
+        newlines += buffer.iter().filter(|&&b| b == b'\n').count();
+        let length = buffer.len();
+        reader.consume(length);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_are_aligned_to_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("site.jsonl");
+        std::fs::write(&path, "aaaa\nbb\ncccccc\nd\neeeee").unwrap();
+
+        let mut chunks = split_files(std::slice::from_ref(&path), 3).unwrap();
+        chunks.sort_by_key(|chunk| chunk.first_line);
+
+        let lines: Vec<(usize, Vec<String>)> = chunks
+            .iter()
+            .map(|chunk| {
+                (
+                    chunk.first_line,
+                    chunk
+                        .open(&path)
+                        .unwrap()
+                        .lines()
+                        .map_while(Result::ok)
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (1, vec!["aaaa".to_string()]),
+                (2, vec!["bb".to_string()]),
+                (3, vec!["cccccc".to_string()]),
+                (4, vec!["d".to_string(), "eeeee".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn large_chunk_covers_whole_file() {
+        let path = PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_data/anime.stackexchange.com.jsonl"
+        ));
+        let size = metadata(&path).unwrap().len();
+
+        let chunks = split_files(&[path], DEFAULT_CHUNK_SIZE).unwrap();
+
+        assert_eq!(
+            chunks,
+            [Chunk {
+                file: 0,
+                range: Some((0, size)),
+                first_line: 1,
+                size
+            }]
+        );
+    }
+
+    #[test]
+    fn compressed_files_are_not_split() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("site.jsonl.zst");
+        std::fs::write(&path, zstd::encode_all("a\nb\nc\n".as_bytes(), 0).unwrap()).unwrap();
+
+        let chunks = split_files(std::slice::from_ref(&path), 1).unwrap();
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].range, None);
+        assert_eq!(chunks[0].open(&path).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn chunks_are_sorted_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let small_path = dir.path().join("small.jsonl");
+        let big_path = dir.path().join("big.jsonl");
+        std::fs::write(&small_path, "a\n").unwrap();
+        std::fs::write(&big_path, "aaaaaaaa\n").unwrap();
+
+        let chunks = split_files(&[small_path, big_path], DEFAULT_CHUNK_SIZE).unwrap();
+
+        assert_eq!(chunks[0].file, 1);
+        assert_eq!(chunks[1].file, 0);
+    }
+}
@@ -0,0 +1,699 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use tp1alejofabregas::{
+    AssociationMeasure, ChattyConfig, ChattyMetric, ChattyThresholds, DedupScope, ErrorPolicy,
+    SimilarityMeasure, TermsScope, Tokenizer, TopWordsMode, VocabularyMode, DEFAULT_CHUNK_SIZE,
+    DEFAULT_DUPLICATE_THRESHOLD, DEFAULT_MIN_TERM_QUESTIONS, DEFAULT_NEAREST_SITES, DEFAULT_TOP_N,
+    PADRON,
+};
+
+/// Procesa los dumps de Stack Exchange y calcula las estadísticas de Sites y Tags.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Subcomandos disponibles.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Procesa las entradas y genera el JSON con los Sites, Tags y chattys.
+    Analyze(Box<AnalyzeArgs>),
+    /// Verifica que todas las líneas de las entradas se puedan parsear, sin procesarlas.
+    Validate(ValidateArgs),
+    /// Compara dos JSON generados por `analyze` y muestra las diferencias.
+    /// De cada Site compara las preguntas, palabras, chatty_tags y contadores (código, idiomas, legibilidad, descartadas y errores); de cada Tag solo las preguntas y palabras; y las listas de totals.
+    Diff(DiffArgs),
+    /// Muestra los tags más asociados a un tag, a partir de los pares de tags de un JSON generado por `analyze --cooccurrence`.
+    Associate(AssociateArgs),
+    /// Mide el tiempo de procesamiento de las entradas con distintas cantidades de threads.
+    Bench(BenchArgs),
+}
+
+/// Parámetros de las entradas a procesar, comunes a los subcomandos que leen los dumps.
+#[derive(Debug, Args)]
+pub struct InputArgs {
+    /// Directorios, archivos o patrones glob con los archivos .jsonl, .jsonl.gz o .jsonl.zst a procesar.
+    #[arg(default_value = "data")]
+    pub inputs: Vec<String>,
+
+    /// Recorre los directorios de entrada recursivamente.
+    #[arg(short, long)]
+    pub recursive: bool,
+}
+
+/// Parámetros del archivo de salida.
+#[derive(Debug, Args)]
+pub struct OutputArgs {
+    /// Archivo donde se escribe la salida. Si no se indica, se escribe por stdout.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Formato de la salida.
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Pretty)]
+    pub format: OutputFormat,
+}
+
+/// Formatos posibles para el JSON de salida.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// JSON indentado.
+    Pretty,
+    /// JSON en una sola línea.
+    Compact,
+}
+
+#[derive(Debug, Args)]
+pub struct AnalyzeArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    /// Cantidad de threads a utilizar. Por defecto se usan los disponibles en el sistema.
+    #[arg(short = 't', long, value_parser = parse_positive)]
+    pub threads: Option<usize>,
+
+    /// Cantidad de elementos de las listas de chattys.
+    #[arg(short = 'n', long, default_value_t = DEFAULT_TOP_N, value_parser = parse_positive)]
+    pub top: usize,
+
+    /// Cantidad de chatty_sites. Si no se indica, se usa `--top`.
+    #[arg(long)]
+    pub top_sites: Option<usize>,
+
+    /// Cantidad de chatty_tags totales. Si no se indica, se usa `--top`.
+    #[arg(long)]
+    pub top_tags: Option<usize>,
+
+    /// Cantidad de chatty_tags de cada Site. Si no se indica, se usa `--top`.
+    #[arg(long)]
+    pub top_site_tags: Option<usize>,
+
+    /// Cantidad de elementos de las listas terse, con los de menor valor de la métrica. Con 0 no se generan.
+    #[arg(short, long, default_value_t = 0)]
+    pub bottom: usize,
+
+    /// Cantidad de terse_sites. Si no se indica, se usa `--bottom`.
+    #[arg(long)]
+    pub bottom_sites: Option<usize>,
+
+    /// Cantidad de terse_tags totales. Si no se indica, se usa `--bottom`.
+    #[arg(long)]
+    pub bottom_tags: Option<usize>,
+
+    /// Cantidad de terse_tags de cada Site. Si no se indica, se usa `--bottom`.
+    #[arg(long)]
+    pub bottom_site_tags: Option<usize>,
+
+    /// Métrica con la que se ordenan los rankings.
+    #[arg(short, long, value_enum, default_value_t = Metric::MeanWords)]
+    pub metric: Metric,
+
+    /// Cantidad mínima de preguntas que tiene que tener un Site o Tag para aparecer en los rankings.
+    #[arg(long, default_value_t = 0)]
+    pub min_questions: usize,
+
+    /// Suaviza las métricas por pregunta hacia el valor de la población, con el peso indicado (en preguntas).
+    #[arg(long, value_parser = parse_weight)]
+    pub smoothing: Option<f64>,
+
+    /// Padrón que se incluye como identificador en la salida.
+    #[arg(short, long, default_value = PADRON)]
+    pub padron: String,
+
+    /// Qué hacer con las líneas que no se pueden leer o parsear.
+    #[arg(long, value_enum, default_value_t = OnError::FailFast)]
+    pub on_error: OnError,
+
+    /// Incluye en la salida la sección `errors` con los errores encontrados en cada Site.
+    #[arg(long)]
+    pub error_report: bool,
+
+    /// Forma de contar las palabras de cada text.
+    #[arg(long, value_enum, default_value_t = TokenizerMode::Whitespace)]
+    pub tokenizer: TokenizerMode,
+
+    /// Incluye en cada Site y Tag estadísticas de palabras por pregunta: mínimo, máximo, media, varianza y percentiles.
+    #[arg(long)]
+    pub length_stats: bool,
+
+    /// Cuenta las palabras distintas de cada Site y Tag, y las incluye en la salida junto con el type-token ratio.
+    #[arg(long, value_enum)]
+    pub vocabulary: Option<VocabularyModeArg>,
+
+    /// Incluye en cada Site y Tag sus N palabras más frecuentes, sin las stopwords.
+    #[arg(long, value_name = "N", value_parser = parse_positive)]
+    pub top_words: Option<usize>,
+
+    /// Forma de contar las palabras más frecuentes.
+    #[arg(long, value_enum, default_value_t = TopWordsModeArg::Sketch)]
+    pub top_words_mode: TopWordsModeArg,
+
+    /// Archivo con las stopwords, una por línea, que reemplazan a las que vienen por defecto (en inglés). Las líneas vacías y las que empiezan con `#` se ignoran.
+    #[arg(long, value_name = "ARCHIVO")]
+    pub stopwords: Option<PathBuf>,
+
+    /// No descarta ninguna stopword de las palabras más frecuentes.
+    #[arg(long, conflicts_with = "stopwords")]
+    pub no_stopwords: bool,
+
+    /// Hace una segunda pasada por las entradas e incluye los N términos más distintivos por TF-IDF de cada Tag respecto de su Site, o de cada Site respecto de todos.
+    #[arg(long, value_name = "N", value_parser = parse_positive)]
+    pub distinctive_terms: Option<usize>,
+
+    /// De qué se calculan los términos distintivos.
+    #[arg(long, value_enum, default_value_t = TermsScopeArg::Tag)]
+    pub distinctive_scope: TermsScopeArg,
+
+    /// Cantidad mínima de preguntas del Tag o Site en las que tiene que aparecer un término distintivo.
+    #[arg(long, default_value_t = DEFAULT_MIN_TERM_QUESTIONS, value_parser = parse_positive)]
+    pub min_term_questions: usize,
+
+    /// Cuenta los pares de tags que aparecen juntos en una pregunta, por Site y en total, y los incluye en la salida.
+    #[arg(long)]
+    pub cooccurrence: bool,
+
+    /// Cantidad mínima de preguntas en las que tiene que aparecer un par de tags para incluirlo.
+    #[arg(long, default_value_t = 1, value_parser = parse_positive)]
+    pub min_pair_count: usize,
+
+    /// Archivo donde se escribe además el grafo de pares de tags. Implica `--cooccurrence`.
+    #[arg(long)]
+    pub graph_output: Option<PathBuf>,
+
+    /// Formato del archivo del grafo.
+    #[arg(long, value_enum, default_value_t = GraphFormat::EdgeList)]
+    pub graph_format: GraphFormat,
+
+    /// Site del que se escribe el grafo. Si no se indica, se escribe el grafo de todos los Sites.
+    #[arg(long)]
+    pub graph_site: Option<String>,
+
+    /// Calcula la similitud entre todos los pares de Sites, y lista en cada Site los más parecidos según esta medida.
+    #[arg(long, value_enum)]
+    pub similarity: Option<Similarity>,
+
+    /// Cantidad de Sites más parecidos que se listan en cada Site.
+    #[arg(long, default_value_t = DEFAULT_NEAREST_SITES, value_parser = parse_positive)]
+    pub nearest_sites: usize,
+
+    /// Calcula la legibilidad de cada Site y Tag (índice de Flesch, grado de Flesch-Kincaid y largo promedio de las oraciones), y los rankings de los más difíciles de leer.
+    #[arg(long)]
+    pub readability: bool,
+
+    /// Cantidad de elementos de las listas de los más difíciles de leer. Si no se indica, se usa `--top`.
+    #[arg(long, value_parser = parse_positive)]
+    pub hardest: Option<usize>,
+
+    /// Detecta el idioma de cada pregunta, incluye en cada Site la cantidad de preguntas y palabras por idioma, y mide las preguntas en japonés, chino y coreano en caracteres.
+    #[arg(long)]
+    pub languages: bool,
+
+    /// Descarta las preguntas con los mismos texts (sin importar mayúsculas ni espacios) que otra ya vista en el mismo Site, o en cualquier Site, y cuenta en cada Site cuántas se descartaron.
+    #[arg(long, value_enum)]
+    pub dedup: Option<DedupScopeArg>,
+
+    /// Busca las preguntas casi duplicadas, dentro de cada Site y entre Sites, con MinHash y LSH, y las incluye en la salida como clusters.
+    #[arg(long)]
+    pub near_duplicates: bool,
+
+    /// Similitud de Jaccard estimada mínima para que dos preguntas se consideren casi duplicadas.
+    #[arg(long, default_value_t = DEFAULT_DUPLICATE_THRESHOLD, value_parser = parse_fraction)]
+    pub duplicate_threshold: f64,
+
+    /// Suma a los Sites y Tags solo la primera pregunta de cada cluster de casi duplicadas. Implica `--near-duplicates`.
+    #[arg(long)]
+    pub dedup_near_duplicates: bool,
+
+    /// Tamaño aproximado en bytes de los rangos en los que se dividen los archivos sin comprimir.
+    #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE, value_parser = clap::value_parser!(u64).range(1..))]
+    pub chunk_size: u64,
+}
+
+impl AnalyzeArgs {
+    /// Configuración de los rankings según los argumentos. Los tamaños específicos tienen prioridad sobre `--top` y `--bottom`.
+    pub fn chatty_config(&self) -> ChattyConfig {
+        ChattyConfig {
+            metric: self.metric.into(),
+            chatty_sites: self.top_sites.unwrap_or(self.top),
+            chatty_tags: self.top_tags.unwrap_or(self.top),
+            site_chatty_tags: self.top_site_tags.unwrap_or(self.top),
+            terse_sites: self.bottom_sites.unwrap_or(self.bottom),
+            terse_tags: self.bottom_tags.unwrap_or(self.bottom),
+            site_terse_tags: self.bottom_site_tags.unwrap_or(self.bottom),
+            thresholds: ChattyThresholds {
+                min_questions: self.min_questions,
+                smoothing: self.smoothing,
+            },
+        }
+    }
+}
+
+/// Métricas de los rankings por línea de comando.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Metric {
+    /// Promedio de palabras por pregunta.
+    MeanWords,
+    /// Mediana de palabras por pregunta.
+    MedianWords,
+    /// Promedio de palabras del título por pregunta.
+    MeanTitleWords,
+    /// Promedio de palabras del cuerpo por pregunta.
+    MeanBodyWords,
+    /// Total de palabras.
+    TotalWords,
+    /// Cantidad de preguntas.
+    Questions,
+}
+
+impl From<Metric> for ChattyMetric {
+    fn from(metric: Metric) -> Self {
+        match metric {
+            Metric::MeanWords => ChattyMetric::MeanWords,
+            Metric::MedianWords => ChattyMetric::MedianWords,
+            Metric::MeanTitleWords => ChattyMetric::MeanTitleWords,
+            Metric::MeanBodyWords => ChattyMetric::MeanBodyWords,
+            Metric::TotalWords => ChattyMetric::TotalWords,
+            Metric::Questions => ChattyMetric::Questions,
+        }
+    }
+}
+
+/// Formatos posibles para el archivo del grafo de pares de tags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// Una arista por línea: `tag_1<TAB>tag_2<TAB>cantidad`.
+    EdgeList,
+    /// GraphML, con la cantidad de preguntas de cada tag y el peso de cada arista.
+    Graphml,
+}
+
+/// Medidas de similitud entre Sites por línea de comando.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Similarity {
+    /// Jaccard de los conjuntos de tags.
+    Jaccard,
+    /// Coseno de las cantidades de preguntas por tag.
+    Cosine,
+}
+
+impl From<Similarity> for SimilarityMeasure {
+    fn from(similarity: Similarity) -> Self {
+        match similarity {
+            Similarity::Jaccard => SimilarityMeasure::Jaccard,
+            Similarity::Cosine => SimilarityMeasure::Cosine,
+        }
+    }
+}
+
+/// Tokenizers por línea de comando.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TokenizerMode {
+    /// Separa por espacios.
+    Whitespace,
+    /// Segmentación de palabras de Unicode.
+    Unicode,
+    /// Segmentación de Unicode sin código, URLs ni markup.
+    Prose,
+}
+
+impl From<TokenizerMode> for Tokenizer {
+    fn from(mode: TokenizerMode) -> Self {
+        match mode {
+            TokenizerMode::Whitespace => Tokenizer::Whitespace,
+            TokenizerMode::Unicode => Tokenizer::Unicode,
+            TokenizerMode::Prose => Tokenizer::Prose,
+        }
+    }
+}
+
+/// Medidas de asociación entre tags por línea de comando.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Measure {
+    /// Información mutua puntual.
+    Pmi,
+    /// Lift.
+    Lift,
+    /// Similitud de Jaccard.
+    Jaccard,
+}
+
+impl From<Measure> for AssociationMeasure {
+    fn from(measure: Measure) -> Self {
+        match measure {
+            Measure::Pmi => AssociationMeasure::Pmi,
+            Measure::Lift => AssociationMeasure::Lift,
+            Measure::Jaccard => AssociationMeasure::Jaccard,
+        }
+    }
+}
+
+/// Formas de contar el vocabulario por línea de comando.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum VocabularyModeArg {
+    /// Estimación con HyperLogLog.
+    Sketch,
+    /// Conjunto con todas las palabras, solo para entradas chicas.
+    Exact,
+}
+
+impl From<VocabularyModeArg> for VocabularyMode {
+    fn from(mode: VocabularyModeArg) -> Self {
+        match mode {
+            VocabularyModeArg::Sketch => VocabularyMode::Sketch,
+            VocabularyModeArg::Exact => VocabularyMode::Exact,
+        }
+    }
+}
+
+/// Formas de contar las palabras más frecuentes por línea de comando.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TopWordsModeArg {
+    /// Space-Saving, con una cantidad fija de contadores.
+    Sketch,
+    /// Todas las palabras, solo para entradas chicas.
+    Exact,
+}
+
+impl From<TopWordsModeArg> for TopWordsMode {
+    fn from(mode: TopWordsModeArg) -> Self {
+        match mode {
+            TopWordsModeArg::Sketch => TopWordsMode::Sketch,
+            TopWordsModeArg::Exact => TopWordsMode::Exact,
+        }
+    }
+}
+
+/// Alcances de los términos distintivos por línea de comando.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TermsScopeArg {
+    /// Cada Tag respecto de su Site.
+    Tag,
+    /// Cada Site respecto de todos los Sites.
+    Site,
+}
+
+impl From<TermsScopeArg> for TermsScope {
+    fn from(scope: TermsScopeArg) -> Self {
+        match scope {
+            TermsScopeArg::Tag => TermsScope::Tag,
+            TermsScopeArg::Site => TermsScope::Site,
+        }
+    }
+}
+
+/// Alcances de la deduplicación exacta por línea de comando.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DedupScopeArg {
+    /// Las repetidas dentro de un mismo Site.
+    Site,
+    /// Las repetidas en cualquier Site.
+    Global,
+}
+
+impl From<DedupScopeArg> for DedupScope {
+    fn from(scope: DedupScopeArg) -> Self {
+        match scope {
+            DedupScopeArg::Site => DedupScope::Site,
+            DedupScopeArg::Global => DedupScope::Global,
+        }
+    }
+}
+
+/// Opciones de la política de errores por línea de comando.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OnError {
+    /// Corta el procesamiento en la primera línea inválida.
+    FailFast,
+    /// Saltea las líneas inválidas y las cuenta.
+    SkipCount,
+    /// Saltea las líneas inválidas, las cuenta e informa cada una por stderr.
+    SkipLog,
+}
+
+impl From<OnError> for ErrorPolicy {
+    fn from(on_error: OnError) -> Self {
+        match on_error {
+            OnError::FailFast => ErrorPolicy::FailFast,
+            OnError::SkipCount => ErrorPolicy::SkipAndCount,
+            OnError::SkipLog => ErrorPolicy::SkipAndLog,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ValidateArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Cantidad de threads a utilizar. Por defecto se usan los disponibles en el sistema.
+    #[arg(short = 't', long, value_parser = parse_positive)]
+    pub threads: Option<usize>,
+}
+
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// JSON generado por `analyze` que se toma como referencia.
+    pub old: PathBuf,
+
+    /// JSON generado por `analyze` que se compara contra la referencia.
+    pub new: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct AssociateArgs {
+    /// JSON generado por `analyze` con los pares de tags.
+    pub result: PathBuf,
+
+    /// Tag cuyos tags asociados se buscan.
+    pub tag: String,
+
+    /// Medida con la que se ordenan los tags asociados.
+    #[arg(short, long, value_enum, default_value_t = Measure::Pmi)]
+    pub measure: Measure,
+
+    /// Site en el que se buscan los tags asociados. Si no se indica, se usan todos los Sites.
+    #[arg(short, long)]
+    pub site: Option<String>,
+
+    /// Cantidad de tags asociados a mostrar.
+    #[arg(short = 'k', long, default_value_t = DEFAULT_TOP_N, value_parser = parse_positive)]
+    pub top: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Cantidades de threads a medir, separadas por coma.
+    #[arg(short = 't', long, value_delimiter = ',', default_value = "1,2,4,8", value_parser = parse_positive)]
+    pub threads: Vec<usize>,
+
+    /// Cantidad de veces que se repite cada medición.
+    #[arg(short, long, default_value_t = 3, value_parser = parse_positive)]
+    pub iterations: usize,
+}
+
+/// Parsea un entero positivo, rechazando el cero.
+fn parse_positive(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("tiene que ser mayor a cero".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parsea un peso de suavizado, que tiene que ser un número finito mayor a cero.
+fn parse_weight(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(weight) if weight.is_finite() && weight > 0.0 => Ok(weight),
+        Ok(_) => Err("tiene que ser un número mayor a cero".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parsea una proporción, que tiene que ser un número mayor a cero y menor o igual a uno.
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(fraction) if fraction > 0.0 && fraction <= 1.0 => Ok(fraction),
+        Ok(_) => Err("tiene que ser un número mayor a cero y menor o igual a uno".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn cli_is_well_formed() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_analyze_arguments() {
+        let cli = Cli::try_parse_from([
+            "tp1",
+            "analyze",
+            "-t",
+            "4",
+            "-n",
+            "5",
+            "-r",
+            "-f",
+            "compact",
+            "-o",
+            "out.json",
+            "test_data",
+        ])
+        .unwrap();
+        match cli.command {
+            Command::Analyze(args) => {
+                assert_eq!(args.threads, Some(4));
+                assert_eq!(args.top, 5);
+                assert!(args.input.recursive);
+                assert_eq!(args.input.inputs, ["test_data".to_string()]);
+                assert_eq!(args.output.format, OutputFormat::Compact);
+                assert_eq!(args.output.output, Some(PathBuf::from("out.json")));
+                assert_eq!(args.padron, PADRON);
+                assert_eq!(args.on_error, OnError::FailFast);
+                assert_eq!(args.tokenizer, TokenizerMode::Whitespace);
+                assert!(!args.cooccurrence);
+                assert_eq!(args.graph_format, GraphFormat::EdgeList);
+                assert_eq!(args.similarity, None);
+                assert_eq!(args.vocabulary, None);
+                assert_eq!(args.top_words, None);
+                assert_eq!(args.top_words_mode, TopWordsModeArg::Sketch);
+                assert_eq!(args.distinctive_terms, None);
+                assert!(!args.near_duplicates && !args.dedup_near_duplicates);
+                assert_eq!(args.dedup, None);
+                assert!(!args.languages);
+                assert!(!args.readability);
+                assert_eq!(args.hardest, None);
+                assert_eq!(args.duplicate_threshold, DEFAULT_DUPLICATE_THRESHOLD);
+                assert_eq!(args.distinctive_scope, TermsScopeArg::Tag);
+                assert_eq!(args.min_term_questions, DEFAULT_MIN_TERM_QUESTIONS);
+                assert_eq!(args.nearest_sites, DEFAULT_NEAREST_SITES);
+                assert_eq!(args.chatty_config(), ChattyConfig::with_top_n(5));
+            }
+            _ => panic!("Se esperaba el subcomando analyze"),
+        }
+    }
+
+    #[test]
+    fn parses_ranking_arguments() {
+        let cli = Cli::try_parse_from([
+            "tp1",
+            "analyze",
+            "-n",
+            "5",
+            "--top-tags",
+            "0",
+            "-b",
+            "3",
+            "--bottom-site-tags",
+            "1",
+            "-m",
+            "median-words",
+            "--min-questions",
+            "10",
+            "--smoothing",
+            "2.5",
+        ])
+        .unwrap();
+        match cli.command {
+            Command::Analyze(args) => assert_eq!(
+                args.chatty_config(),
+                ChattyConfig {
+                    metric: ChattyMetric::MedianWords,
+                    chatty_sites: 5,
+                    chatty_tags: 0,
+                    site_chatty_tags: 5,
+                    terse_sites: 3,
+                    terse_tags: 3,
+                    site_terse_tags: 1,
+                    thresholds: ChattyThresholds {
+                        min_questions: 10,
+                        smoothing: Some(2.5),
+                    },
+                }
+            ),
+            _ => panic!("Se esperaba el subcomando analyze"),
+        }
+    }
+
+    #[test]
+    fn parses_associate_arguments() {
+        let cli = Cli::try_parse_from([
+            "tp1",
+            "associate",
+            "out.json",
+            "rust",
+            "-m",
+            "jaccard",
+            "-s",
+            "site",
+            "-k",
+            "3",
+        ])
+        .unwrap();
+        match cli.command {
+            Command::Associate(args) => {
+                assert_eq!(args.result, PathBuf::from("out.json"));
+                assert_eq!(args.tag, "rust");
+                assert_eq!(args.measure, Measure::Jaccard);
+                assert_eq!(args.site.as_deref(), Some("site"));
+                assert_eq!(args.top, 3);
+            }
+            _ => panic!("Se esperaba el subcomando associate"),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        assert!(Cli::try_parse_from(["tp1"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "-t", "0"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "-t", "abc"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "-n", "0"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--on-error", "ignore"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "-m", "ratio"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--tokenizer", "regex"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--min-pair-count", "0"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--graph-format", "dot"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--smoothing", "0"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--similarity", "euclidean"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--vocabulary", "bloom"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--top-words", "0"]).is_err());
+        assert!(Cli::try_parse_from([
+            "tp1",
+            "analyze",
+            "--stopwords",
+            "stop.txt",
+            "--no-stopwords"
+        ])
+        .is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--nearest-sites", "0"]).is_err());
+        assert!(
+            Cli::try_parse_from(["tp1", "analyze", "--distinctive-scope", "question"]).is_err()
+        );
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--duplicate-threshold", "0"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--dedup", "exact"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--hardest", "0"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--duplicate-threshold", "1.5"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "analyze", "--smoothing", "inf"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "diff", "old.json"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "bench", "-t", "1,x"]).is_err());
+        assert!(Cli::try_parse_from(["tp1", "associate", "out.json"]).is_err());
+        assert!(
+            Cli::try_parse_from(["tp1", "associate", "out.json", "rust", "-m", "chi2"]).is_err()
+        );
+    }
+}
@@ -0,0 +1,179 @@
+/// Cantidad de código de una pregunta: líneas de los bloques de código y caracteres de código, incluyendo el código inline.
+/// Se detectan los bloques con fences (``` o ~~~), los bloques indentados (4 espacios o un tab) y el código inline entre backticks.
+/// Un bloque con fence se cierra con un fence del mismo caracter y al menos igual de largo, y un bloque indentado tiene que empezar después de una línea vacía (o al principio del text), como en Markdown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CodeCounts {
+    /// Líneas dentro de bloques de código, sin contar las líneas de los fences.
+    pub lines: usize,
+    /// Caracteres de código, sin contar la indentación de los bloques indentados ni los backticks.
+    pub chars: usize,
+}
+
+impl CodeCounts {
+    /// Cuenta el código de un text.
+    pub fn from_text(text: &str) -> CodeCounts {
+        let mut code = CodeCounts::default();
+        let mut scanner = BlockScanner::default();
+        text.lines().for_each(|line| match scanner.classify(line) {
+            BlockLine::Fence => {}
+            BlockLine::Code(code_line) => {
+                if !code_line.trim().is_empty() {
+                    code.add_line(code_line);
+                }
+            }
+            BlockLine::Prose(line) => code.chars += inline_code_chars(line),
+        });
+        code
+    }
+
+    /// Cuenta el código de todos los texts de una pregunta.
+    pub fn from_texts(texts: &[String]) -> CodeCounts {
+        texts.iter().map(|text| CodeCounts::from_text(text)).fold(
+            CodeCounts::default(),
+            |total, code| CodeCounts {
+                lines: total.lines + code.lines,
+                chars: total.chars + code.chars,
+            },
+        )
+    }
+
+    /// Indica si la pregunta tiene algo de código.
+    pub fn has_code(&self) -> bool {
+        self.lines > 0 || self.chars > 0
+    }
+
+    /// Suma una línea de un bloque de código.
+    fn add_line(&mut self, line: &str) {
+        self.lines += 1;
+        self.chars += line.chars().count();
+    }
+}
+
+/// Tipo de una línea de un text según los bloques de código de Markdown.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum BlockLine<'a> {
+    /// Línea que abre o cierra un bloque con fence.
+    Fence,
+    /// Línea de un bloque de código, sin la indentación si es un bloque indentado.
+    Code(&'a str),
+    /// Línea fuera de los bloques de código, que puede tener código inline.
+    Prose(&'a str),
+}
+
+/// Recorre las líneas de un text en orden, recordando el fence abierto (su caracter y largo) y si la línea anterior era parte de un párrafo.
+#[derive(Debug, Default)]
+pub(crate) struct BlockScanner {
+    fence: Option<(char, usize)>,
+    in_paragraph: bool,
+}
+
+impl BlockScanner {
+    /// Clasifica la siguiente línea del text.
+    pub(crate) fn classify<'a>(&mut self, line: &'a str) -> BlockLine<'a> {
+        let trimmed = line.trim_start();
+        if let Some((fence_char, fence_len)) = self.fence {
+            let closes = fence_run(trimmed).is_some_and(|(c, len)| {
+                c == fence_char && len >= fence_len && trimmed[len..].trim().is_empty()
+            });
+            if closes {
+                self.fence = None;
+                return BlockLine::Fence;
+            }
+            return BlockLine::Code(line);
+        }
+        if let Some(fence) = fence_run(trimmed) {
+            self.fence = Some(fence);
+            self.in_paragraph = false;
+            return BlockLine::Fence;
+        }
+        if !self.in_paragraph {
+            if let Some(code_line) = line.strip_prefix("    ").or(line.strip_prefix('\t')) {
+                return BlockLine::Code(code_line);
+            }
+        }
+        self.in_paragraph = !trimmed.is_empty();
+        BlockLine::Prose(line)
+    }
+}
+
+/// Si la línea (sin indentación) empieza con un fence de al menos tres ``` o ~~~, devuelve su caracter y su largo.
+fn fence_run(trimmed: &str) -> Option<(char, usize)> {
+    let fence_char = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let len = trimmed.chars().take_while(|c| *c == fence_char).count();
+    (len >= 3).then_some((fence_char, len))
+}
+
+/// Cuenta los caracteres de código inline de una línea, entre pares de backticks. Un backtick sin cerrar no es código.
+fn inline_code_chars(line: &str) -> usize {
+    line.split('`')
+        .enumerate()
+        .take(line.matches('`').count() / 2 * 2)
+        .filter(|(i, _)| i % 2 == 1)
+        .map(|(_, code)| code.chars().count())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_fenced_and_indented_blocks() {
+        let text = "Why does this fail?\n```rust\nfn main() {\n}\n```\nOr:\n\n    let x = 1;\n\tx += 1;\n    \nThanks";
+
+        assert_eq!(
+            CodeCounts::from_text(text),
+            CodeCounts {
+                lines: 4,
+                chars: 11 + 1 + 10 + 7
+            }
+        );
+    }
+
+    #[test]
+    fn fences_close_with_the_same_fence() {
+        let text = "```\n~~~\ncode\n```\n````\n```\n````\nAfter";
+
+        assert_eq!(
+            CodeCounts::from_text(text),
+            CodeCounts {
+                lines: 3,
+                chars: 3 + 4 + 3
+            }
+        );
+    }
+
+    #[test]
+    fn indented_code_needs_a_blank_line() {
+        let list = CodeCounts::from_text("- first item\n    continues here\n- second");
+        assert_eq!(list, CodeCounts::default());
+
+        let code = CodeCounts::from_text("    at start\nparagraph\n    not code\n\n    code");
+        assert_eq!(code.lines, 2);
+    }
+
+    #[test]
+    fn counts_inline_code() {
+        let code = CodeCounts::from_text("Use `map` or `filter_map`, not ` alone");
+
+        assert_eq!(
+            code,
+            CodeCounts {
+                lines: 0,
+                chars: 13
+            }
+        );
+        assert!(code.has_code());
+    }
+
+    #[test]
+    fn prose_has_no_code() {
+        let code = CodeCounts::from_texts(&[
+            "A title".to_string(),
+            "Just some text\nin two lines".to_string(),
+        ]);
+
+        assert_eq!(code, CodeCounts::default());
+        assert!(!code.has_code());
+    }
+}
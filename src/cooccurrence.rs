@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Cantidad de preguntas en las que aparece cada par de tags.
+/// Cada par se guarda una sola vez, bajo el tag menor en orden alfabético: `{"a": {"b": 3}}` significa que `a` y `b` aparecen juntos en 3 preguntas.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TagPairs {
+    pairs: HashMap<String, HashMap<String, usize>>,
+}
+
+impl TagPairs {
+    /// Suma los pares de tags de una pregunta. Los tags tienen que estar ordenados y sin repetidos.
+    pub fn add_question(&mut self, tags: &[String]) {
+        tags.iter().enumerate().for_each(|(i, tag_1)| {
+            if i + 1 < tags.len() {
+                let neighbors = self.pairs.entry(tag_1.to_string()).or_default();
+                tags[i + 1..].iter().for_each(|tag_2| {
+                    *neighbors.entry(tag_2.to_string()).or_insert(0) += 1;
+                });
+            }
+        });
+    }
+
+    /// Une otros pares a sí mismo consumiéndolos, de forma que los mapas se mueven en vez de clonarse.
+    pub fn merge(&mut self, other: TagPairs) {
+        other
+            .pairs
+            .into_iter()
+            .for_each(
+                |(tag_1, other_neighbors)| match self.pairs.get_mut(&tag_1) {
+                    Some(neighbors) => other_neighbors.into_iter().for_each(|(tag_2, count)| {
+                        *neighbors.entry(tag_2).or_insert(0) += count;
+                    }),
+                    None => {
+                        self.pairs.insert(tag_1, other_neighbors);
+                    }
+                },
+            );
+    }
+
+    /// Suma otros pares a sí mismo, in-place.
+    pub fn add(&mut self, other: &TagPairs) {
+        other.pairs.iter().for_each(|(tag_1, other_neighbors)| {
+            let neighbors = self.pairs.entry(tag_1.to_string()).or_default();
+            other_neighbors.iter().for_each(|(tag_2, count)| {
+                *neighbors.entry(tag_2.to_string()).or_insert(0) += count;
+            });
+        });
+    }
+
+    /// Cantidad de preguntas en las que aparecen juntos los dos tags, en cualquier orden.
+    pub fn count(&self, tag_1: &str, tag_2: &str) -> usize {
+        let (first, second) = if tag_1 <= tag_2 {
+            (tag_1, tag_2)
+        } else {
+            (tag_2, tag_1)
+        };
+        self.pairs
+            .get(first)
+            .and_then(|neighbors| neighbors.get(second))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Tags que aparecen junto a `tag` en alguna pregunta, con la cantidad de preguntas en las que aparecen juntos.
+    pub fn neighbors(&self, tag: &str) -> Vec<(&str, usize)> {
+        let after = self
+            .pairs
+            .get(tag)
+            .into_iter()
+            .flat_map(|neighbors| neighbors.iter())
+            .map(|(tag_2, count)| (tag_2.as_str(), *count));
+        let before = self
+            .pairs
+            .iter()
+            .filter(|(tag_1, _)| tag_1.as_str() < tag)
+            .filter_map(|(tag_1, neighbors)| {
+                neighbors.get(tag).map(|count| (tag_1.as_str(), *count))
+            });
+        after.chain(before).collect()
+    }
+
+    /// Descarta los pares que aparecen en menos de `min_count` preguntas.
+    pub fn prune(&mut self, min_count: usize) {
+        self.pairs.retain(|_, neighbors| {
+            neighbors.retain(|_, count| *count >= min_count);
+            !neighbors.is_empty()
+        });
+    }
+
+    /// Cantidad de pares distintos.
+    pub fn len(&self) -> usize {
+        self.pairs.values().map(|neighbors| neighbors.len()).sum()
+    }
+
+    /// Indica si no hay ningún par.
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Todos los pares con su cantidad, ordenados por cantidad de mayor a menor y después por nombre.
+    pub fn edges(&self) -> Vec<(&str, &str, usize)> {
+        let mut edges: Vec<(&str, &str, usize)> = self
+            .pairs
+            .iter()
+            .flat_map(|(tag_1, neighbors)| {
+                neighbors
+                    .iter()
+                    .map(move |(tag_2, count)| (tag_1.as_str(), tag_2.as_str(), *count))
+            })
+            .collect();
+        edges.sort_by(|edge_1, edge_2| {
+            edge_2
+                .2
+                .cmp(&edge_1.2)
+                .then(edge_1.0.cmp(edge_2.0))
+                .then(edge_1.1.cmp(edge_2.1))
+        });
+        edges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn counts_every_pair_once() {
+        let mut pairs = TagPairs::default();
+        pairs.add_question(&tags(&["a", "b", "c"]));
+        pairs.add_question(&tags(&["a", "c"]));
+        pairs.add_question(&tags(&["d"]));
+
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs.count("a", "c"), 2);
+        assert_eq!(pairs.count("c", "a"), 2);
+        assert_eq!(pairs.count("b", "c"), 1);
+        assert_eq!(pairs.count("a", "d"), 0);
+        assert_eq!(pairs.edges(), [("a", "c", 2), ("a", "b", 1), ("b", "c", 1)]);
+        let mut neighbors = pairs.neighbors("b");
+        neighbors.sort_unstable();
+        assert_eq!(neighbors, [("a", 1), ("c", 1)]);
+        assert!(pairs.neighbors("d").is_empty());
+    }
+
+    #[test]
+    fn merge_and_add_are_the_same() {
+        let mut pairs_1 = TagPairs::default();
+        pairs_1.add_question(&tags(&["a", "b"]));
+        let mut pairs_2 = TagPairs::default();
+        pairs_2.add_question(&tags(&["a", "b", "c"]));
+        pairs_2.add_question(&tags(&["b", "c"]));
+
+        let mut added = pairs_1.clone();
+        added.add(&pairs_2);
+        pairs_1.merge(pairs_2);
+
+        assert_eq!(added, pairs_1);
+        assert_eq!(pairs_1.count("a", "b"), 2);
+        assert_eq!(pairs_1.count("b", "c"), 2);
+    }
+
+    #[test]
+    fn prune_removes_rare_pairs() {
+        let mut pairs = TagPairs::default();
+        pairs.add_question(&tags(&["a", "b", "c"]));
+        pairs.add_question(&tags(&["a", "b"]));
+        pairs.prune(2);
+
+        assert_eq!(pairs.edges(), [("a", "b", 2)]);
+        pairs.prune(3);
+        assert!(pairs.is_empty());
+    }
+}
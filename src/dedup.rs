@@ -0,0 +1,212 @@
+use std::{
+    collections::{hash_map::DefaultHasher, hash_map::Entry, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{
+    error::ProcessingError, input::get_site_name, line::Line, options::ProcessOptions,
+    processed_sites::fold_lines,
+};
+
+/// Cantidad de shards del conjunto de fingerprints. Cada shard tiene su propio lock, así que los threads casi nunca se bloquean entre sí.
+const SHARDS: usize = 64;
+
+/// Dónde se buscan las preguntas repetidas al deduplicar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupScope {
+    /// Solo se descartan las repetidas dentro de un mismo Site.
+    Site,
+    /// Se descartan las repetidas en cualquier Site.
+    Global,
+}
+
+/// Posición de una pregunta: el orden de su archivo (por path) y su número de línea. Se comparan en ese orden.
+pub type QuestionPosition = (usize, usize);
+
+/// Conjunto concurrente de fingerprints de los texts normalizados de las preguntas ya vistas, dividido en shards con un lock cada uno.
+/// Cada fingerprint guarda la posición de la primera pregunta con esos texts, así que de cada grupo de repetidas siempre se queda la misma, sin importar en qué orden lleguen los threads.
+/// No guarda los textos, sino un hash de 64 bits y una posición por pregunta distinta (unos 32 bytes, más lo que ocupa el HashMap), así que la memoria crece con la cantidad de preguntas distintas pero no con el largo de los textos.
+#[derive(Debug)]
+pub struct Fingerprints {
+    scope: DedupScope,
+    shards: Vec<Mutex<HashMap<u64, QuestionPosition>>>,
+}
+
+impl Fingerprints {
+    /// Crea un conjunto vacío que busca las repetidas según `scope`.
+    pub fn new(scope: DedupScope) -> Fingerprints {
+        Fingerprints {
+            scope,
+            shards: (0..SHARDS).map(|_| Mutex::default()).collect(),
+        }
+    }
+
+    /// Agrega el fingerprint de la línea en la posición `position` del Site `sitename`.
+    /// Si ya había una pregunta con los mismos texts, devuelve la posición de la que queda descartada, que es la mayor de las dos; si no, devuelve None.
+    pub fn insert(
+        &self,
+        sitename: &str,
+        position: QuestionPosition,
+        line: &Line,
+    ) -> Option<QuestionPosition> {
+        let site = match self.scope {
+            DedupScope::Site => Some(sitename),
+            DedupScope::Global => None,
+        };
+        let fingerprint = fingerprint(site, &line.texts);
+        let mut shard = self.shards[fingerprint as usize % SHARDS]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match shard.entry(fingerprint) {
+            Entry::Vacant(entry) => {
+                entry.insert(position);
+                None
+            }
+            Entry::Occupied(mut entry) if position < *entry.get() => Some(entry.insert(position)),
+            Entry::Occupied(_) => Some(position),
+        }
+    }
+
+    /// Cantidad de preguntas distintas vistas.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap_or_else(PoisonError::into_inner).len())
+            .sum()
+    }
+
+    /// Indica si todavía no se vio ninguna pregunta.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Lee los archivos JSON en paralelo y busca las preguntas repetidas según `options.dedup`, con un conjunto de fingerprints compartido por los threads.
+/// De cada grupo de preguntas con los mismos texts normalizados se queda la del menor archivo (por path) y número de línea, y se devuelven las demás, por archivo y número de línea.
+/// Las líneas inválidas y las que ya están excluidas en las opciones se ignoran. Si las opciones no piden deduplicar, no se lee nada.
+pub fn find_exact_duplicates(
+    json_paths: &[PathBuf],
+    options: &ProcessOptions,
+) -> Result<HashMap<PathBuf, HashSet<usize>>, ProcessingError> {
+    let Some(scope) = options.dedup else {
+        return Ok(HashMap::new());
+    };
+    let site_names: Vec<String> = json_paths.iter().map(|p| get_site_name(p)).collect();
+    let mut order: Vec<usize> = (0..json_paths.len()).collect();
+    order.sort_by(|file_1, file_2| json_paths[*file_1].cmp(&json_paths[*file_2]));
+    let mut ranks = vec![0; json_paths.len()];
+    order
+        .iter()
+        .enumerate()
+        .for_each(|(rank, file)| ranks[*file] = rank);
+
+    let fingerprints = Fingerprints::new(scope);
+    let duplicates: Vec<QuestionPosition> = fold_lines(
+        json_paths,
+        options.chunk_size,
+        |mut duplicates: Vec<QuestionPosition>, file, number, line| {
+            if options.is_excluded(&json_paths[file], number) {
+                return Ok(duplicates);
+            }
+            if let Some(line) = line.ok().and_then(|line| Line::parse(&line).ok()) {
+                duplicates.extend(fingerprints.insert(
+                    &site_names[file],
+                    (ranks[file], number),
+                    &line,
+                ));
+            }
+            Ok(duplicates)
+        },
+        |mut duplicates_1, duplicates_2| {
+            duplicates_1.extend(duplicates_2);
+            duplicates_1
+        },
+    )?;
+
+    let mut excluded: HashMap<PathBuf, HashSet<usize>> = HashMap::new();
+    duplicates.into_iter().for_each(|(rank, line)| {
+        excluded
+            .entry(json_paths[order[rank]].to_path_buf())
+            .or_default()
+            .insert(line);
+    });
+    Ok(excluded)
+}
+
+/// Hash de los texts normalizados (en minúsculas y con los espacios colapsados), junto con el Site si se indica.
+/// Así dos preguntas que solo difieren en mayúsculas o espacios tienen el mismo fingerprint.
+fn fingerprint(site: Option<&str>, texts: &[String]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    site.hash(&mut hasher);
+    texts.len().hash(&mut hasher);
+    texts.iter().for_each(|text| {
+        text.split_whitespace()
+            .for_each(|word| word.to_lowercase().hash(&mut hasher));
+        // Separa los texts, para que no sea lo mismo pasar una palabra del título al cuerpo
+        hasher.write_u8(0xff);
+    });
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(texts: &[&str]) -> Line {
+        Line {
+            texts: texts.iter().map(|text| text.to_string()).collect(),
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn normalizes_texts() {
+        let fingerprints = Fingerprints::new(DedupScope::Global);
+        assert_eq!(
+            fingerprints.insert("a", (0, 1), &line(&["Hola  mundo", "cuerpo"])),
+            None
+        );
+        assert_eq!(
+            fingerprints.insert("a", (0, 2), &line(&[" hola mundo ", "CUERPO"])),
+            Some((0, 2))
+        );
+        assert_eq!(
+            fingerprints.insert("a", (0, 3), &line(&["hola", "mundo cuerpo"])),
+            None
+        );
+        assert_eq!(
+            fingerprints.insert("a", (0, 4), &line(&["hola mundo cuerpo"])),
+            None
+        );
+        assert_eq!(fingerprints.len(), 3);
+    }
+
+    #[test]
+    fn keeps_the_first_position_in_any_order() {
+        let fingerprints = Fingerprints::new(DedupScope::Global);
+        assert_eq!(fingerprints.insert("b", (1, 5), &line(&["texto"])), None);
+        assert_eq!(
+            fingerprints.insert("a", (0, 9), &line(&["texto"])),
+            Some((1, 5))
+        );
+        assert_eq!(
+            fingerprints.insert("b", (1, 1), &line(&["texto"])),
+            Some((1, 1))
+        );
+    }
+
+    #[test]
+    fn scope_per_site_or_global() {
+        let per_site = Fingerprints::new(DedupScope::Site);
+        assert_eq!(per_site.insert("a", (0, 1), &line(&["texto"])), None);
+        assert_eq!(per_site.insert("b", (1, 1), &line(&["texto"])), None);
+        assert!(per_site.insert("b", (1, 2), &line(&["texto"])).is_some());
+
+        let global = Fingerprints::new(DedupScope::Global);
+        assert!(global.is_empty());
+        assert_eq!(global.insert("a", (0, 1), &line(&["texto"])), None);
+        assert!(global.insert("b", (1, 1), &line(&["texto"])).is_some());
+    }
+}
@@ -0,0 +1,311 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+};
+
+use crate::{error::SiteErrors, processed_sites::ProcessedSites, site::Site};
+
+/// Diferencia entre dos ProcessedSites. Los valores numéricos se guardan como (questions, words).
+#[derive(Debug, PartialEq)]
+pub enum Difference {
+    SiteAdded(String),
+    SiteRemoved(String),
+    SiteChanged {
+        name: String,
+        old: (usize, usize),
+        new: (usize, usize),
+    },
+    SiteChattyTagsChanged {
+        name: String,
+        old: Vec<String>,
+        new: Vec<String>,
+    },
+    /// Cambió otro contador numérico del Site, como las palabras de los títulos, el código, los errores o el desglose por idioma. Si el Site no tiene el contador, vale 0.
+    SiteCounterChanged {
+        name: String,
+        counter: String,
+        old: usize,
+        new: usize,
+    },
+    TagAdded(String),
+    TagRemoved(String),
+    TagChanged {
+        name: String,
+        old: (usize, usize),
+        new: (usize, usize),
+    },
+    TotalChanged {
+        name: String,
+        old: Vec<String>,
+        new: Vec<String>,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::SiteAdded(name) => write!(f, "+ site {}", name),
+            Difference::SiteRemoved(name) => write!(f, "- site {}", name),
+            Difference::SiteChanged { name, old, new } => write!(
+                f,
+                "~ site {}: questions {} -> {}, words {} -> {}",
+                name, old.0, new.0, old.1, new.1
+            ),
+            Difference::SiteChattyTagsChanged { name, old, new } => {
+                write!(f, "~ site {} chatty_tags: {:?} -> {:?}", name, old, new)
+            }
+            Difference::SiteCounterChanged {
+                name,
+                counter,
+                old,
+                new,
+            } => write!(f, "~ site {} {}: {} -> {}", name, counter, old, new),
+            Difference::TagAdded(name) => write!(f, "+ tag {}", name),
+            Difference::TagRemoved(name) => write!(f, "- tag {}", name),
+            Difference::TagChanged { name, old, new } => write!(
+                f,
+                "~ tag {}: questions {} -> {}, words {} -> {}",
+                name, old.0, new.0, old.1, new.1
+            ),
+            Difference::TotalChanged { name, old, new } => {
+                write!(f, "~ totals {}: {:?} -> {:?}", name, old, new)
+            }
+        }
+    }
+}
+
+/// Compara dos ProcessedSites y devuelve sus diferencias, ordenadas por Sites, Tags y totals y luego por nombre.
+/// De cada Site se comparan las preguntas, las palabras, los chatty_tags y los contadores numéricos de `site_counters` (títulos y cuerpos, código, preguntas sin tags, descartadas, idiomas, legibilidad y errores).
+/// De cada Tag solo se comparan las preguntas y las palabras. No se comparan las listas calculadas a partir de los contadores (terse, hardest, nearest, términos distintivos) salvo las de totals, ni el vocabulario o las palabras más frecuentes.
+pub fn diff_processed_sites(old: &ProcessedSites, new: &ProcessedSites) -> Vec<Difference> {
+    let mut differences = vec![];
+
+    for name in sorted_keys(&old.sites, &new.sites) {
+        match (old.sites.get(name), new.sites.get(name)) {
+            (Some(_), None) => differences.push(Difference::SiteRemoved(name.to_string())),
+            (None, Some(_)) => differences.push(Difference::SiteAdded(name.to_string())),
+            (Some(old_site), Some(new_site)) => {
+                let old_counts = (old_site.questions, old_site.words);
+                let new_counts = (new_site.questions, new_site.words);
+                if old_counts != new_counts {
+                    differences.push(Difference::SiteChanged {
+                        name: name.to_string(),
+                        old: old_counts,
+                        new: new_counts,
+                    });
+                }
+                if old_site.chatty_tags != new_site.chatty_tags {
+                    differences.push(Difference::SiteChattyTagsChanged {
+                        name: name.to_string(),
+                        old: old_site.chatty_tags.clone(),
+                        new: new_site.chatty_tags.clone(),
+                    });
+                }
+                let old_errors = old.errors.as_ref().and_then(|errors| errors.get(name));
+                let new_errors = new.errors.as_ref().and_then(|errors| errors.get(name));
+                let old_counters = site_counters(old_site, old_errors);
+                let new_counters = site_counters(new_site, new_errors);
+                let counters: BTreeSet<&String> =
+                    old_counters.keys().chain(new_counters.keys()).collect();
+                counters.into_iter().for_each(|counter| {
+                    let old_value = old_counters.get(counter).copied().unwrap_or(0);
+                    let new_value = new_counters.get(counter).copied().unwrap_or(0);
+                    if old_value != new_value {
+                        differences.push(Difference::SiteCounterChanged {
+                            name: name.to_string(),
+                            counter: counter.to_string(),
+                            old: old_value,
+                            new: new_value,
+                        });
+                    }
+                });
+            }
+            (None, None) => {}
+        }
+    }
+
+    for name in sorted_keys(&old.tags, &new.tags) {
+        match (old.tags.get(name), new.tags.get(name)) {
+            (Some(_), None) => differences.push(Difference::TagRemoved(name.to_string())),
+            (None, Some(_)) => differences.push(Difference::TagAdded(name.to_string())),
+            (Some(old_tag), Some(new_tag)) => {
+                let old_counts = (old_tag.questions, old_tag.words);
+                let new_counts = (new_tag.questions, new_tag.words);
+                if old_counts != new_counts {
+                    differences.push(Difference::TagChanged {
+                        name: name.to_string(),
+                        old: old_counts,
+                        new: new_counts,
+                    });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    for name in sorted_keys(&old.totals, &new.totals) {
+        let old_total = old.totals.get(name).cloned().unwrap_or_default();
+        let new_total = new.totals.get(name).cloned().unwrap_or_default();
+        if old_total != new_total {
+            differences.push(Difference::TotalChanged {
+                name: name.to_string(),
+                old: old_total,
+                new: new_total,
+            });
+        }
+    }
+
+    differences
+}
+
+/// Contadores numéricos de un Site que compara `diff`, además de las preguntas y palabras, por nombre. Los opcionales solo están si el Site (o su reporte de errores) los tiene.
+fn site_counters(site: &Site, errors: Option<&SiteErrors>) -> BTreeMap<String, usize> {
+    let mut counters = BTreeMap::from([
+        ("title_words".to_string(), site.title_words),
+        ("body_words".to_string(), site.body_words),
+        ("code_lines".to_string(), site.code_lines),
+        ("code_chars".to_string(), site.code_chars),
+        ("code_questions".to_string(), site.code_questions),
+        (
+            "untagged_questions".to_string(),
+            site.tags_per_question.untagged(),
+        ),
+    ]);
+    if let Some(removed) = site.removed_duplicates {
+        counters.insert("removed_duplicates".to_string(), removed);
+    }
+    if let Some(removed) = site.removed_near_duplicates {
+        counters.insert("removed_near_duplicates".to_string(), removed);
+    }
+    site.languages
+        .iter()
+        .flatten()
+        .for_each(|(language, stats)| {
+            let language = format!("{:?}", language).to_lowercase();
+            counters.insert(format!("languages.{}.questions", language), stats.questions);
+            counters.insert(format!("languages.{}.words", language), stats.words);
+        });
+    if let Some(readability) = site.readability {
+        counters.insert("readability.sentences".to_string(), readability.sentences);
+        counters.insert("readability.words".to_string(), readability.words);
+        counters.insert("readability.syllables".to_string(), readability.syllables);
+    }
+    if let Some(errors) = errors {
+        counters.insert(
+            "errors.unreadable_lines".to_string(),
+            errors.unreadable_lines,
+        );
+        counters.insert("errors.parse_errors".to_string(), errors.parse_errors);
+        counters.insert("errors.missing_texts".to_string(), errors.missing_texts);
+        counters.insert("errors.missing_tags".to_string(), errors.missing_tags);
+        counters.insert("errors.file_errors".to_string(), errors.file_errors.len());
+    }
+    counters
+}
+
+/// Obtiene la unión ordenada de las claves de dos HashMaps.
+fn sorted_keys<'a, T>(
+    old: &'a HashMap<String, T>,
+    new: &'a HashMap<String, T>,
+) -> BTreeSet<&'a String> {
+    old.keys().chain(new.keys()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        language::{Language, LanguageStats},
+        tag::Tag,
+    };
+
+    fn processed_sites(site_questions: usize, tag_name: &str, chatty: &str) -> ProcessedSites {
+        let site = Site::new(
+            site_questions,
+            10,
+            HashMap::from([(tag_name.to_string(), Tag::new(1, 10))]),
+            vec![chatty.to_string()],
+        );
+        ProcessedSites::new(
+            "106160".to_string(),
+            HashMap::from([("site1".to_string(), site)]),
+            HashMap::from([(tag_name.to_string(), Tag::new(1, 10))]),
+            HashMap::from([("chatty_sites".to_string(), vec!["site1".to_string()])]),
+        )
+    }
+
+    #[test]
+    fn no_differences_for_equal_results() {
+        let old = processed_sites(1, "tag1", "tag1");
+        let new = processed_sites(1, "tag1", "tag1");
+
+        assert!(diff_processed_sites(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn reports_differences() {
+        let old = processed_sites(1, "tag1", "tag1");
+        let new = processed_sites(2, "tag2", "tag2");
+
+        let differences = diff_processed_sites(&old, &new);
+
+        assert_eq!(
+            differences,
+            [
+                Difference::SiteChanged {
+                    name: "site1".to_string(),
+                    old: (1, 10),
+                    new: (2, 10)
+                },
+                Difference::SiteChattyTagsChanged {
+                    name: "site1".to_string(),
+                    old: vec!["tag1".to_string()],
+                    new: vec!["tag2".to_string()]
+                },
+                Difference::TagRemoved("tag1".to_string()),
+                Difference::TagAdded("tag2".to_string()),
+            ]
+        );
+        assert_eq!(
+            differences[0].to_string(),
+            "~ site site1: questions 1 -> 2, words 10 -> 10"
+        );
+    }
+
+    #[test]
+    fn reports_other_site_counters() {
+        let old = processed_sites(1, "tag1", "tag1");
+        let mut new = processed_sites(1, "tag1", "tag1");
+        let site = new.sites.get_mut("site1").unwrap();
+        site.code_lines = 3;
+        site.removed_duplicates = Some(2);
+        site.languages = Some(BTreeMap::from([(
+            Language::En,
+            LanguageStats {
+                questions: 1,
+                words: 10,
+            },
+        )]));
+        new.errors = Some(HashMap::from([(
+            "site1".to_string(),
+            SiteErrors {
+                parse_errors: 4,
+                ..SiteErrors::default()
+            },
+        )]));
+
+        let differences = diff_processed_sites(&old, &new);
+
+        let counters: Vec<String> = differences.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            counters,
+            [
+                "~ site site1 code_lines: 0 -> 3",
+                "~ site site1 errors.parse_errors: 0 -> 4",
+                "~ site site1 languages.en.questions: 0 -> 1",
+                "~ site site1 languages.en.words: 0 -> 10",
+                "~ site site1 removed_duplicates: 0 -> 2",
+            ]
+        );
+    }
+}
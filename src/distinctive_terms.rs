@@ -0,0 +1,388 @@
+use std::{collections::HashMap, mem, path::PathBuf};
+
+use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ProcessingError,
+    input::get_site_name,
+    language::Language,
+    line::Line,
+    options::ProcessOptions,
+    processed_sites::{fold_lines, ProcessedSites},
+    question::count_tokens,
+};
+
+/// Cantidad por defecto de términos distintivos que se guardan en cada Tag o Site.
+pub const DEFAULT_DISTINCTIVE_TERMS: usize = 10;
+/// Cantidad mínima por defecto de preguntas del Tag o Site en las que tiene que aparecer un término para ser distintivo, de forma que no ganen las palabras mal escritas.
+pub const DEFAULT_MIN_TERM_QUESTIONS: usize = 2;
+
+/// Contra qué se compara cada conjunto de preguntas para elegir sus términos distintivos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TermsScope {
+    /// Los términos de cada Tag, comparados con las preguntas de su Site.
+    #[default]
+    Tag,
+    /// Los términos de cada Site, comparados con las preguntas de todos los Sites.
+    Site,
+}
+
+/// Configuración de los términos distintivos: cuántos se guardan, de qué y cuántas preguntas tienen que tenerlos como mínimo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DistinctiveTermsConfig {
+    pub top_n: usize,
+    pub scope: TermsScope,
+    pub min_questions: usize,
+}
+
+impl DistinctiveTermsConfig {
+    /// Configuración con la cantidad de términos y el alcance indicados, y el mínimo de preguntas por defecto.
+    pub fn new(top_n: usize, scope: TermsScope) -> DistinctiveTermsConfig {
+        DistinctiveTermsConfig {
+            top_n,
+            scope,
+            min_questions: DEFAULT_MIN_TERM_QUESTIONS,
+        }
+    }
+}
+
+impl Default for DistinctiveTermsConfig {
+    fn default() -> Self {
+        DistinctiveTermsConfig::new(DEFAULT_DISTINCTIVE_TERMS, TermsScope::default())
+    }
+}
+
+/// Un término distintivo con su puntaje TF-IDF.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DistinctiveTerm {
+    pub term: String,
+    pub score: f64,
+}
+
+/// Cantidad de veces que aparece un término y cantidad de preguntas en las que aparece.
+#[derive(Debug, Clone, Copy, Default)]
+struct TermCount {
+    occurrences: usize,
+    questions: usize,
+}
+
+/// Términos de un conjunto de preguntas, con la cantidad total de preguntas y de palabras.
+#[derive(Debug, Default)]
+struct Terms {
+    questions: usize,
+    tokens: usize,
+    counts: HashMap<String, TermCount>,
+}
+
+impl Terms {
+    fn add_question(&mut self, tokens: &[(String, usize)]) {
+        self.questions += 1;
+        tokens.iter().for_each(|(token, occurrences)| {
+            self.tokens += occurrences;
+            let count = self.counts.entry(token.to_string()).or_default();
+            count.occurrences += occurrences;
+            count.questions += 1;
+        });
+    }
+
+    fn merge(&mut self, other: Terms) {
+        self.questions += other.questions;
+        self.tokens += other.tokens;
+        other.counts.into_iter().for_each(|(token, other_count)| {
+            let count = self.counts.entry(token).or_default();
+            count.occurrences += other_count.occurrences;
+            count.questions += other_count.questions;
+        });
+    }
+
+    /// Los `config.top_n` términos de mayor TF-IDF, de mayor a menor y desempatando por nombre.
+    /// TF es la proporción de las palabras que son el término, e IDF el logaritmo de `questions` (las preguntas de referencia) sobre las que tienen el término según `document_frequency`.
+    /// Los términos que aparecen en todas las preguntas de referencia tienen puntaje 0 y no se incluyen.
+    fn distinctive(
+        &self,
+        questions: usize,
+        document_frequency: impl Fn(&str) -> usize,
+        config: &DistinctiveTermsConfig,
+    ) -> Vec<DistinctiveTerm> {
+        let mut terms: Vec<DistinctiveTerm> = self
+            .counts
+            .iter()
+            .filter(|(_, count)| count.questions >= config.min_questions)
+            .map(|(token, count)| {
+                let tf = count.occurrences as f64 / self.tokens as f64;
+                let idf = (questions as f64 / document_frequency(token).max(1) as f64).ln();
+                DistinctiveTerm {
+                    term: token.to_string(),
+                    score: tf * idf,
+                }
+            })
+            .filter(|term| term.score > 0.0)
+            .collect();
+        terms.sort_by(|term_1, term_2| {
+            term_2
+                .score
+                .total_cmp(&term_1.score)
+                .then_with(|| term_1.term.cmp(&term_2.term))
+        });
+        terms.truncate(config.top_n);
+        terms
+    }
+}
+
+/// Términos de un Site y, si el alcance es por Tag, de cada uno de sus Tags.
+#[derive(Debug, Default)]
+struct SiteTerms {
+    site: Terms,
+    tags: HashMap<String, Terms>,
+}
+
+impl SiteTerms {
+    fn merge(&mut self, other: SiteTerms) {
+        self.site.merge(other.site);
+        other.tags.into_iter().for_each(|(tag, terms)| {
+            self.tags.entry(tag).or_default().merge(terms);
+        });
+    }
+}
+
+/// Resultado parcial de la pasada de términos, que cada thread acumula como en el procesamiento principal.
+#[derive(Debug, Default)]
+struct TermsAccumulator {
+    sites: HashMap<String, SiteTerms>,
+}
+
+impl TermsAccumulator {
+    /// Suma los términos de una línea al Site indicado. Las líneas inválidas se ignoran, porque ya se manejaron según la política de errores en la primera pasada.
+    /// Las palabras se separan con el tokenizer de las opciones y, si se detectan los idiomas, las de las preguntas CJK por caracteres, igual que en la primera pasada.
+    fn add_line(
+        mut self,
+        sitename: &str,
+        line: &str,
+        options: &ProcessOptions,
+        scope: TermsScope,
+    ) -> Self {
+        let Ok(mut line) = Line::parse(line) else {
+            return self;
+        };
+        let language = options.languages.then(|| Language::detect(&line.texts));
+        let tokens = count_tokens(&line, options.tokenizer, language);
+        let site_terms = self.sites.entry(sitename.to_string()).or_default();
+        site_terms.site.add_question(&tokens);
+        if scope == TermsScope::Tag {
+            line.tags.sort_unstable();
+            line.tags.dedup();
+            line.tags.into_iter().for_each(|tag| {
+                site_terms
+                    .tags
+                    .entry(tag)
+                    .or_default()
+                    .add_question(&tokens);
+            });
+        }
+        self
+    }
+
+    fn merge(mut self, mut other: TermsAccumulator) -> TermsAccumulator {
+        if self.sites.len() < other.sites.len() {
+            mem::swap(&mut self, &mut other);
+        }
+        other.sites.into_iter().for_each(|(sitename, site_terms)| {
+            self.sites.entry(sitename).or_default().merge(site_terms);
+        });
+        self
+    }
+}
+
+impl ProcessedSites {
+    /// Hace una segunda pasada por los archivos JSON para calcular los términos distintivos por TF-IDF según la configuración: los de cada Tag respecto de su Site, o los de cada Site respecto de todos los Sites.
+    /// Se usan el tokenizer y el tamaño de los chunks de las opciones, y se ignoran las mismas preguntas que se excluyeron al procesar los Sites (casi duplicadas o repetidas), así que los términos son de las mismas preguntas que se contaron.
+    /// Los términos se guardan en el campo `distinctive_terms` de cada Tag o Site. Los Tags totales no tienen términos distintivos, porque cada Tag se compara con su propio Site.
+    /// Guarda en memoria la cantidad de cada palabra de cada Site (y de cada Tag, si el alcance es por Tag), así que con entradas grandes ocupa bastante más que el procesamiento principal.
+    pub fn process_distinctive_terms(
+        &mut self,
+        json_paths: &[PathBuf],
+        options: &ProcessOptions,
+        config: &DistinctiveTermsConfig,
+    ) -> Result<(), ProcessingError> {
+        let site_names: Vec<String> = json_paths.iter().map(|p| get_site_name(p)).collect();
+        let options = ProcessOptions {
+            excluded_questions: self
+                .excluded_questions
+                .clone()
+                .or_else(|| options.excluded_questions.clone()),
+            ..options.clone()
+        };
+        let accumulator = fold_lines(
+            json_paths,
+            options.chunk_size,
+            |accumulator: TermsAccumulator, file, number, line| {
+                let sitename = &site_names[file];
+                Ok(match line {
+                    Ok(line) if !options.is_excluded(&json_paths[file], number) => {
+                        accumulator.add_line(sitename, &line, &options, config.scope)
+                    }
+                    _ => accumulator,
+                })
+            },
+            TermsAccumulator::merge,
+        )?;
+
+        let terms = accumulator.sites;
+        match config.scope {
+            TermsScope::Tag => self.sites.par_iter_mut().for_each(|(sitename, site)| {
+                let Some(site_terms) = terms.get(sitename) else {
+                    return;
+                };
+                let document_frequency = |token: &str| {
+                    site_terms
+                        .site
+                        .counts
+                        .get(token)
+                        .map_or(0, |count| count.questions)
+                };
+                site.tags.iter_mut().for_each(|(tag_name, tag)| {
+                    if let Some(tag_terms) = site_terms.tags.get(tag_name) {
+                        tag.distinctive_terms = tag_terms.distinctive(
+                            site_terms.site.questions,
+                            document_frequency,
+                            config,
+                        );
+                    }
+                });
+            }),
+            TermsScope::Site => {
+                let questions = terms
+                    .values()
+                    .map(|site_terms| site_terms.site.questions)
+                    .sum();
+                let mut document_frequencies: HashMap<&str, usize> = HashMap::new();
+                terms.values().for_each(|site_terms| {
+                    site_terms.site.counts.iter().for_each(|(token, count)| {
+                        *document_frequencies.entry(token).or_insert(0) += count.questions;
+                    })
+                });
+                let document_frequency =
+                    |token: &str| document_frequencies.get(token).copied().unwrap_or(0);
+                self.sites.par_iter_mut().for_each(|(sitename, site)| {
+                    if let Some(site_terms) = terms.get(sitename) {
+                        site.distinctive_terms =
+                            site_terms
+                                .site
+                                .distinctive(questions, document_frequency, config);
+                    }
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(questions: &[&[(&str, usize)]]) -> Terms {
+        let mut terms = Terms::default();
+        questions.iter().for_each(|question| {
+            let tokens: Vec<(String, usize)> = question
+                .iter()
+                .map(|(token, count)| (token.to_string(), *count))
+                .collect();
+            terms.add_question(&tokens);
+        });
+        terms
+    }
+
+    #[test]
+    fn scores_terms_by_tf_idf() {
+        let tag = terms(&[
+            &[("the", 2), ("rust", 1)],
+            &[("the", 1), ("rust", 2), ("borrow", 1)],
+        ]);
+        let mut site = terms(&[&[("the", 1), ("python", 1)], &[("the", 1), ("python", 1)]]);
+        site.merge(terms(&[
+            &[("the", 2), ("rust", 1)],
+            &[("the", 1), ("rust", 2), ("borrow", 1)],
+        ]));
+        let config = DistinctiveTermsConfig::new(5, TermsScope::Tag);
+
+        let distinctive = tag.distinctive(
+            site.questions,
+            |token| site.counts.get(token).map_or(0, |count| count.questions),
+            &config,
+        );
+
+        // "the" aparece en todas las preguntas del Site, y "borrow" en una sola pregunta del Tag
+        assert_eq!(distinctive.len(), 1);
+        assert_eq!(distinctive[0].term, "rust");
+        assert!((distinctive[0].score - 3.0 / 7.0 * 2f64.ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ranks_by_score_and_name() {
+        let tag = terms(&[
+            &[("b", 1), ("a", 1), ("c", 2)],
+            &[("b", 1), ("a", 1), ("c", 2)],
+        ]);
+        let config = DistinctiveTermsConfig {
+            top_n: 2,
+            scope: TermsScope::Tag,
+            min_questions: 1,
+        };
+
+        let distinctive = tag.distinctive(4, |_| 2, &config);
+
+        let names: Vec<&str> = distinctive.iter().map(|term| term.term.as_str()).collect();
+        assert_eq!(names, ["c", "a"]);
+    }
+
+    #[test]
+    fn accumulates_lines_by_scope() {
+        let line = "{\"texts\": [\"Rust rust\", \"borrow\"], \"tags\": [\"b\", \"a\", \"b\"]}";
+        let add = |accumulator: TermsAccumulator, line, scope| {
+            accumulator.add_line("site", line, &ProcessOptions::default(), scope)
+        };
+        let accumulator = add(TermsAccumulator::default(), line, TermsScope::Tag);
+        let accumulator = add(accumulator, "no es json", TermsScope::Tag);
+        let other = add(TermsAccumulator::default(), line, TermsScope::Tag);
+        let accumulator = accumulator.merge(other);
+
+        let site_terms = accumulator.sites.get("site").unwrap();
+        assert_eq!(site_terms.site.questions, 2);
+        assert_eq!(site_terms.site.tokens, 6);
+        assert_eq!(site_terms.tags.len(), 2);
+        assert_eq!(site_terms.tags.get("b").unwrap().questions, 2);
+        assert_eq!(
+            site_terms.tags.get("a").unwrap().counts["rust"].occurrences,
+            4
+        );
+
+        let accumulator = add(TermsAccumulator::default(), line, TermsScope::Site);
+        let site_terms = accumulator.sites.get("site").unwrap();
+        assert_eq!(site_terms.site.questions, 1);
+        assert!(site_terms.tags.is_empty());
+    }
+
+    #[test]
+    fn splits_cjk_lines_like_the_first_pass() {
+        let line = "{\"texts\": [\"ファイルを読む\", \"Rust で読む\"], \"tags\": []}";
+        let add = |languages| {
+            let options = ProcessOptions {
+                languages,
+                ..ProcessOptions::default()
+            };
+            TermsAccumulator::default().add_line("site", line, &options, TermsScope::Site)
+        };
+
+        let accumulator = add(false);
+        assert_eq!(accumulator.sites["site"].site.tokens, 3);
+
+        let accumulator = add(true);
+        let site_terms = &accumulator.sites["site"].site;
+        assert_eq!(site_terms.tokens, 11);
+        assert_eq!(site_terms.counts["読"].occurrences, 2);
+        assert_eq!(site_terms.counts["rust"].occurrences, 1);
+    }
+}
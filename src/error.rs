@@ -0,0 +1,270 @@
+use std::{error::Error, fmt, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Cantidad máxima de números de línea con errores que se guardan por Site.
+const MAX_ERROR_SAMPLE: usize = 10;
+
+/// Errores que pueden ocurrir al obtener las entradas o procesarlas.
+#[derive(Debug)]
+pub enum ProcessingError {
+    /// No se pudo expandir una entrada (directorio, archivo o patrón glob).
+    Directory { input: String, source: io::Error },
+    /// No se pudo abrir un archivo, o leer una de sus líneas si se indica `line`.
+    Io {
+        path: PathBuf,
+        line: Option<usize>,
+        source: io::Error,
+    },
+    /// Una línea no es un JSON válido.
+    Json {
+        path: PathBuf,
+        line: usize,
+        source: serde_json::Error,
+    },
+    /// A una línea le falta un campo obligatorio (`texts` o `tags`).
+    MissingField {
+        path: PathBuf,
+        line: usize,
+        field: &'static str,
+    },
+    /// No se pudo crear el pool de threads de Rayon.
+    ThreadPool(rayon::ThreadPoolBuildError),
+}
+
+impl fmt::Display for ProcessingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessingError::Directory { input, source } => write!(
+                f,
+                "No se pudieron obtener los archivos JSON de {}: {}",
+                input, source
+            ),
+            ProcessingError::Io {
+                path,
+                line: Some(line),
+                source,
+            } => write!(
+                f,
+                "No se pudo leer la línea {} de {}: {}",
+                line,
+                path.display(),
+                source
+            ),
+            ProcessingError::Io {
+                path,
+                line: None,
+                source,
+            } => write!(
+                f,
+                "No se pudo leer el archivo {}: {}",
+                path.display(),
+                source
+            ),
+            ProcessingError::Json { path, line, source } => write!(
+                f,
+                "No se pudo parsear la línea {} de {} a un Line: {}",
+                line,
+                path.display(),
+                source
+            ),
+            ProcessingError::MissingField { path, line, field } => write!(
+                f,
+                "A la línea {} de {} le falta el campo {}",
+                line,
+                path.display(),
+                field
+            ),
+            ProcessingError::ThreadPool(source) => write!(
+                f,
+                "No se pudo iniciar Rayon con la cantidad de threads indicada: {}",
+                source
+            ),
+        }
+    }
+}
+
+impl Error for ProcessingError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProcessingError::Directory { source, .. } | ProcessingError::Io { source, .. } => {
+                Some(source)
+            }
+            ProcessingError::Json { source, .. } => Some(source),
+            ProcessingError::MissingField { .. } => None,
+            ProcessingError::ThreadPool(source) => Some(source),
+        }
+    }
+}
+
+impl ProcessingError {
+    /// Indica si el error corta la lectura del resto del archivo, como una falla de lectura que no es una línea con UTF-8 inválido (por ejemplo un archivo comprimido truncado o corrupto).
+    pub fn is_file_error(&self) -> bool {
+        matches!(self, ProcessingError::Io { source, .. } if source.kind() != io::ErrorKind::InvalidData)
+    }
+}
+
+impl From<rayon::ThreadPoolBuildError> for ProcessingError {
+    fn from(error: rayon::ThreadPoolBuildError) -> Self {
+        ProcessingError::ThreadPool(error)
+    }
+}
+
+/// Qué hacer cuando una línea no se puede leer o parsear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Se corta el procesamiento y se devuelve el primer error encontrado.
+    #[default]
+    FailFast,
+    /// Se saltea la línea y se cuenta en `skipped_lines`.
+    SkipAndCount,
+    /// Se saltea la línea, se cuenta y se informa por stderr con el archivo y número de línea.
+    SkipAndLog,
+}
+
+/// Errores encontrados al leer las líneas de un Site: cuántas no se pudieron leer, cuántas no eran JSON válido, a cuántas les faltaba `texts` o `tags`, y los números de las primeras líneas con errores.
+/// Los errores que cortaron la lectura de un archivo se guardan aparte en `file_errors`, porque las líneas que faltan no se pueden contar.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct SiteErrors {
+    pub unreadable_lines: usize,
+    pub parse_errors: usize,
+    pub missing_texts: usize,
+    pub missing_tags: usize,
+    pub sample_lines: Vec<usize>,
+    /// Mensajes de los errores que cortaron la lectura de un archivo, con la línea en la que se cortó. Solo se serializa si hay alguno.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub file_errors: Vec<String>,
+}
+
+impl SiteErrors {
+    /// Crea un SiteErrors con un único error, el de la línea indicada.
+    pub fn from_error(error: &ProcessingError) -> SiteErrors {
+        let mut site_errors = SiteErrors::default();
+        match error {
+            error if error.is_file_error() => site_errors.file_errors.push(error.to_string()),
+            ProcessingError::Io {
+                line: Some(line), ..
+            } => {
+                site_errors.unreadable_lines = 1;
+                site_errors.sample_lines.push(*line);
+            }
+            ProcessingError::Json { line, .. } => {
+                site_errors.parse_errors = 1;
+                site_errors.sample_lines.push(*line);
+            }
+            ProcessingError::MissingField { line, field, .. } => {
+                if *field == "texts" {
+                    site_errors.missing_texts = 1;
+                } else {
+                    site_errors.missing_tags = 1;
+                }
+                site_errors.sample_lines.push(*line);
+            }
+            _ => {}
+        }
+        site_errors
+    }
+
+    /// Cantidad total de líneas con errores, sin contar las que faltan por los errores de archivo.
+    pub fn total(&self) -> usize {
+        self.unreadable_lines + self.parse_errors + self.missing_texts + self.missing_tags
+    }
+
+    /// Suma otro SiteErrors a sí mismo, in-place. De la muestra se conservan los números de línea más chicos.
+    pub fn add(&mut self, other: &SiteErrors) {
+        self.unreadable_lines += other.unreadable_lines;
+        self.parse_errors += other.parse_errors;
+        self.missing_texts += other.missing_texts;
+        self.missing_tags += other.missing_tags;
+        self.sample_lines.extend(&other.sample_lines);
+        self.sample_lines.sort_unstable();
+        self.sample_lines.truncate(MAX_ERROR_SAMPLE);
+        self.file_errors.extend(other.file_errors.iter().cloned());
+        self.file_errors.sort_unstable();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn displays_file_and_line() {
+        let error = ProcessingError::MissingField {
+            path: PathBuf::from("data/anime.stackexchange.com.jsonl"),
+            line: 3,
+            field: "tags",
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "A la línea 3 de data/anime.stackexchange.com.jsonl le falta el campo tags"
+        );
+        assert!(error.source().is_none());
+    }
+
+    #[test]
+    fn site_errors_count_each_kind() {
+        let mut site_errors = SiteErrors::from_error(&ProcessingError::MissingField {
+            path: PathBuf::from("a.jsonl"),
+            line: 3,
+            field: "texts",
+        });
+        site_errors.add(&SiteErrors::from_error(&ProcessingError::Io {
+            path: PathBuf::from("a.jsonl"),
+            line: Some(1),
+            source: io::Error::new(io::ErrorKind::InvalidData, "UTF-8 inválido"),
+        }));
+
+        assert_eq!(site_errors.missing_texts, 1);
+        assert_eq!(site_errors.unreadable_lines, 1);
+        assert_eq!(site_errors.parse_errors, 0);
+        assert_eq!(site_errors.missing_tags, 0);
+        assert_eq!(site_errors.total(), 2);
+        assert_eq!(site_errors.sample_lines, [1, 3]);
+
+        site_errors.add(&SiteErrors::from_error(&ProcessingError::Io {
+            path: PathBuf::from("a.jsonl.gz"),
+            line: Some(7),
+            source: io::Error::new(io::ErrorKind::UnexpectedEof, "truncado"),
+        }));
+        assert_eq!(site_errors.total(), 2);
+        assert_eq!(
+            site_errors.file_errors,
+            ["No se pudo leer la línea 7 de a.jsonl.gz: truncado"]
+        );
+    }
+
+    #[test]
+    fn site_errors_sample_is_capped() {
+        let mut site_errors = SiteErrors::default();
+        for line in (1..=2 * MAX_ERROR_SAMPLE).rev() {
+            site_errors.add(&SiteErrors::from_error(&ProcessingError::MissingField {
+                path: PathBuf::from("a.jsonl"),
+                line,
+                field: "tags",
+            }));
+        }
+
+        assert_eq!(site_errors.missing_tags, 2 * MAX_ERROR_SAMPLE);
+        assert_eq!(
+            site_errors.sample_lines,
+            (1..=MAX_ERROR_SAMPLE).collect::<Vec<usize>>()
+        );
+    }
+
+    #[test]
+    fn keeps_the_source_error() {
+        let error = ProcessingError::Io {
+            path: PathBuf::from("data"),
+            line: None,
+            source: io::Error::new(io::ErrorKind::NotFound, "no existe"),
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "No se pudo leer el archivo data: no existe"
+        );
+        assert!(error.source().is_some());
+    }
+}
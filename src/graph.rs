@@ -0,0 +1,119 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use crate::{cooccurrence::TagPairs, tag::Tag};
+
+/// Escribe los pares como una lista de aristas, una por línea: `tag_1<TAB>tag_2<TAB>cantidad`.
+pub fn write_edge_list(pairs: &TagPairs, writer: &mut impl Write) -> io::Result<()> {
+    pairs
+        .edges()
+        .into_iter()
+        .try_for_each(|(tag_1, tag_2, count)| writeln!(writer, "{}\t{}\t{}", tag_1, tag_2, count))
+}
+
+/// Escribe los pares como un grafo GraphML no dirigido. Cada nodo es un tag con su cantidad de preguntas (si está en `tags`), y cada arista tiene como peso la cantidad de preguntas en las que aparecen juntos.
+pub fn write_graphml(
+    pairs: &TagPairs,
+    tags: &HashMap<String, Tag>,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    let edges = pairs.edges();
+    let mut nodes: Vec<&str> = edges
+        .iter()
+        .flat_map(|(tag_1, tag_2, _)| [*tag_1, *tag_2])
+        .collect();
+    nodes.sort_unstable();
+    nodes.dedup();
+
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        writer,
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">"
+    )?;
+    writeln!(
+        writer,
+        "  <key id=\"questions\" for=\"node\" attr.name=\"questions\" attr.type=\"long\"/>"
+    )?;
+    writeln!(
+        writer,
+        "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"long\"/>"
+    )?;
+    writeln!(writer, "  <graph id=\"tags\" edgedefault=\"undirected\">")?;
+    for node in nodes {
+        match tags.get(node) {
+            Some(tag) => writeln!(
+                writer,
+                "    <node id=\"{}\"><data key=\"questions\">{}</data></node>",
+                escape_xml(node),
+                tag.questions
+            )?,
+            None => writeln!(writer, "    <node id=\"{}\"/>", escape_xml(node))?,
+        }
+    }
+    for (tag_1, tag_2, count) in edges {
+        writeln!(
+            writer,
+            "    <edge source=\"{}\" target=\"{}\"><data key=\"weight\">{}</data></edge>",
+            escape_xml(tag_1),
+            escape_xml(tag_2),
+            count
+        )?;
+    }
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")
+}
+
+/// Escapa los caracteres especiales de XML de un texto que va dentro de un atributo.
+fn escape_xml(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&apos;".to_string(),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs() -> TagPairs {
+        let mut pairs = TagPairs::default();
+        pairs.add_question(&["c#".to_string(), "linq".to_string()]);
+        pairs.add_question(&["c#".to_string(), "linq".to_string(), "r&d".to_string()]);
+        pairs
+    }
+
+    #[test]
+    fn writes_edge_list() {
+        let mut output = Vec::new();
+        write_edge_list(&pairs(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "c#\tlinq\t2\nc#\tr&d\t1\nlinq\tr&d\t1\n"
+        );
+    }
+
+    #[test]
+    fn writes_graphml() {
+        let tags = HashMap::from([("c#".to_string(), Tag::new(5, 100))]);
+        let mut output = Vec::new();
+        write_graphml(&pairs(), &tags, &mut output).unwrap();
+        let graphml = String::from_utf8(output).unwrap();
+
+        assert!(graphml.contains("<node id=\"c#\"><data key=\"questions\">5</data></node>"));
+        assert!(graphml.contains("<node id=\"r&amp;d\"/>"));
+        assert!(graphml
+            .contains("<edge source=\"c#\" target=\"linq\"><data key=\"weight\">2</data></edge>"));
+        assert_eq!(graphml.matches("<node ").count(), 3);
+        assert_eq!(graphml.matches("<edge ").count(), 3);
+        assert!(graphml.ends_with("</graphml>\n"));
+    }
+}
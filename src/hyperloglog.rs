@@ -0,0 +1,207 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+/// Bits del hash que eligen el registro. Con 2^12 registros el error relativo típico de la estimación es 1.04 / 64, alrededor de 1.6%.
+const PRECISION: u32 = 12;
+/// Cantidad de registros del sketch denso.
+const REGISTERS: usize = 1 << PRECISION;
+/// Bits del hash que eligen el registro en la forma dispersa. Con tantos registros casi no hay colisiones, así que los conjuntos chicos se cuentan prácticamente exacto.
+const SPARSE_PRECISION: u32 = 25;
+/// Cantidad máxima de entradas de la forma dispersa antes de pasar a la forma densa, de forma que nunca ocupe más que ella.
+const SPARSE_LIMIT: usize = REGISTERS / 8;
+
+/// Sketch HyperLogLog para estimar la cantidad de elementos distintos sin guardarlos.
+/// Cada registro guarda la máxima posición del primer bit en 1 de los hashes que caen en él, así que unir dos sketches es tomar el máximo de cada registro, sin importar el orden.
+/// Como en HyperLogLog++, mientras tiene pocos elementos los guarda en forma dispersa con más precisión, porque la mayoría de los Tags tienen vocabularios chicos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    registers: Registers,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Registers {
+    /// Pares (registro, valor) con SPARSE_PRECISION bits de registro, ordenados por registro.
+    Sparse(Vec<(u32, u8)>),
+    Dense(Vec<u8>),
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog {
+            registers: Registers::Sparse(vec![]),
+        }
+    }
+}
+
+impl HyperLogLog {
+    /// Agrega un elemento al sketch.
+    pub fn add(&mut self, item: &str) {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        let hash = hasher.finish();
+        match &mut self.registers {
+            Registers::Sparse(entries) => {
+                let index = (hash >> (64 - SPARSE_PRECISION)) as u32;
+                insert_sparse(entries, index, rank(hash, SPARSE_PRECISION));
+                if entries.len() > SPARSE_LIMIT {
+                    self.densify();
+                }
+            }
+            Registers::Dense(registers) => {
+                let index = (hash >> (64 - PRECISION)) as usize;
+                registers[index] = registers[index].max(rank(hash, PRECISION));
+            }
+        }
+    }
+
+    /// Une otro sketch a sí mismo, in-place.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        match (&mut self.registers, &other.registers) {
+            (Registers::Sparse(entries), Registers::Sparse(other_entries)) => {
+                other_entries
+                    .iter()
+                    .for_each(|(index, rank)| insert_sparse(entries, *index, *rank));
+                if entries.len() > SPARSE_LIMIT {
+                    self.densify();
+                }
+            }
+            (Registers::Dense(registers), Registers::Sparse(other_entries)) => other_entries
+                .iter()
+                .for_each(|(index, rank)| set_dense(registers, *index, *rank)),
+            (_, Registers::Dense(other_registers)) => {
+                self.densify();
+                if let Registers::Dense(registers) = &mut self.registers {
+                    registers
+                        .iter_mut()
+                        .zip(other_registers.iter())
+                        .for_each(|(rank, other_rank)| *rank = (*rank).max(*other_rank));
+                }
+            }
+        }
+    }
+
+    /// Estima la cantidad de elementos distintos agregados. En la forma dispersa, y en la densa con registros en cero, usa conteo lineal.
+    pub fn estimate(&self) -> usize {
+        let estimate = match &self.registers {
+            Registers::Sparse(entries) => {
+                let m = (1u64 << SPARSE_PRECISION) as f64;
+                m * (m / (m - entries.len() as f64)).ln()
+            }
+            Registers::Dense(registers) => {
+                let m = REGISTERS as f64;
+                let (zeros, sum) = registers.iter().fold((0, 0.0), |(zeros, sum), rank| {
+                    (
+                        zeros + (*rank == 0) as usize,
+                        sum + 2f64.powi(-(*rank as i32)),
+                    )
+                });
+                let raw = 0.7213 / (1.0 + 1.079 / m) * m * m / sum;
+                if raw <= 2.5 * m && zeros > 0 {
+                    m * (m / zeros as f64).ln()
+                } else {
+                    raw
+                }
+            }
+        };
+        estimate.round() as usize
+    }
+
+    /// Pasa los registros a la forma densa, si no lo estaban.
+    fn densify(&mut self) {
+        if let Registers::Sparse(entries) = &self.registers {
+            let mut registers = vec![0; REGISTERS];
+            entries
+                .iter()
+                .for_each(|(index, rank)| set_dense(&mut registers, *index, *rank));
+            self.registers = Registers::Dense(registers);
+        }
+    }
+}
+
+/// Posición del primer bit en 1 del hash después de los `precision` bits que eligen el registro.
+fn rank(hash: u64, precision: u32) -> u8 {
+    ((hash << precision) | (1 << (precision - 1))).leading_zeros() as u8 + 1
+}
+
+/// Actualiza la entrada dispersa del registro `index` si `rank` es mayor a la que tiene, o la agrega.
+fn insert_sparse(entries: &mut Vec<(u32, u8)>, index: u32, rank: u8) {
+    match entries.binary_search_by_key(&index, |(i, _)| *i) {
+        Ok(position) => entries[position].1 = entries[position].1.max(rank),
+        Err(position) => entries.insert(position, (index, rank)),
+    }
+}
+
+/// Pasa una entrada dispersa a su registro denso. Los bits del índice disperso que sobran pasan a ser parte del valor del registro.
+fn set_dense(registers: &mut [u8], index: u32, sparse_rank: u8) {
+    let extra_bits = SPARSE_PRECISION - PRECISION;
+    let dense_index = (index >> extra_bits) as usize;
+    let extra = index & ((1 << extra_bits) - 1);
+    let rank = if extra == 0 {
+        extra_bits as u8 + sparse_rank
+    } else {
+        (extra.leading_zeros() - (32 - extra_bits)) as u8 + 1
+    };
+    registers[dense_index] = registers[dense_index].max(rank);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relative_error(estimate: usize, exact: usize) -> f64 {
+        (estimate as f64 - exact as f64).abs() / exact as f64
+    }
+
+    #[test]
+    fn estimates_small_and_large_cardinalities() {
+        let mut sketch = HyperLogLog::default();
+        assert_eq!(sketch.estimate(), 0);
+
+        (0..100).for_each(|i| sketch.add(&format!("word{}", i)));
+        (0..100).for_each(|i| sketch.add(&format!("word{}", i)));
+        assert!(matches!(sketch.registers, Registers::Sparse(_)));
+        assert_eq!(sketch.estimate(), 100);
+
+        (100..100_000).for_each(|i| sketch.add(&format!("word{}", i)));
+        assert!(matches!(sketch.registers, Registers::Dense(_)));
+        assert!(relative_error(sketch.estimate(), 100_000) < 0.05);
+    }
+
+    #[test]
+    fn densify_keeps_the_dense_registers() {
+        let mut sparse = HyperLogLog::default();
+        let mut dense = HyperLogLog {
+            registers: Registers::Dense(vec![0; REGISTERS]),
+        };
+        (0..SPARSE_LIMIT).for_each(|i| {
+            sparse.add(&i.to_string());
+            dense.add(&i.to_string());
+        });
+        sparse.densify();
+
+        assert_eq!(sparse, dense);
+    }
+
+    #[test]
+    fn merge_is_the_same_as_adding_everything() {
+        let mut all = HyperLogLog::default();
+        let mut sparse = HyperLogLog::default();
+        let mut dense = HyperLogLog::default();
+        (0..50).for_each(|i| {
+            all.add(&i.to_string());
+            sparse.add(&i.to_string());
+        });
+        (25..5000).for_each(|i| {
+            all.add(&i.to_string());
+            dense.add(&i.to_string());
+        });
+
+        let mut merged = sparse.clone();
+        merged.merge(&dense);
+        assert_eq!(merged, all);
+        dense.merge(&sparse);
+        assert_eq!(dense, all);
+    }
+}
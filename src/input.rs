@@ -0,0 +1,326 @@
+use std::{
+    fs::{read_dir, File},
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use flate2::read::MultiGzDecoder;
+
+/// Extensiones de los archivos de entrada soportados. Los comprimidos se descomprimen como stream al leerlos.
+const JSON_EXTENSIONS: [&str; 3] = [".jsonl", ".jsonl.gz", ".jsonl.zst"];
+
+/// Tipo de compresión de un archivo de entrada, según su extensión.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Obtiene la compresión de un archivo a partir de su nombre.
+    pub fn from_path(path: &Path) -> Compression {
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        if file_name.ends_with(".gz") {
+            Compression::Gzip
+        } else if file_name.ends_with(".zst") {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// Indica si el path corresponde a un archivo JSON a procesar (comprimido o no).
+pub fn is_json_path(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| JSON_EXTENSIONS.iter().any(|ext| name.ends_with(ext)))
+}
+
+/// Obtiene el nombre del Site a partir del path del archivo, sacándole la extensión completa (por ejemplo `.jsonl.gz`).
+pub fn get_site_name(path: &Path) -> String {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    JSON_EXTENSIONS
+        .iter()
+        .rev()
+        .find_map(|ext| file_name.strip_suffix(ext))
+        .unwrap_or(file_name)
+        .to_string()
+}
+
+/// Indica si la entrada es un patrón glob (contiene `*`, `?` o `[`).
+pub fn is_glob_pattern(input: &str) -> bool {
+    input.contains(['*', '?', '['])
+}
+
+/// Expande una entrada indicada en tiempo de ejecución a los paths de los archivos JSON que representa.
+/// Puede ser un directorio (recorrido recursivamente si se indica), un archivo individual o un patrón glob.
+pub fn expand_input(input: &str, recursive: bool) -> io::Result<Vec<PathBuf>> {
+    if is_glob_pattern(input) {
+        return expand_glob(input);
+    }
+    let path = PathBuf::from(input);
+    if path.is_dir() {
+        let mut paths = vec![];
+        collect_dir(&path, recursive, &mut paths)?;
+        Ok(paths.into_iter().filter(|p| is_json_path(p)).collect())
+    } else if path.is_file() {
+        Ok(vec![path])
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No existe la entrada {}", input),
+        ))
+    }
+}
+
+/// Agrega a `paths` los archivos del directorio indicado, bajando a los subdirectorios si `recursive` es true.
+/// Los links simbólicos a directorios no se siguen, para que un ciclo de links no haga la recursión infinita. Los links a archivos sí se agregan.
+fn collect_dir(dir: &Path, recursive: bool, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if recursive {
+                collect_dir(&path, recursive, paths)?;
+            }
+        } else if !(file_type.is_symlink() && path.is_dir()) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Expande un patrón glob a los archivos JSON que lo cumplen. Se recorre a partir del directorio más largo del patrón que no tiene comodines.
+/// `*` y `?` no atraviesan separadores de directorio, mientras que `**` sí.
+/// Si ningún archivo cumple el patrón (o su directorio no existe), se devuelve un error NotFound, igual que con una entrada que no existe.
+fn expand_glob(pattern: &str) -> io::Result<Vec<PathBuf>> {
+    let base: PathBuf = Path::new(pattern)
+        .components()
+        .take_while(|c| !is_glob_pattern(&c.as_os_str().to_string_lossy()))
+        .collect();
+    let base = if base.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        base
+    };
+    let mut candidates = vec![];
+    if base.is_dir() {
+        collect_dir(&base, true, &mut candidates)?;
+    }
+    let stripped = pattern.strip_prefix("./").unwrap_or(pattern);
+    let paths: Vec<PathBuf> = candidates
+        .into_iter()
+        .filter(|p| {
+            let path = p.to_string_lossy();
+            let path = path.strip_prefix("./").unwrap_or(&path);
+            glob_match(stripped.as_bytes(), path.as_bytes()) && is_json_path(p)
+        })
+        .collect();
+    if paths.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Ningún archivo cumple el patrón {}", pattern),
+        ));
+    }
+    Ok(paths)
+}
+
+/// Compara un texto contra un patrón glob que admite `*`, `**`, `?` y clases de caracteres `[...]`.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => {
+            let rest = pattern[2..].strip_prefix(b"/").unwrap_or(&pattern[2..]);
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        Some(b'*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob_match(&pattern[1..], &text[i..])),
+        Some(b'?') => !text.is_empty() && text[0] != b'/' && glob_match(&pattern[1..], &text[1..]),
+        Some(b'[') => match (pattern.iter().position(|&c| c == b']'), text.first()) {
+            (Some(end), Some(&c)) => {
+                let class = &pattern[1..end];
+                let (negated, class) = match class.first() {
+                    Some(b'!') | Some(b'^') => (true, &class[1..]),
+                    _ => (false, class),
+                };
+                let matches = class.iter().enumerate().any(|(i, &start)| {
+                    if class.get(i + 1) == Some(&b'-') && i + 2 < class.len() {
+                        (start..=class[i + 2]).contains(&c)
+                    } else {
+                        start == c
+                    }
+                });
+                matches != negated && glob_match(&pattern[end + 1..], &text[1..])
+            }
+            _ => false,
+        },
+        Some(&c) => text.first() == Some(&c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Abre el archivo indicado y devuelve un reader que lo descomprime como stream si hace falta.
+pub fn open_reader(path: &Path) -> io::Result<Box<dyn BufRead + Send>> {
+    let file = File::open(path)?;
+    let reader: Box<dyn Read + Send> = match Compression::from_path(path) {
+        Compression::None => return Ok(Box::new(BufReader::new(file))),
+        Compression::Gzip => Box::new(MultiGzDecoder::new(file)),
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(file)?),
+    };
+    Ok(Box::new(BufReader::new(reader)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn detects_json_paths() {
+        assert!(is_json_path(&PathBuf::from(
+            "data/anime.stackexchange.com.jsonl"
+        )));
+        assert!(is_json_path(&PathBuf::from(
+            "data/anime.stackexchange.com.jsonl.gz"
+        )));
+        assert!(is_json_path(&PathBuf::from(
+            "data/anime.stackexchange.com.jsonl.zst"
+        )));
+        assert!(!is_json_path(&PathBuf::from(
+            "data/anime.stackexchange.com.json"
+        )));
+        assert!(!is_json_path(&PathBuf::from(
+            "data/anime.stackexchange.com.gz"
+        )));
+    }
+
+    #[test]
+    fn site_name_strips_compound_extension() {
+        assert_eq!(
+            get_site_name(&PathBuf::from("data/anime.stackexchange.com.jsonl")),
+            "anime.stackexchange.com"
+        );
+        assert_eq!(
+            get_site_name(&PathBuf::from("data/anime.stackexchange.com.jsonl.gz")),
+            "anime.stackexchange.com"
+        );
+        assert_eq!(
+            get_site_name(&PathBuf::from("data/anime.stackexchange.com.jsonl.zst")),
+            "anime.stackexchange.com"
+        );
+    }
+
+    #[test]
+    fn glob_matches_patterns() {
+        assert!(glob_match(
+            b"data/*.jsonl",
+            b"data/anime.stackexchange.com.jsonl"
+        ));
+        assert!(!glob_match(b"data/*.jsonl", b"data/sub/anime.jsonl"));
+        assert!(glob_match(b"data/**/*.jsonl", b"data/sub/anime.jsonl"));
+        assert!(glob_match(b"data/**/*.jsonl", b"data/anime.jsonl"));
+        assert!(glob_match(b"data/a?ime.jsonl", b"data/anime.jsonl"));
+        assert!(glob_match(b"data/[a-c]*.jsonl", b"data/anime.jsonl"));
+        assert!(!glob_match(b"data/[!a]*.jsonl", b"data/anime.jsonl"));
+    }
+
+    #[test]
+    fn expands_directories_files_and_globs() {
+        let test_data = concat!(env!("CARGO_MANIFEST_DIR"), "/test_data");
+
+        assert_eq!(expand_input(test_data, false).unwrap().len(), 3);
+        assert_eq!(
+            expand_input(
+                &format!("{}/anime.stackexchange.com.jsonl", test_data),
+                false
+            )
+            .unwrap()
+            .len(),
+            1
+        );
+        assert_eq!(
+            expand_input(&format!("{}/an*.jsonl", test_data), false)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(expand_input(&format!("{}/no_existe", test_data), false).is_err());
+    }
+
+    #[test]
+    fn globs_without_matches_are_errors() {
+        let test_data = concat!(env!("CARGO_MANIFEST_DIR"), "/test_data");
+
+        let error = expand_input(&format!("{}/zz*.jsonl", test_data), false).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(error.to_string().contains("zz*.jsonl"));
+        let error = expand_input(&format!("{}/no_existe/*.jsonl", test_data), false).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn does_not_follow_directory_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub_dir = dir.path().join("sub");
+        std::fs::create_dir(&sub_dir).unwrap();
+        std::fs::write(dir.path().join("a.jsonl"), "").unwrap();
+        std::fs::write(sub_dir.join("b.jsonl"), "").unwrap();
+        std::os::unix::fs::symlink(dir.path(), sub_dir.join("loop")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("a.jsonl"), sub_dir.join("link.jsonl")).unwrap();
+
+        let dir_str = dir.path().to_str().unwrap();
+        assert_eq!(expand_input(dir_str, true).unwrap().len(), 3);
+        assert_eq!(
+            expand_input(&format!("{}/**/*.jsonl", dir_str), false)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn expands_directories_recursively() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub_dir = dir.path().join("sub");
+        std::fs::create_dir(&sub_dir).unwrap();
+        std::fs::write(dir.path().join("a.jsonl"), "").unwrap();
+        std::fs::write(sub_dir.join("b.jsonl.gz"), "").unwrap();
+        std::fs::write(sub_dir.join("c.txt"), "").unwrap();
+
+        let dir_str = dir.path().to_str().unwrap();
+        assert_eq!(expand_input(dir_str, false).unwrap().len(), 1);
+        assert_eq!(expand_input(dir_str, true).unwrap().len(), 2);
+        assert_eq!(
+            expand_input(&format!("{}/**/*.jsonl*", dir_str), false)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn compression_from_extension() {
+        assert_eq!(
+            Compression::from_path(&PathBuf::from("a.jsonl")),
+            Compression::None
+        );
+        assert_eq!(
+            Compression::from_path(&PathBuf::from("a.jsonl.gz")),
+            Compression::Gzip
+        );
+        assert_eq!(
+            Compression::from_path(&PathBuf::from("a.jsonl.zst")),
+            Compression::Zstd
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Cantidad máxima de caracteres de cada pregunta que se miran para detectar su idioma.
+const SAMPLE_CHARS: usize = 1000;
+
+/// Idiomas que se pueden detectar. Los que usan el alfabeto latino se distinguen por trigramas de caracteres, y el resto por su sistema de escritura.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    En,
+    Es,
+    Pt,
+    Fr,
+    De,
+    Ru,
+    Ja,
+    Zh,
+    Ko,
+    /// Sin letras, con letras de otro sistema de escritura (griego, árabe, hebreo, devanagari...), o sin ningún trigrama conocido.
+    Unknown,
+}
+
+/// Idiomas con alfabeto latino, en el mismo orden que sus perfiles.
+const LATIN_LANGUAGES: [Language; 5] = [
+    Language::En,
+    Language::Es,
+    Language::Pt,
+    Language::Fr,
+    Language::De,
+];
+
+/// Trigramas más frecuentes de cada idioma con alfabeto latino, de mayor a menor frecuencia. Los espacios marcan el comienzo o el final de una palabra.
+const PROFILES: [&[&str]; 5] = [
+    &[
+        " th", "the", "he ", " an", "and", "nd ", "ing", "ng ", " to", "to ", " of", "of ", " in",
+        "ion", "tio", "ed ", " is", "is ", " it", "it ", "hat", "tha", "at ", " wh", "you", " yo",
+        "ou ", "for", " fo", " be", "er ", "re ", "ent", "ter", "ly ", "ere", "her", "wit", "ith",
+        " wi", "thi", "his", "how", " ho", "ow ",
+    ],
+    &[
+        " de", "de ", " la", "la ", " qu", "que", "ue ", "os ", " el", "el ", "es ", " en", "en ",
+        "as ", "ión", "ció", "ón ", " co", "con", " lo", "los", "ado", "do ", " se", " pa", "par",
+        "ara", "ra ", " po", "por", " un", "una", "na ", "est", " es", "nte", "ar ", "ero", "cio",
+        "sta", "mos", "tar", " pe", "per", "ier",
+    ],
+    &[
+        " de", "de ", "os ", " qu", "que", "ue ", " co", "ão ", "ção", "çõe", " do", "do ", " da",
+        "da ", " em", "em ", " pa", "par", "ara", "com", "om ", "ent", " nã", "não", "as ", " se",
+        "nte", " um", "um ", "uma", "ma ", "es ", " o ", " a ", "ar ", "ado", "est", "men", "nto",
+        "ões", "vel", "ver", " vo", "voc", "ocê",
+    ],
+    &[
+        " de", "de ", "es ", " le", "le ", "ent", " la", "la ", " qu", "que", "ue ", "les", " et",
+        "et ", "ion", "nt ", " un", "re ", "tio", "ne ", " pa", " po", "pou", "our", "ur ", " ce",
+        "est", " es", "st ", "on ", " je", "je ", "ai ", "des", " d'", "ait", " pr", "eur", " co",
+        "ons", "ous", " vo", "vou", "ell", " à ",
+    ],
+    &[
+        "en ", "er ", " di", "die", "ie ", "der", " de", "ch ", "ich", "sch", "ein", " ei", "und",
+        " un", "nd ", "ine", "den", "ten", "cht", " ic", "ist", " is", "st ", " da", "das", "as ",
+        " zu", "zu ", "nic", " mi", "mit", "it ", "gen", "ung", "ber", "auf", " au", "eit", "ben",
+        " we", "wie", "ken", "hen", " ni", "ode",
+    ],
+];
+
+impl Language {
+    /// Detecta el idioma de los texts de una pregunta, mirando solo sus primeros caracteres.
+    /// Primero se elige el sistema de escritura con más letras: kana (con o sin kanji) es japonés, hangul coreano, los ideogramas solos chino, el cirílico ruso y cualquier otro es Unknown.
+    /// Si predomina el alfabeto latino, se elige el idioma cuyo perfil de trigramas más se parece al de la pregunta.
+    pub fn detect(texts: &[String]) -> Language {
+        let sample: Vec<char> = texts
+            .iter()
+            .flat_map(|text| text.chars().chain([' ']))
+            .take(SAMPLE_CHARS)
+            .collect();
+        let mut scripts = ScriptCounts::default();
+        sample.iter().for_each(|c| scripts.add(*c));
+
+        let max = scripts
+            .latin
+            .max(scripts.cyrillic)
+            .max(scripts.kana + scripts.han)
+            .max(scripts.hangul)
+            .max(scripts.other);
+        if max == 0 {
+            Language::Unknown
+        } else if max == scripts.kana + scripts.han && scripts.kana > 0 {
+            Language::Ja
+        } else if max == scripts.kana + scripts.han {
+            Language::Zh
+        } else if max == scripts.hangul {
+            Language::Ko
+        } else if max == scripts.cyrillic {
+            Language::Ru
+        } else if max > scripts.latin {
+            Language::Unknown
+        } else {
+            detect_latin(&sample)
+        }
+    }
+
+    /// Indica si el idioma se escribe sin espacios entre palabras, de forma que su largo se mide en caracteres.
+    pub fn is_cjk(&self) -> bool {
+        matches!(self, Language::Ja | Language::Zh | Language::Ko)
+    }
+}
+
+/// Cantidad de letras de cada sistema de escritura.
+#[derive(Debug, Default)]
+struct ScriptCounts {
+    latin: usize,
+    cyrillic: usize,
+    kana: usize,
+    han: usize,
+    hangul: usize,
+    /// Letras de los sistemas de escritura que no se distinguen.
+    other: usize,
+}
+
+impl ScriptCounts {
+    fn add(&mut self, c: char) {
+        match c {
+            '\u{3040}'..='\u{30FF}' => self.kana += 1,
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => self.hangul += 1,
+            c if is_han(c) => self.han += 1,
+            '\u{0400}'..='\u{04FF}' => self.cyrillic += 1,
+            c if is_latin(c) => self.latin += 1,
+            c if c.is_alphabetic() => self.other += 1,
+            _ => {}
+        }
+    }
+}
+
+/// Indica si el caracter es una letra del alfabeto latino, con o sin diacríticos.
+fn is_latin(c: char) -> bool {
+    match c {
+        'A'..='Z' | 'a'..='z' => true,
+        '\u{00C0}'..='\u{024F}'
+        | '\u{1E00}'..='\u{1EFF}'
+        | '\u{2C60}'..='\u{2C7F}'
+        | '\u{A720}'..='\u{A7FF}'
+        | '\u{FF21}'..='\u{FF5A}' => c.is_alphabetic(),
+        _ => false,
+    }
+}
+
+/// Indica si el caracter es un ideograma chino (kanji, hanzi o hanja).
+fn is_han(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}')
+}
+
+/// Cantidad de preguntas de un idioma en un Site, y su largo total (en palabras, o en caracteres si el idioma es CJK).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageStats {
+    pub questions: usize,
+    pub words: usize,
+}
+
+impl LanguageStats {
+    /// Suma una pregunta con el largo indicado.
+    pub fn add(&mut self, words: usize) {
+        self.questions += 1;
+        self.words += words;
+    }
+
+    /// Suma otras estadísticas a sí mismas, in-place.
+    pub fn merge(&mut self, other: &LanguageStats) {
+        self.questions += other.questions;
+        self.words += other.words;
+    }
+}
+
+/// Suma las estadísticas por idioma de `other` a las de `languages`, si las tiene.
+pub fn merge_languages(
+    languages: &mut Option<BTreeMap<Language, LanguageStats>>,
+    other: &Option<BTreeMap<Language, LanguageStats>>,
+) {
+    if let Some(other) = other {
+        let languages = languages.get_or_insert_with(BTreeMap::new);
+        other.iter().for_each(|(language, stats)| {
+            languages.entry(*language).or_default().merge(stats);
+        });
+    }
+}
+
+/// Indica si el caracter es de un sistema de escritura CJK, en el que cada caracter cuenta como una palabra.
+fn is_cjk_char(c: char) -> bool {
+    is_han(c)
+        || matches!(c, '\u{3040}'..='\u{30FF}' | '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}')
+}
+
+/// Largo de un texto contando cada caracter CJK como una palabra, y cada secuencia de otras letras o dígitos como una palabra.
+/// Reemplaza a la cantidad de palabras separadas por espacios en los idiomas que no separan las palabras.
+pub fn cjk_length(text: &str) -> usize {
+    let mut length = 0;
+    for_each_cjk_word(text, |_| length += 1);
+    length
+}
+
+/// Llama a `f` con cada una de las palabras del texto, las mismas que cuenta `cjk_length`.
+pub fn for_each_cjk_word(text: &str, mut f: impl FnMut(&str)) {
+    let mut word_start = None;
+    text.char_indices().for_each(|(i, c)| {
+        if c.is_alphanumeric() && !is_cjk_char(c) {
+            word_start.get_or_insert(i);
+            return;
+        }
+        if let Some(start) = word_start.take() {
+            f(&text[start..i]);
+        }
+        if is_cjk_char(c) {
+            f(&text[i..i + c.len_utf8()]);
+        }
+    });
+    if let Some(start) = word_start {
+        f(&text[start..]);
+    }
+}
+
+/// Para cada trigrama de los perfiles, su peso en cada idioma latino: mayor cuanto más arriba está en el perfil, y 0 si no está.
+fn profile_weights() -> &'static HashMap<[char; 3], [usize; 5]> {
+    static WEIGHTS: OnceLock<HashMap<[char; 3], [usize; 5]>> = OnceLock::new();
+    WEIGHTS.get_or_init(|| {
+        let mut weights: HashMap<[char; 3], [usize; 5]> = HashMap::new();
+        PROFILES.iter().enumerate().for_each(|(language, profile)| {
+            profile.iter().enumerate().for_each(|(rank, trigram)| {
+                let chars: Vec<char> = trigram.chars().collect();
+                if let [a, b, c] = chars[..] {
+                    weights.entry([a, b, c]).or_default()[language] += profile.len() - rank;
+                }
+            })
+        });
+        weights
+    })
+}
+
+/// Elige el idioma latino con mayor puntaje, sumando los pesos de los trigramas de la muestra. Las palabras se pasan a minúsculas y se rodean de espacios.
+fn detect_latin(sample: &[char]) -> Language {
+    let weights = profile_weights();
+    let mut scores = [0; 5];
+    let mut window = [' '; 3];
+    sample
+        .iter()
+        .flat_map(|c| c.to_lowercase())
+        .map(|c| {
+            if c.is_alphabetic() || c == '\'' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .chain([' '])
+        .for_each(|c| {
+            if c == ' ' && window[2] == ' ' {
+                return;
+            }
+            window = [window[1], window[2], c];
+            if let Some(weight) = weights.get(&window) {
+                scores
+                    .iter_mut()
+                    .zip(weight)
+                    .for_each(|(score, w)| *score += w);
+            }
+        });
+    let (best, score) = scores
+        .iter()
+        .enumerate()
+        .max_by_key(|(language, score)| (**score, std::cmp::Reverse(*language)))
+        .unwrap_or((0, &0));
+    if *score == 0 {
+        Language::Unknown
+    } else {
+        LATIN_LANGUAGES[best]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(text: &str) -> Language {
+        Language::detect(&[text.to_string()])
+    }
+
+    #[test]
+    fn detects_latin_languages_by_trigrams() {
+        assert_eq!(
+            detect("How do I iterate over the lines of a file and count the words in it?"),
+            Language::En
+        );
+        assert_eq!(
+            detect(
+                "¿Cómo puedo recorrer las líneas de un archivo y contar las palabras que tiene?"
+            ),
+            Language::Es
+        );
+        assert_eq!(
+            detect(
+                "Como posso percorrer as linhas de um arquivo e contar as palavras? Não funciona."
+            ),
+            Language::Pt
+        );
+        assert_eq!(
+            detect("Comment est-ce que je peux lire les lignes d'un fichier et compter les mots?"),
+            Language::Fr
+        );
+        assert_eq!(
+            detect("Wie kann ich die Zeilen einer Datei lesen und die Wörter zählen? Das ist nicht einfach."),
+            Language::De
+        );
+    }
+
+    #[test]
+    fn detects_other_scripts() {
+        assert_eq!(detect("Как прочитать файл построчно?"), Language::Ru);
+        assert_eq!(detect("ファイルを一行ずつ読む方法は?"), Language::Ja);
+        assert_eq!(detect("如何逐行读取文件?"), Language::Zh);
+        assert_eq!(detect("파일을 한 줄씩 읽는 방법은?"), Language::Ko);
+        assert_eq!(detect("1234 ?!"), Language::Unknown);
+        assert_eq!(detect("Πώς διαβάζω ένα αρχείο;"), Language::Unknown);
+        assert_eq!(detect("كيف أقرأ ملفا سطرا بسطر؟"), Language::Unknown);
+        assert_eq!(detect("איך קוראים קובץ שורה אחר שורה?"), Language::Unknown);
+        assert_eq!(detect("फ़ाइल को पंक्ति दर पंक्ति कैसे पढ़ें?"), Language::Unknown);
+        assert_eq!(
+            detect("Read the file with open() in Python: αβγ"),
+            Language::En
+        );
+        assert_eq!(Language::detect(&[]), Language::Unknown);
+        assert!(Language::Ja.is_cjk() && !Language::Ru.is_cjk());
+    }
+
+    #[test]
+    fn measures_cjk_text_by_characters() {
+        assert_eq!(cjk_length("ファイルを読む"), 7);
+        assert_eq!(cjk_length("Rust で ファイル を読む v2"), 10);
+        assert_eq!(cjk_length(""), 0);
+
+        let mut words = vec![];
+        for_each_cjk_word("Rust で読む v2", |word| words.push(word.to_string()));
+        assert_eq!(words, ["Rust", "で", "読", "む", "v2"]);
+    }
+}
@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+
+use crate::sketch::QuantileSketch;
+
+/// Distribución de la cantidad de palabras por pregunta de un Site o Tag.
+/// Siempre guarda un sketch de cuantiles, que se usa para los rankings por mediana. Si se piden estadísticas detalladas, guarda además el mínimo, el máximo y los momentos de Welford (media y suma de cuadrados de las diferencias).
+/// Todo se puede unir con otra distribución sin importar el orden, así que se combina correctamente en el reduce paralelo.
+/// Solo se serializa el resumen (LengthSummary), y solamente si hay estadísticas detalladas.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(into = "LengthSummary", from = "LengthSummary")]
+pub struct LengthStats {
+    sketch: QuantileSketch,
+    moments: Option<Moments>,
+}
+
+/// Momentos de Welford de los valores agregados, que se pueden unir con la fórmula de Chan.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Moments {
+    count: u64,
+    min: usize,
+    max: usize,
+    mean: f64,
+    m2: f64,
+}
+
+/// Resumen serializado de una distribución detallada. La varianza es poblacional (m2 / count) y los percentiles son aproximados.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LengthSummary {
+    pub count: u64,
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+    pub variance: f64,
+    pub p50: Option<f64>,
+    pub p90: Option<f64>,
+    pub p99: Option<f64>,
+}
+
+impl LengthStats {
+    /// Crea una distribución vacía, que guarda también las estadísticas detalladas si `detailed` es true.
+    pub fn new(detailed: bool) -> LengthStats {
+        LengthStats {
+            sketch: QuantileSketch::default(),
+            moments: detailed.then(Moments::default),
+        }
+    }
+
+    /// Indica si la distribución guarda las estadísticas detalladas.
+    pub fn is_detailed(&self) -> bool {
+        self.moments.is_some()
+    }
+
+    /// Indica si la distribución solo guarda el sketch, y por lo tanto no se serializa.
+    pub fn is_sketch_only(&self) -> bool {
+        self.moments.is_none()
+    }
+
+    /// Sketch de cuantiles de la distribución.
+    pub fn sketch(&self) -> &QuantileSketch {
+        &self.sketch
+    }
+
+    /// Agrega la cantidad de palabras de una pregunta.
+    pub fn add(&mut self, words: usize) {
+        self.sketch.add(words);
+        if let Some(moments) = &mut self.moments {
+            moments.add(words);
+        }
+    }
+
+    /// Une otra distribución a sí misma, in-place. Si alguna de las dos tiene estadísticas detalladas, el resultado también.
+    pub fn merge(&mut self, other: &LengthStats) {
+        self.sketch.merge(&other.sketch);
+        match (&mut self.moments, other.moments) {
+            (Some(moments), Some(other_moments)) => moments.merge(&other_moments),
+            (None, Some(other_moments)) => self.moments = Some(other_moments),
+            _ => {}
+        }
+    }
+
+    /// Resumen de la distribución, o None si no guarda estadísticas detalladas.
+    /// Los percentiles se acotan al mínimo y máximo exactos, porque el sketch los puede estimar apenas por fuera.
+    pub fn summary(&self) -> Option<LengthSummary> {
+        self.moments.map(|moments| {
+            let percentile = |q: f64| {
+                self.sketch
+                    .quantile(q)
+                    .map(|value| value.clamp(moments.min as f64, moments.max as f64))
+            };
+            LengthSummary {
+                count: moments.count,
+                min: moments.min,
+                max: moments.max,
+                mean: moments.mean,
+                variance: moments.variance(),
+                p50: percentile(0.5),
+                p90: percentile(0.9),
+                p99: percentile(0.99),
+            }
+        })
+    }
+}
+
+impl Moments {
+    /// Agrega un valor, actualizando la media y m2 con el algoritmo de Welford.
+    fn add(&mut self, value: usize) {
+        self.min = if self.count == 0 {
+            value
+        } else {
+            self.min.min(value)
+        };
+        self.max = self.max.max(value);
+        self.count += 1;
+        let delta = value as f64 - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value as f64 - self.mean);
+    }
+
+    /// Une los momentos de otro conjunto de valores, con la fórmula de Chan para la varianza en paralelo.
+    fn merge(&mut self, other: &Moments) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * (self.count * other.count) as f64 / count as f64;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.count = count;
+    }
+
+    /// Varianza poblacional de los valores agregados.
+    fn variance(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.m2 / self.count as f64
+        }
+    }
+}
+
+impl From<LengthStats> for LengthSummary {
+    /// Solo se llama al serializar distribuciones detalladas, porque las demás se omiten de la salida.
+    fn from(stats: LengthStats) -> Self {
+        stats.summary().unwrap_or(LengthSummary {
+            count: 0,
+            min: 0,
+            max: 0,
+            mean: 0.0,
+            variance: 0.0,
+            p50: None,
+            p90: None,
+            p99: None,
+        })
+    }
+}
+
+impl From<LengthSummary> for LengthStats {
+    /// Reconstruye los momentos a partir de un resumen deserializado. El sketch queda vacío, porque los percentiles no alcanzan para reconstruirlo.
+    fn from(summary: LengthSummary) -> Self {
+        LengthStats {
+            sketch: QuantileSketch::default(),
+            moments: Some(Moments {
+                count: summary.count,
+                min: summary.min,
+                max: summary.max,
+                mean: summary.mean,
+                m2: summary.variance * summary.count as f64,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_detailed_statistics() {
+        let mut stats = LengthStats::new(true);
+        [2, 4, 4, 4, 5, 5, 7, 9]
+            .iter()
+            .for_each(|words| stats.add(*words));
+
+        let summary = stats.summary().unwrap();
+        assert_eq!(summary.count, 8);
+        assert_eq!(summary.min, 2);
+        assert_eq!(summary.max, 9);
+        assert_eq!(summary.mean, 5.0);
+        assert_eq!(summary.variance, 4.0);
+        assert!((summary.p50.unwrap() - 4.0).abs() <= 0.04);
+    }
+
+    #[test]
+    fn merge_is_the_same_as_adding_everything() {
+        let mut stats = LengthStats::new(true);
+        let mut stats1 = LengthStats::new(true);
+        let mut stats2 = LengthStats::new(true);
+        (1..100).for_each(|words| {
+            stats.add(words);
+            if words < 30 {
+                stats1.add(words);
+            } else {
+                stats2.add(words);
+            }
+        });
+        stats1.merge(&stats2);
+
+        let summary = stats.summary().unwrap();
+        let merged = stats1.summary().unwrap();
+        assert_eq!(merged.count, summary.count);
+        assert_eq!((merged.min, merged.max), (1, 99));
+        assert!((merged.mean - summary.mean).abs() < 1e-9);
+        assert!((merged.variance - summary.variance).abs() < 1e-9);
+        assert_eq!(merged.p90, summary.p90);
+    }
+
+    #[test]
+    fn only_detailed_statistics_have_summary() {
+        let mut stats = LengthStats::new(false);
+        stats.add(10);
+
+        assert_eq!(stats.summary(), None);
+        assert_eq!(stats.sketch().count(), 1);
+    }
+
+    #[test]
+    fn percentiles_are_within_min_and_max() {
+        let mut stats = LengthStats::new(true);
+        stats.add(92);
+
+        let summary = stats.summary().unwrap();
+        assert_eq!(summary.p50, Some(92.0));
+        assert_eq!(summary.p99, Some(92.0));
+    }
+}
@@ -0,0 +1,77 @@
+//! Procesamiento concurrente de los dumps de Stack Exchange: calcula, por Site y por Tag, la cantidad de preguntas y palabras, y los chattys.
+//!
+//! El punto de entrada es [`ProcessedSites::builder`], que recibe las entradas y opciones y devuelve el [`ProcessedSites`] resultante.
+
+mod accumulator;
+mod association;
+mod builder;
+mod chatty;
+mod chunks;
+mod code;
+mod cooccurrence;
+mod dedup;
+mod diff;
+mod distinctive_terms;
+mod error;
+mod graph;
+mod hyperloglog;
+mod input;
+mod language;
+mod lengths;
+mod line;
+mod minhash;
+mod near_duplicates;
+mod options;
+mod processed_sites;
+mod question;
+mod readability;
+mod similarity;
+mod site;
+mod sketch;
+mod tag;
+mod tag_counts;
+mod tokenizer;
+mod top_words;
+mod validate;
+mod vocabulary;
+mod words;
+
+pub use association::{Association, AssociationMeasure};
+pub use builder::ProcessedSitesBuilder;
+pub use chatty::{ChattyConfig, ChattyMetric, ChattyThresholds};
+pub use chunks::DEFAULT_CHUNK_SIZE;
+pub use code::CodeCounts;
+pub use cooccurrence::TagPairs;
+pub use dedup::{DedupScope, Fingerprints};
+pub use diff::{diff_processed_sites, Difference};
+pub use distinctive_terms::{
+    DistinctiveTerm, DistinctiveTermsConfig, TermsScope, DEFAULT_DISTINCTIVE_TERMS,
+    DEFAULT_MIN_TERM_QUESTIONS,
+};
+pub use error::{ErrorPolicy, ProcessingError, SiteErrors};
+pub use graph::{write_edge_list, write_graphml};
+pub use hyperloglog::HyperLogLog;
+pub use language::{cjk_length, Language, LanguageStats};
+pub use lengths::{LengthStats, LengthSummary};
+pub use line::{Line, LineError};
+pub use minhash::MinHasher;
+pub use near_duplicates::{
+    DuplicateCluster, NearDuplicates, NearDuplicatesConfig, QuestionRef,
+    DEFAULT_DUPLICATE_THRESHOLD, DEFAULT_LSH_BANDS, DEFAULT_MINHASH_HASHES, DEFAULT_SHINGLE_SIZE,
+};
+pub use options::ProcessOptions;
+pub use processed_sites::{get_json_paths, process_sites, ProcessedSites, DEFAULT_TOP_N, PADRON};
+pub use question::Question;
+pub use readability::{Readability, ReadabilityConfig, ReadabilitySummary};
+pub use similarity::{SimilarityMeasure, SiteSimilarity, DEFAULT_NEAREST_SITES};
+pub use site::Site;
+pub use sketch::QuantileSketch;
+pub use tag::Tag;
+pub use tag_counts::{TagsPerQuestion, TagsPerQuestionSummary};
+pub use tokenizer::Tokenizer;
+pub use top_words::{
+    TopWords, TopWordsConfig, TopWordsMode, WordFrequency, DEFAULT_STOPWORDS, DEFAULT_TOP_WORDS,
+};
+pub use validate::{validate_files, FileValidation};
+pub use vocabulary::{Vocabulary, VocabularyMode, VocabularySummary};
+pub use words::WordCounts;
@@ -6,3 +6,57 @@ pub struct Line {
     pub texts: Vec<String>,
     pub tags: Vec<String>,
 }
+
+/// Línea tal como viene en el JSON, con los campos opcionales para poder distinguir un campo faltante de un JSON inválido.
+#[derive(Deserialize)]
+struct RawLine {
+    texts: Option<Vec<String>>,
+    tags: Option<Vec<String>>,
+}
+
+/// Error al parsear una línea a un Line.
+#[derive(Debug)]
+pub enum LineError {
+    Json(serde_json::Error),
+    MissingField(&'static str),
+}
+
+impl Line {
+    /// Parsea una línea JSON a un Line, indicando si falló el parseo o si falta alguno de los campos.
+    pub fn parse(line: &str) -> Result<Line, LineError> {
+        let raw_line: RawLine = serde_json::from_str(line).map_err(LineError::Json)?;
+        Ok(Line {
+            texts: raw_line.texts.ok_or(LineError::MissingField("texts"))?,
+            tags: raw_line.tags.ok_or(LineError::MissingField("tags"))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_valid_line() {
+        let line = Line::parse("{\"texts\": [\"title\", \"body\"], \"tags\": [\"tag\"]}").unwrap();
+
+        assert_eq!(line.texts, ["title", "body"]);
+        assert_eq!(line.tags, ["tag"]);
+    }
+
+    #[test]
+    fn distinguishes_missing_fields_from_invalid_json() {
+        assert!(matches!(
+            Line::parse("{\"texts\": [\"title\"]}"),
+            Err(LineError::MissingField("tags"))
+        ));
+        assert!(matches!(
+            Line::parse("{\"tags\": []}"),
+            Err(LineError::MissingField("texts"))
+        ));
+        assert!(matches!(
+            Line::parse("{\"texts\": [\"title\"], "),
+            Err(LineError::Json(_))
+        ));
+    }
+}
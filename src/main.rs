@@ -1,47 +1,352 @@
-use std::{env, thread::available_parallelism};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
 
-use crate::processed_sites::{get_json_paths, process_sites};
+use clap::Parser;
 
-mod line;
-mod processed_sites;
-mod site;
-mod tag;
+use tp1alejofabregas::{
+    diff_processed_sites, get_json_paths, process_sites, validate_files, write_edge_list,
+    write_graphml, ChattyConfig, DistinctiveTermsConfig, NearDuplicatesConfig, ProcessOptions,
+    ProcessedSites, ReadabilityConfig, TagPairs, TopWordsConfig,
+};
 
-const DATA_PATH: &str = "/data";
+use crate::cli::{
+    AnalyzeArgs, AssociateArgs, BenchArgs, Cli, Command, DiffArgs, GraphFormat, OutputFormat,
+    ValidateArgs,
+};
 
-fn main() {
-    let n_threads = get_numthreads_parameter();
+mod cli;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Analyze(args) => analyze(*args),
+        Command::Validate(args) => validate(args),
+        Command::Diff(args) => diff(args),
+        Command::Associate(args) => associate(args),
+        Command::Bench(args) => bench(args),
+    };
+    match result {
+        Ok(exit_code) => exit_code,
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Inicia el pool global de Rayon con la cantidad de threads indicada. Si no se indica, Rayon usa los threads disponibles en el sistema.
+fn init_thread_pool(threads: Option<usize>) -> Result<(), String> {
     rayon::ThreadPoolBuilder::new()
-        .num_threads(n_threads)
+        .num_threads(threads.unwrap_or(0))
         .build_global()
-        .expect("[ERROR] No se pudo iniciar Rayon con la cantidad de threads indicada");
+        .map_err(|e| {
+            format!(
+                "No se pudo iniciar Rayon con la cantidad de threads indicada: {}",
+                e
+            )
+        })
+}
+
+/// Procesa las entradas y escribe el JSON resultante en la salida indicada.
+fn analyze(args: AnalyzeArgs) -> Result<ExitCode, String> {
+    init_thread_pool(args.threads)?;
+
+    let chatty_config = args.chatty_config();
+    let mut builder = ProcessedSites::builder()
+        .inputs(args.input.inputs)
+        .recursive(args.input.recursive)
+        .chatty_config(chatty_config)
+        .padron(args.padron)
+        .error_policy(args.on_error.into())
+        .error_report(args.error_report)
+        .chunk_size(args.chunk_size)
+        .length_stats(args.length_stats)
+        .tokenizer(args.tokenizer.into())
+        .cooccurrence(args.cooccurrence || args.graph_output.is_some())
+        .min_pair_count(args.min_pair_count)
+        .nearest_sites(args.nearest_sites)
+        .languages(args.languages);
+    if let Some(top_n) = args.top_words {
+        let mut config = TopWordsConfig::new(top_n, args.top_words_mode.into());
+        if args.no_stopwords {
+            config = config.with_stopwords(Vec::<String>::new());
+        } else if let Some(path) = &args.stopwords {
+            config = config.with_stopwords(read_stopwords(path)?);
+        }
+        builder = builder.top_words(config);
+    }
+    if let Some(vocabulary) = args.vocabulary {
+        builder = builder.vocabulary(vocabulary.into());
+    }
+    if let Some(similarity) = args.similarity {
+        builder = builder.site_similarity(similarity.into());
+    }
+    if args.readability {
+        builder = builder.readability(ReadabilityConfig {
+            min_questions: args.min_questions,
+            ..ReadabilityConfig::with_top_n(args.hardest.unwrap_or(args.top))
+        });
+    }
+    if let Some(dedup) = args.dedup {
+        builder = builder.dedup(dedup.into());
+    }
+    if args.near_duplicates || args.dedup_near_duplicates {
+        builder = builder.near_duplicates(NearDuplicatesConfig {
+            threshold: args.duplicate_threshold,
+            dedup: args.dedup_near_duplicates,
+            ..NearDuplicatesConfig::default()
+        });
+    }
+    if let Some(top_n) = args.distinctive_terms {
+        let mut config = DistinctiveTermsConfig::new(top_n, args.distinctive_scope.into());
+        config.min_questions = args.min_term_questions;
+        builder = builder.distinctive_terms(config);
+    }
+    let processed_sites = builder.build().map_err(|e| e.to_string())?;
+    if processed_sites.skipped_lines > 0 {
+        eprintln!(
+            "[WARN] Se saltearon {} líneas que no se pudieron leer o parsear",
+            processed_sites.skipped_lines
+        );
+    }
+    if processed_sites.unreadable_files > 0 {
+        eprintln!(
+            "[WARN] No se pudieron leer completos {} archivos",
+            processed_sites.unreadable_files
+        );
+    }
+    if let Some(near_duplicates) = processed_sites
+        .near_duplicates
+        .as_ref()
+        .filter(|near_duplicates| near_duplicates.capped_buckets > 0)
+    {
+        eprintln!(
+            "[WARN] {} buckets de LSH superaron el límite de comparaciones; pueden faltar preguntas casi duplicadas",
+            near_duplicates.capped_buckets
+        );
+    }
+
+    if let Some(path) = &args.graph_output {
+        write_graph(
+            &processed_sites,
+            path,
+            args.graph_format,
+            args.graph_site.as_deref(),
+        )?;
+    }
+
+    let serialized = match args.output.format {
+        OutputFormat::Pretty => serde_json::to_string_pretty(&processed_sites),
+        OutputFormat::Compact => serde_json::to_string(&processed_sites),
+    }
+    .map_err(|e| {
+        format!(
+            "No se pudieron serializar los ProcessedSites a un JSON: {}",
+            e
+        )
+    })?;
+    match args.output.output {
+        Some(path) => fs::write(&path, serialized + "\n")
+            .map_err(|e| format!("No se pudo escribir {}: {}", path.display(), e))?,
+        None => println!("{}", serialized),
+    }
+    Ok(ExitCode::SUCCESS)
+}
 
-    let json_paths = get_json_paths(DATA_PATH);
+/// Escribe en `path` el grafo de pares de tags de todos los Sites, o del Site indicado.
+fn write_graph(
+    processed_sites: &ProcessedSites,
+    path: &Path,
+    format: GraphFormat,
+    site: Option<&str>,
+) -> Result<(), String> {
+    let (pairs, tags) = match site {
+        None => (processed_sites.cooccurrence.as_ref(), &processed_sites.tags),
+        Some(site_name) => {
+            let site = processed_sites
+                .sites
+                .get(site_name)
+                .ok_or(format!("El Site {} no está en las entradas", site_name))?;
+            (site.cooccurrence.as_ref(), &site.tags)
+        }
+    };
+    let empty_pairs = TagPairs::default();
+    let pairs = pairs.unwrap_or(&empty_pairs);
 
-    let mut processed_sites = process_sites(json_paths);
+    let mut writer = BufWriter::new(
+        File::create(path).map_err(|e| format!("No se pudo crear {}: {}", path.display(), e))?,
+    );
+    match format {
+        GraphFormat::EdgeList => write_edge_list(pairs, &mut writer),
+        GraphFormat::Graphml => write_graphml(pairs, tags, &mut writer),
+    }
+    .and_then(|_| writer.flush())
+    .map_err(|e| format!("No se pudo escribir {}: {}", path.display(), e))
+}
 
-    processed_sites.process_chatty();
+/// Valida las entradas. Termina con error si alguna línea es inválida o algún archivo no se pudo leer.
+fn validate(args: ValidateArgs) -> Result<ExitCode, String> {
+    init_thread_pool(args.threads)?;
 
-    let serialized = serde_json::to_string_pretty(&processed_sites)
-        .expect("[ERROR] No se pudieron serializar los ProcessedSites a un JSON");
-    println!("{}", serialized);
+    let json_paths =
+        get_json_paths(&args.input.inputs, args.input.recursive).map_err(|e| e.to_string())?;
+
+    let mut all_valid = true;
+    for validation in validate_files(json_paths) {
+        match validation {
+            Ok(validation) if validation.is_valid() => {
+                println!(
+                    "OK {} ({} líneas)",
+                    validation.path.display(),
+                    validation.lines
+                );
+            }
+            Ok(validation) => {
+                all_valid = false;
+                println!(
+                    "INVALIDO {}: {} de {} líneas inválidas (por ejemplo {:?})",
+                    validation.path.display(),
+                    validation.invalid_lines,
+                    validation.lines,
+                    validation.invalid_sample
+                );
+            }
+            Err(e) => {
+                all_valid = false;
+                println!("ERROR {}", e);
+            }
+        }
+    }
+    Ok(if all_valid {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
 }
 
-/// Obtiene la cantidad de threads a ejecutar indicada por línea de comando. En caso de que se ingrese una cantidad errónea, se va a utilizar la cantidad de threads disponibles en el sistema en el que se está ejecutando.
-fn get_numthreads_parameter() -> usize {
-    let args: Vec<String> = env::args().collect();
-    if args.len() == 2 && args[1].parse::<usize>().is_ok() {
-        // println!("[INFO] La cantidad de threads especificada es {}", args[1]);
-        args[1]
-            .parse()
-            .expect("Ya me fijé que se puede parsear a un usize")
+/// Compara dos salidas de `analyze`. Termina con error si hay diferencias.
+fn diff(args: DiffArgs) -> Result<ExitCode, String> {
+    let old = read_processed_sites(&args.old)?;
+    let new = read_processed_sites(&args.new)?;
+
+    let differences = diff_processed_sites(&old, &new);
+    differences
+        .iter()
+        .for_each(|difference| println!("{}", difference));
+    Ok(if differences.is_empty() {
+        ExitCode::SUCCESS
     } else {
-        let default_parallelism_approx = available_parallelism()
-            .expect("No se pudo obtener la cantidad de threads del sistema")
-            .get();
-        if args.len() != 1 {
-            eprintln!("[ERROR] Parámetros inválidos, se usará el valor adecuado para este sistema ({} threads)", default_parallelism_approx);
+        ExitCode::FAILURE
+    })
+}
+
+/// Lee un archivo de stopwords, una por línea, ignorando las líneas vacías y los comentarios que empiezan con `#`.
+fn read_stopwords(path: &Path) -> Result<Vec<String>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Lee un JSON generado por `analyze`.
+fn read_processed_sites(path: &Path) -> Result<ProcessedSites, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("{} no es una salida válida: {}", path.display(), e))
+}
+
+/// Muestra los tags más asociados al tag indicado, uno por línea: `tag<TAB>preguntas_juntos<TAB>medida`.
+fn associate(args: AssociateArgs) -> Result<ExitCode, String> {
+    let processed_sites = read_processed_sites(&args.result)?;
+    if let Some(site) = &args.site {
+        if !processed_sites.sites.contains_key(site) {
+            return Err(format!(
+                "El Site {} no está en {}",
+                site,
+                args.result.display()
+            ));
         }
-        default_parallelism_approx
     }
+
+    let associations = processed_sites
+        .associated_tags(
+            &args.tag,
+            args.measure.into(),
+            args.site.as_deref(),
+            args.top,
+        )
+        .ok_or(format!(
+            "{} no tiene los pares de tags, hay que generarlo con --cooccurrence",
+            args.result.display()
+        ))?;
+    if associations.is_empty() {
+        eprintln!("[WARN] El tag {} no aparece junto a otros tags", args.tag);
+    }
+    associations.iter().for_each(|association| {
+        println!(
+            "{}\t{}\t{:.4}",
+            association.tag, association.pair_questions, association.score
+        )
+    });
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Mide el tiempo promedio de procesar las entradas con cada cantidad de threads indicada.
+/// Las entradas se expanden una sola vez, antes de medir.
+fn bench(args: BenchArgs) -> Result<ExitCode, String> {
+    let json_paths =
+        get_json_paths(&args.input.inputs, args.input.recursive).map_err(|e| e.to_string())?;
+
+    println!("threads\tpromedio_ms\tspeedup");
+    let mut baseline = None;
+    for n_threads in args.threads {
+        let elapsed = time_processing(&json_paths, n_threads, args.iterations)?;
+        let baseline = *baseline.get_or_insert(elapsed);
+        println!(
+            "{}\t{:.2}\t{:.2}",
+            n_threads,
+            elapsed.as_secs_f64() * 1000.0,
+            baseline.as_secs_f64() / elapsed.as_secs_f64()
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Procesa los archivos `iterations` veces en un pool de `n_threads` threads y devuelve el tiempo promedio.
+/// El pool se crea antes de medir, así que solo se mide el procesamiento y los chattys.
+fn time_processing(
+    json_paths: &[PathBuf],
+    n_threads: usize,
+    iterations: usize,
+) -> Result<Duration, String> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(n_threads)
+        .build()
+        .map_err(|e| {
+            format!(
+                "No se pudo iniciar Rayon con la cantidad de threads indicada: {}",
+                e
+            )
+        })?;
+    let options = ProcessOptions::default();
+    let chatty = ChattyConfig::default();
+    pool.install(|| {
+        let start = Instant::now();
+        for _ in 0..iterations {
+            let mut processed_sites =
+                process_sites(json_paths.to_vec(), &options).map_err(|e| e.to_string())?;
+            processed_sites.process_chatty(&chatty);
+        }
+        Ok(start.elapsed() / iterations as u32)
+    })
 }
@@ -0,0 +1,109 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+/// Calcula firmas MinHash de textos, a partir de sus shingles de palabras consecutivas.
+/// La proporción de posiciones iguales entre dos firmas estima la similitud de Jaccard entre los conjuntos de shingles de los textos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinHasher {
+    /// Cantidad de funciones de hash, que es el largo de cada firma.
+    pub hashes: usize,
+    /// Cantidad de palabras consecutivas de cada shingle.
+    pub shingle_size: usize,
+}
+
+impl MinHasher {
+    /// Firma de un texto ya separado en palabras. Los textos con menos palabras que un shingle forman un único shingle, y los textos vacíos no tienen firma.
+    pub fn signature(&self, words: &[String]) -> Option<Vec<u32>> {
+        if words.is_empty() {
+            return None;
+        }
+        let mut signature = vec![u32::MAX; self.hashes];
+        words
+            .windows(self.shingle_size.clamp(1, words.len()))
+            .for_each(|shingle| {
+                let mut hasher = DefaultHasher::new();
+                shingle.hash(&mut hasher);
+                let hash = hasher.finish();
+                signature.iter_mut().enumerate().for_each(|(i, min)| {
+                    *min = (*min).min(mix(hash ^ seed(i)) as u32);
+                });
+            });
+        Some(signature)
+    }
+}
+
+/// Similitud de Jaccard estimada entre dos firmas del mismo largo: la proporción de posiciones en las que coinciden.
+pub fn estimated_similarity(signature_1: &[u32], signature_2: &[u32]) -> f64 {
+    let equal = signature_1
+        .iter()
+        .zip(signature_2)
+        .filter(|(hash_1, hash_2)| hash_1 == hash_2)
+        .count();
+    equal as f64 / signature_1.len().max(1) as f64
+}
+
+/// Hash de una banda de la firma, para LSH: dos firmas caen en el mismo bucket de una banda solo si coinciden en todas sus posiciones.
+pub fn band_hash(band: &[u32]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    band.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Semilla de la función de hash número `i`.
+fn seed(i: usize) -> u64 {
+    (i as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+/// Mezcla los bits de `x` (el finalizador de SplitMix64), de forma que cada semilla da una función de hash distinta.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn similar_texts_have_similar_signatures() {
+        let minhasher = MinHasher {
+            hashes: 256,
+            shingle_size: 2,
+        };
+        let text = "how do i configure the borrow checker to accept this code in rust";
+        let signature = minhasher.signature(&words(text)).unwrap();
+        let same = minhasher.signature(&words(text)).unwrap();
+        let similar = minhasher
+            .signature(&words(&format!("{} please", text)))
+            .unwrap();
+        let different = minhasher
+            .signature(&words("what is the best anime of the season"))
+            .unwrap();
+
+        assert_eq!(signature.len(), 256);
+        assert_eq!(estimated_similarity(&signature, &same), 1.0);
+        // Los shingles comparten 12 de 13
+        assert!((estimated_similarity(&signature, &similar) - 12.0 / 13.0).abs() < 0.1);
+        assert!(estimated_similarity(&signature, &different) < 0.1);
+    }
+
+    #[test]
+    fn short_and_empty_texts() {
+        let minhasher = MinHasher {
+            hashes: 16,
+            shingle_size: 3,
+        };
+        assert_eq!(minhasher.signature(&[]), None);
+        let short = minhasher.signature(&words("hola mundo")).unwrap();
+        assert_eq!(short, minhasher.signature(&words("hola mundo")).unwrap());
+        assert_ne!(short, minhasher.signature(&words("mundo hola")).unwrap());
+        assert_eq!(band_hash(&short[..4]), band_hash(&short[..4]));
+    }
+}
@@ -0,0 +1,407 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ProcessingError,
+    input::get_site_name,
+    line::Line,
+    minhash::{band_hash, estimated_similarity, MinHasher},
+    processed_sites::fold_lines,
+    tokenizer::Tokenizer,
+};
+
+/// Similitud de Jaccard estimada mínima por defecto para que dos preguntas se consideren casi duplicadas.
+pub const DEFAULT_DUPLICATE_THRESHOLD: f64 = 0.8;
+/// Cantidad por defecto de funciones de hash de cada firma MinHash.
+pub const DEFAULT_MINHASH_HASHES: usize = 128;
+/// Cantidad por defecto de bandas de LSH. Con 16 bandas de 8 hashes, los pares con similitud 0.8 caen en el mismo bucket de alguna banda con probabilidad mayor a 99%.
+pub const DEFAULT_LSH_BANDS: usize = 16;
+/// Cantidad por defecto de palabras consecutivas de cada shingle.
+pub const DEFAULT_SHINGLE_SIZE: usize = 3;
+
+/// Configuración de la búsqueda de preguntas casi duplicadas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearDuplicatesConfig {
+    /// Similitud estimada mínima entre dos preguntas para unirlas en un cluster.
+    pub threshold: f64,
+    /// Largo de las firmas y tamaño de los shingles.
+    pub minhasher: MinHasher,
+    /// Cantidad de bandas en las que se divide cada firma para LSH.
+    pub bands: usize,
+    /// Si es true, de cada cluster solo se suma la primera pregunta a los Sites y Tags.
+    pub dedup: bool,
+}
+
+impl Default for NearDuplicatesConfig {
+    fn default() -> Self {
+        NearDuplicatesConfig {
+            threshold: DEFAULT_DUPLICATE_THRESHOLD,
+            minhasher: MinHasher {
+                hashes: DEFAULT_MINHASH_HASHES,
+                shingle_size: DEFAULT_SHINGLE_SIZE,
+            },
+            bands: DEFAULT_LSH_BANDS,
+            dedup: false,
+        }
+    }
+}
+
+/// Cantidad máxima de preguntas anteriores del mismo bucket de LSH con las que se compara cada pregunta.
+/// Acota las comparaciones en los buckets muy grandes (por ejemplo, muchas copias de la misma pregunta), que igual quedan unidas en cadena.
+/// Es una aproximación: en un bucket más grande no se comparan los pares más alejados, así que pueden faltar casi duplicadas. La salida cuenta cuántos buckets llegaron al límite.
+const MAX_BUCKET_COMPARISONS: usize = 64;
+
+/// Una pregunta, identificada por el archivo en el que está y su número de línea. También se guarda el Site del archivo, para mostrarlo.
+/// Se identifica por el archivo y no por el Site, porque dos archivos pueden tener el mismo nombre de Site (por ejemplo `a.jsonl` y `a.jsonl.gz`, o el mismo nombre en dos directorios).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct QuestionRef {
+    pub path: PathBuf,
+    pub line: usize,
+    pub site: String,
+}
+
+/// Preguntas casi duplicadas entre sí, ordenadas por archivo y línea. `cross_site` indica si están en más de un Site.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicateCluster {
+    pub questions: Vec<QuestionRef>,
+    pub cross_site: bool,
+}
+
+/// Clusters de preguntas casi duplicadas, dentro de un mismo Site o entre Sites.
+/// `duplicate_questions` es la cantidad de preguntas que sobran, sin contar la primera de cada cluster, y `deduplicated` indica si se descartaron de los Sites y Tags.
+/// `capped_buckets` es la cantidad de buckets de LSH en los que no se compararon todos los pares por superar MAX_BUCKET_COMPARISONS; si no es 0, pueden faltar clusters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NearDuplicates {
+    pub threshold: f64,
+    pub clusters: Vec<DuplicateCluster>,
+    pub duplicate_questions: usize,
+    pub deduplicated: bool,
+    #[serde(default)]
+    pub capped_buckets: usize,
+}
+
+impl NearDuplicates {
+    /// Preguntas que se descartan al deduplicar: todas las de cada cluster menos la primera, por archivo y número de línea.
+    pub fn excluded_questions(&self) -> HashMap<PathBuf, HashSet<usize>> {
+        let mut excluded: HashMap<PathBuf, HashSet<usize>> = HashMap::new();
+        self.clusters.iter().for_each(|cluster| {
+            cluster.questions.iter().skip(1).for_each(|question| {
+                excluded
+                    .entry(question.path.to_path_buf())
+                    .or_default()
+                    .insert(question.line);
+            })
+        });
+        excluded
+    }
+}
+
+/// Firma MinHash de una pregunta, con el índice de su archivo y su número de línea.
+struct SignedQuestion {
+    file: usize,
+    line: usize,
+    signature: Vec<u32>,
+}
+
+/// Lee los archivos JSON en paralelo y busca las preguntas casi duplicadas, dentro de cada Site y entre Sites.
+/// Calcula la firma MinHash de las palabras de cada pregunta (en minúsculas, según el tokenizer), y divide las firmas en bandas para LSH, procesando cada banda en paralelo.
+/// Dentro de cada bucket se compara cada pregunta con las anteriores (hasta MAX_BUCKET_COMPARISONS), y los pares con similitud estimada de al menos `config.threshold` se unen en clusters.
+/// Las líneas inválidas y las preguntas sin palabras se ignoran. Guarda una firma por pregunta, así que ocupa unos `4 * hashes` bytes por pregunta.
+pub fn find_near_duplicates(
+    json_paths: &[PathBuf],
+    chunk_size: u64,
+    tokenizer: Tokenizer,
+    config: &NearDuplicatesConfig,
+) -> Result<NearDuplicates, ProcessingError> {
+    let site_names: Vec<String> = json_paths.iter().map(|p| get_site_name(p)).collect();
+    let mut questions: Vec<SignedQuestion> = fold_lines(
+        json_paths,
+        chunk_size,
+        |mut questions: Vec<SignedQuestion>, file, number, line| {
+            if let Some(signature) = line
+                .ok()
+                .and_then(|line| Line::parse(&line).ok())
+                .and_then(|line| config.minhasher.signature(&words(&line, tokenizer)))
+            {
+                questions.push(SignedQuestion {
+                    file,
+                    line: number,
+                    signature,
+                });
+            }
+            Ok(questions)
+        },
+        |mut questions_1, questions_2| {
+            questions_1.extend(questions_2);
+            questions_1
+        },
+    )?;
+    questions.sort_unstable_by(|question_1, question_2| {
+        (&json_paths[question_1.file], question_1.line)
+            .cmp(&(&json_paths[question_2.file], question_2.line))
+    });
+
+    let (pairs, capped_buckets) = similar_pairs(&questions, config);
+    let mut clusters: Vec<DuplicateCluster> = group_pairs(questions.len(), &pairs)
+        .into_iter()
+        .map(|indexes| {
+            let questions: Vec<QuestionRef> = indexes
+                .into_iter()
+                .map(|i| QuestionRef {
+                    path: json_paths[questions[i].file].to_path_buf(),
+                    line: questions[i].line,
+                    site: site_names[questions[i].file].to_string(),
+                })
+                .collect();
+            let cross_site = questions.iter().any(|q| q.site != questions[0].site);
+            DuplicateCluster {
+                questions,
+                cross_site,
+            }
+        })
+        .collect();
+    clusters.sort_unstable_by(|cluster_1, cluster_2| {
+        cluster_1.questions[0].cmp(&cluster_2.questions[0])
+    });
+    Ok(NearDuplicates {
+        threshold: config.threshold,
+        duplicate_questions: clusters.iter().map(|c| c.questions.len() - 1).sum(),
+        clusters,
+        deduplicated: config.dedup,
+        capped_buckets,
+    })
+}
+
+/// Palabras de todos los texts de la línea, en orden y en minúsculas.
+fn words(line: &Line, tokenizer: Tokenizer) -> Vec<String> {
+    let mut words = vec![];
+    line.texts.iter().for_each(|text| {
+        tokenizer.for_each_word(text, |word| words.push(word.to_lowercase()));
+    });
+    words
+}
+
+/// Pares de índices de preguntas que caen en el mismo bucket de alguna banda y cuya similitud estimada supera el umbral, y la cantidad de buckets que llegaron al límite de comparaciones. Un mismo par puede aparecer varias veces.
+/// Cada pregunta se compara con las anteriores de su bucket, hasta las últimas MAX_BUCKET_COMPARISONS, así que dos preguntas parecidas entre sí se unen aunque no se parezcan a la primera del bucket.
+fn similar_pairs(
+    questions: &[SignedQuestion],
+    config: &NearDuplicatesConfig,
+) -> (Vec<(usize, usize)>, usize) {
+    let rows = (config.minhasher.hashes / config.bands.max(1)).max(1);
+    let bands = config.minhasher.hashes / rows;
+    (0..bands)
+        .into_par_iter()
+        .map(|band| {
+            let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+            let mut pairs = vec![];
+            let mut capped_buckets = 0;
+            questions.iter().enumerate().for_each(|(i, question)| {
+                let hash = band_hash(&question.signature[band * rows..(band + 1) * rows]);
+                let bucket = buckets.entry(hash).or_default();
+                // Desde esta pregunta, las primeras del bucket ya no se comparan con las nuevas
+                capped_buckets += (bucket.len() == MAX_BUCKET_COMPARISONS + 1) as usize;
+                let previous = &bucket[bucket.len().saturating_sub(MAX_BUCKET_COMPARISONS)..];
+                previous.iter().for_each(|j| {
+                    let similarity =
+                        estimated_similarity(&questions[*j].signature, &question.signature);
+                    if similarity >= config.threshold {
+                        pairs.push((*j, i));
+                    }
+                });
+                bucket.push(i);
+            });
+            (pairs, capped_buckets)
+        })
+        .reduce(
+            || (vec![], 0),
+            |(mut pairs_1, capped_1), (pairs_2, capped_2)| {
+                pairs_1.extend(pairs_2);
+                (pairs_1, capped_1 + capped_2)
+            },
+        )
+}
+
+/// Agrupa los índices unidos por los pares (componentes conexas, con union-find), y devuelve los grupos de al menos dos índices, cada uno ordenado.
+fn group_pairs(len: usize, pairs: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut parents: Vec<usize> = (0..len).collect();
+    fn find(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+    pairs.iter().for_each(|(i, j)| {
+        let (root_i, root_j) = (find(&mut parents, *i), find(&mut parents, *j));
+        parents[root_i.max(root_j)] = root_i.min(root_j);
+    });
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    (0..len).for_each(|i| {
+        let root = find(&mut parents, i);
+        groups.entry(root).or_default().push(i);
+    });
+    groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::*;
+
+    fn write_site(dir: &Path, name: &str, texts: &[&str]) -> PathBuf {
+        let path = dir.join(format!("{}.stackexchange.com.jsonl", name));
+        let lines: Vec<String> = texts
+            .iter()
+            .map(|text| format!("{{\"texts\": [\"{}\"], \"tags\": [\"tag\"]}}", text))
+            .collect();
+        fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    const QUESTION: &str = "how can i make the borrow checker accept a mutable reference inside this loop in my rust program";
+
+    #[test]
+    fn finds_clusters_within_and_across_sites() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = vec![
+            write_site(
+                dir.path(),
+                "a",
+                &[QUESTION, "what is the best anime of the season", QUESTION],
+            ),
+            write_site(
+                dir.path(),
+                "b",
+                &["no es json", &format!("{} thanks", QUESTION), ""],
+            ),
+        ];
+
+        let duplicates = find_near_duplicates(
+            &paths,
+            16,
+            Tokenizer::default(),
+            &NearDuplicatesConfig::default(),
+        )
+        .unwrap();
+
+        assert_eq!(duplicates.clusters.len(), 1);
+        let cluster = &duplicates.clusters[0];
+        let questions: Vec<(&str, usize)> = cluster
+            .questions
+            .iter()
+            .map(|q| (q.site.as_str(), q.line))
+            .collect();
+        assert_eq!(
+            questions,
+            [
+                ("a.stackexchange.com", 1),
+                ("a.stackexchange.com", 3),
+                ("b.stackexchange.com", 2)
+            ]
+        );
+        assert!(cluster.cross_site);
+        assert_eq!(duplicates.duplicate_questions, 2);
+
+        let excluded = duplicates.excluded_questions();
+        assert_eq!(excluded[&paths[0]], HashSet::from([3]));
+        assert_eq!(excluded[&paths[1]], HashSet::from([2]));
+    }
+
+    #[test]
+    fn same_site_in_two_files_is_not_mixed() {
+        let dir = tempfile::tempdir().unwrap();
+        let other_dir = dir.path().join("other");
+        fs::create_dir(&other_dir).unwrap();
+        let paths = vec![
+            write_site(dir.path(), "a", &["first question here", QUESTION]),
+            write_site(&other_dir, "a", &[QUESTION]),
+        ];
+
+        let duplicates = find_near_duplicates(
+            &paths,
+            16,
+            Tokenizer::default(),
+            &NearDuplicatesConfig::default(),
+        )
+        .unwrap();
+
+        let excluded = duplicates.excluded_questions();
+        assert_eq!(excluded.len(), 1);
+        assert_eq!(excluded[&paths[1]], HashSet::from([1]));
+        assert!(!duplicates.clusters[0].cross_site);
+    }
+
+    #[test]
+    fn compares_each_question_with_the_previous_ones_in_its_bucket() {
+        // Las tres firmas coinciden en la primera banda, así que caen en el mismo bucket; 1 y 2 se parecen entre sí, pero no a 0, que es la primera del bucket
+        let signed = |file, signature: Vec<u32>| SignedQuestion {
+            file,
+            line: 1,
+            signature,
+        };
+        let questions = vec![
+            signed(0, vec![1, 1, 1, 1, 1, 1, 1, 1, 9, 9]),
+            signed(1, vec![1, 1, 1, 1, 1, 1, 2, 2, 2, 2]),
+            signed(2, vec![1, 1, 1, 1, 1, 1, 2, 2, 2, 3]),
+        ];
+        let config = NearDuplicatesConfig {
+            threshold: 0.9,
+            minhasher: MinHasher {
+                hashes: 10,
+                shingle_size: 3,
+            },
+            bands: 2,
+            dedup: false,
+        };
+
+        let (pairs, capped_buckets) = similar_pairs(&questions, &config);
+
+        assert!(pairs.contains(&(1, 2)));
+        assert!(!pairs.iter().any(|(i, _)| *i == 0));
+        assert_eq!(capped_buckets, 0);
+    }
+
+    #[test]
+    fn counts_buckets_that_hit_the_comparison_limit() {
+        let questions: Vec<SignedQuestion> = (0..MAX_BUCKET_COMPARISONS + 2)
+            .map(|line| SignedQuestion {
+                file: 0,
+                line,
+                signature: vec![1; 10],
+            })
+            .collect();
+        let config = NearDuplicatesConfig {
+            minhasher: MinHasher {
+                hashes: 10,
+                shingle_size: 3,
+            },
+            bands: 2,
+            ..NearDuplicatesConfig::default()
+        };
+
+        let (pairs, capped_buckets) = similar_pairs(&questions, &config);
+
+        // Las dos bandas tienen un solo bucket con todas las preguntas, y la última ya no se compara con la primera
+        assert_eq!(capped_buckets, 2);
+        assert!(pairs.contains(&(0, MAX_BUCKET_COMPARISONS)));
+        assert!(!pairs.contains(&(0, MAX_BUCKET_COMPARISONS + 1)));
+    }
+
+    #[test]
+    fn groups_connected_pairs() {
+        let mut groups = group_pairs(6, &[(0, 2), (4, 5), (2, 0), (5, 2)]);
+        groups.sort();
+        assert_eq!(groups, [vec![0, 2, 4, 5]]);
+        assert!(group_pairs(3, &[]).is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    chunks::DEFAULT_CHUNK_SIZE, dedup::DedupScope, error::ErrorPolicy, tokenizer::Tokenizer,
+    top_words::TopWordsConfig, vocabulary::VocabularyMode,
+};
+
+/// Opciones del procesamiento de las entradas que recibe process_sites.
+#[derive(Debug, Clone)]
+pub struct ProcessOptions {
+    /// Qué hacer con las líneas que no se pueden leer o parsear.
+    pub error_policy: ErrorPolicy,
+    /// Tamaño aproximado en bytes de los rangos en los que se dividen los archivos sin comprimir.
+    pub chunk_size: u64,
+    /// Si es true, cada Site y Tag guarda estadísticas detalladas de palabras por pregunta (mínimo, máximo, varianza y percentiles).
+    pub length_stats: bool,
+    /// Forma de contar las palabras de cada text.
+    pub tokenizer: Tokenizer,
+    /// Si es true, se cuentan los pares de tags que aparecen juntos en una pregunta, por Site y en total.
+    pub cooccurrence: bool,
+    /// Cantidad mínima de preguntas en las que tiene que aparecer un par de tags para que se guarde.
+    pub min_pair_count: usize,
+    /// Si está, cada Site y Tag cuenta sus palabras distintas en el modo indicado.
+    pub vocabulary: Option<VocabularyMode>,
+    /// Si está, cada Site y Tag cuenta sus palabras más frecuentes según la configuración.
+    pub top_words: Option<TopWordsConfig>,
+    /// Preguntas que no se suman a sus Sites y Tags (por ejemplo, por estar duplicadas), por archivo y número de línea.
+    pub excluded_questions: Option<Arc<HashMap<PathBuf, HashSet<usize>>>>,
+    /// Si está, antes de procesar se buscan las preguntas con los mismos texts normalizados (en el mismo Site o en cualquiera), y solo se suma la del menor archivo y número de línea de cada grupo.
+    pub dedup: Option<DedupScope>,
+    /// Si es true, se detecta el idioma de cada pregunta, se desglosan los Sites por idioma y las preguntas en idiomas CJK se miden en caracteres.
+    pub languages: bool,
+    /// Si es true, cada Site y Tag cuenta sus oraciones, palabras y sílabas para calcular su legibilidad.
+    pub readability: bool,
+}
+
+impl Default for ProcessOptions {
+    fn default() -> Self {
+        ProcessOptions {
+            error_policy: ErrorPolicy::default(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            length_stats: false,
+            tokenizer: Tokenizer::default(),
+            cooccurrence: false,
+            min_pair_count: 1,
+            vocabulary: None,
+            top_words: None,
+            excluded_questions: None,
+            dedup: None,
+            languages: false,
+            readability: false,
+        }
+    }
+}
+
+impl ProcessOptions {
+    /// Indica si la línea número `line` del archivo en `path` está entre las preguntas excluidas.
+    pub fn is_excluded(&self, path: &Path, line: usize) -> bool {
+        self.excluded_questions
+            .as_ref()
+            .and_then(|excluded| excluded.get(path))
+            .is_some_and(|lines| lines.contains(&line))
+    }
+
+    /// Agrega preguntas a las excluidas, por archivo y número de línea, sin sacar las que ya estaban.
+    pub fn exclude(&mut self, questions: &HashMap<PathBuf, HashSet<usize>>) {
+        if questions.is_empty() {
+            return;
+        }
+        let mut excluded = self
+            .excluded_questions
+            .as_deref()
+            .cloned()
+            .unwrap_or_default();
+        questions.iter().for_each(|(path, lines)| {
+            excluded
+                .entry(path.to_path_buf())
+                .or_default()
+                .extend(lines);
+        });
+        self.excluded_questions = Some(Arc::new(excluded));
+    }
+}
@@ -1,24 +1,75 @@
 use std::{
-    collections::HashMap,
-    fs::{read_dir, File},
-    io::{BufRead, BufReader},
-    path::PathBuf,
+    collections::{HashMap, HashSet},
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 
-use rayon::iter::{IntoParallelRefIterator, ParallelBridge, ParallelIterator};
-use serde::Serialize;
+use rayon::iter::{once, Either, IntoParallelRefIterator, ParallelBridge, ParallelIterator};
+use serde::{Deserialize, Serialize};
 
-use crate::{line::Line, site::Site, tag::Tag};
+use crate::{
+    accumulator::SitesAccumulator,
+    builder::ProcessedSitesBuilder,
+    chatty::{ChattyConfig, ChattyMetric, ChattyThresholds},
+    chunks::{split_files, Chunk},
+    cooccurrence::TagPairs,
+    dedup::find_exact_duplicates,
+    error::{ProcessingError, SiteErrors},
+    input::{expand_input, get_site_name},
+    language::Language,
+    near_duplicates::NearDuplicates,
+    options::ProcessOptions,
+    readability::READABILITY_LANGUAGE,
+    similarity::SiteSimilarity,
+    site::Site,
+    sketch::QuantileSketch,
+    tag::Tag,
+    tokenizer::Tokenizer,
+    words::WordCounts,
+};
 
-const PADRON: &str = "106160";
+pub const PADRON: &str = "106160";
+/// Cantidad de elementos por defecto de las listas de chattys.
+pub const DEFAULT_TOP_N: usize = 10;
 
 /// Estructura que contiene la información final del cómputo que se va a mostrar en formato JSON. Para eso, serializamos con serde_json.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ProcessedSites {
     pub padron: String,
     pub sites: HashMap<String, Site>,
     pub tags: HashMap<String, Tag>,
     pub totals: HashMap<String, Vec<String>>,
+    /// Cantidad de líneas que se saltearon por no poder leerse o parsearse.
+    #[serde(skip)]
+    pub skipped_lines: usize,
+    /// Cantidad de archivos que se dejaron de leer antes de terminar, por ejemplo por estar truncados.
+    #[serde(skip)]
+    pub unreadable_files: usize,
+    /// Errores encontrados por Site al leer las entradas. Es opcional, solo se serializa si está presente.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errors: Option<HashMap<String, SiteErrors>>,
+    /// Umbrales que se aplicaron a los rankings. Solo se serializa si se aplicó alguno.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thresholds: Option<ChattyThresholds>,
+    /// Tokenizer con el que se contaron las palabras.
+    #[serde(default)]
+    pub tokenizer: Tokenizer,
+    /// Idioma de las preguntas con las que se calculó la legibilidad. Solo está si se pidió calcularla.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readability_language: Option<Language>,
+    /// Pares de tags que aparecen juntos en todos los Sites. Solo está si se pidió contarlos.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooccurrence: Option<TagPairs>,
+    /// Similitud entre todos los pares de Sites. Solo está si se pidió calcularla.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<SiteSimilarity>,
+    /// Clusters de preguntas casi duplicadas. Solo está si se pidió buscarlas.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub near_duplicates: Option<NearDuplicates>,
+    /// Preguntas que no se sumaron a los Sites y Tags (por ser casi duplicadas o repetidas), por archivo y número de línea. Las siguientes pasadas por las entradas descartan las mismas.
+    #[serde(skip)]
+    pub excluded_questions: Option<Arc<HashMap<PathBuf, HashSet<usize>>>>,
 }
 
 impl ProcessedSites {
@@ -34,140 +85,315 @@ impl ProcessedSites {
             sites,
             tags,
             totals,
+            skipped_lines: 0,
+            unreadable_files: 0,
+            errors: None,
+            thresholds: None,
+            tokenizer: Tokenizer::default(),
+            readability_language: None,
+            cooccurrence: None,
+            similarity: None,
+            near_duplicates: None,
+            excluded_questions: None,
         }
     }
 
-    /// Genera todos los chattys (top 10) para este ProcessedSites.
-    /// Calcula los chatty_sites.
-    /// Calcula los chatty_tags para cada Site.
-    /// Calcula los chatty_tags de los Tags totales.
-    pub fn process_chatty(&mut self) {
-        let chatty_sites_totals: Vec<(&String, f64)> = self
+    /// Crea un builder para procesar entradas y obtener un ProcessedSites.
+    pub fn builder() -> ProcessedSitesBuilder {
+        ProcessedSitesBuilder::new()
+    }
+
+    /// Genera todos los rankings para este ProcessedSites, según la métrica, tamaños y umbrales de la configuración.
+    /// Calcula los chatty_sites (y terse_sites).
+    /// Calcula los chatty_tags (y terse_tags) para cada Site.
+    /// Calcula los chatty_tags (y terse_tags) de los Tags totales.
+    /// Las listas de tamaño 0 no se generan. Los Sites y Tags totales se suavizan hacia el valor de todo el corpus, y los Tags de cada Site hacia el de su Site.
+    /// Si se aplicó algún umbral, queda registrado en `thresholds`.
+    pub fn process_chatty(&mut self, config: &ChattyConfig) {
+        let metric = config.metric;
+        let thresholds = config.thresholds;
+        let corpus_prior = self.corpus_metric(metric);
+
+        let sites_totals: Vec<(&String, f64)> = self
             .sites
             .par_iter()
-            .map(|(name, site)| (name, site.words as f64 / site.questions as f64))
+            .filter_map(|(name, site)| {
+                thresholds
+                    .score(metric, site.questions, site.metric(metric), corpus_prior)
+                    .map(|score| (name, score))
+            })
             .collect();
-        self.totals
-            .insert("chatty_sites".to_string(), get_chatty(chatty_sites_totals));
+        insert_rankings(
+            &mut self.totals,
+            ("chatty_sites", config.chatty_sites),
+            ("terse_sites", config.terse_sites),
+            sites_totals,
+        );
 
-        let chatty_tags_totals: Vec<(&String, f64)> = self
+        let tags_totals: Vec<(&String, f64)> = self
             .tags
             .par_iter()
-            .map(|(name, tag)| (name, tag.words as f64 / tag.questions as f64))
+            .filter_map(|(name, tag)| {
+                thresholds
+                    .score(metric, tag.questions, tag.metric(metric), corpus_prior)
+                    .map(|score| (name, score))
+            })
             .collect();
-        self.totals
-            .insert("chatty_tags".to_string(), get_chatty(chatty_tags_totals));
+        insert_rankings(
+            &mut self.totals,
+            ("chatty_tags", config.chatty_tags),
+            ("terse_tags", config.terse_tags),
+            tags_totals,
+        );
 
         self.sites.iter_mut().for_each(|(_site_name, site)| {
-            let chatty_tags: Vec<(&String, f64)> = site
+            let site_prior = site.metric(metric);
+            let site_tags: Vec<(&String, f64)> = site
                 .tags
                 .par_iter()
-                .map(|(name, tag)| (name, tag.words as f64 / tag.questions as f64))
+                .filter_map(|(name, tag)| {
+                    thresholds
+                        .score(metric, tag.questions, tag.metric(metric), site_prior)
+                        .map(|score| (name, score))
+                })
                 .collect();
-            site.chatty_tags.extend(get_chatty(chatty_tags));
+            site.terse_tags = get_terse(site_tags.clone(), config.site_terse_tags);
+            site.chatty_tags = get_chatty(site_tags, config.site_chatty_tags);
+        });
+
+        self.thresholds = thresholds.is_applied().then_some(thresholds);
+    }
+
+    /// Registra en el Site de cada archivo de `json_paths` cuántas de sus preguntas se descartaron por ser casi duplicadas según `excluded`.
+    /// Los Sites que se quedaron sin preguntas se crean vacíos, igual que al descartar las repetidas exactas, así que siempre aparecen en el resultado.
+    pub fn add_removed_near_duplicates(
+        &mut self,
+        json_paths: &[PathBuf],
+        excluded: &HashMap<PathBuf, HashSet<usize>>,
+        options: &ProcessOptions,
+    ) {
+        json_paths.iter().for_each(|path| {
+            let site = self
+                .sites
+                .entry(get_site_name(path))
+                .or_insert_with(|| Site::empty(options));
+            *site.removed_near_duplicates.get_or_insert(0) +=
+                excluded.get(path).map_or(0, HashSet::len);
+        });
+    }
+
+    /// Descarta los pares de tags, totales y de cada Site, que aparecen en menos de `min_count` preguntas.
+    /// Los totales se calculan antes de descartar, así que un par puede quedar en el total aunque no alcance el mínimo en ningún Site.
+    pub fn prune_pairs(&mut self, min_count: usize) {
+        if let Some(cooccurrence) = &mut self.cooccurrence {
+            cooccurrence.prune(min_count);
+        }
+        self.sites.values_mut().for_each(|site| {
+            if let Some(cooccurrence) = &mut site.cooccurrence {
+                cooccurrence.prune(min_count);
+            }
         });
     }
+
+    /// Valor de la métrica para todo el corpus, sumando las preguntas y palabras de todos los Sites.
+    fn corpus_metric(&self, metric: ChattyMetric) -> f64 {
+        let mut lengths = QuantileSketch::default();
+        let mut title_body = WordCounts::default();
+        let (mut questions, mut words) = (0, 0);
+        self.sites.values().for_each(|site| {
+            questions += site.questions;
+            words += site.words;
+            title_body.title += site.title_words;
+            title_body.body += site.body_words;
+            lengths.merge(site.lengths.sketch());
+        });
+        metric.value(questions, words, title_body, &lengths)
+    }
+}
+
+/// Agrega a los totals las listas chatty y terse de los items indicados, si su tamaño no es 0.
+fn insert_rankings(
+    totals: &mut HashMap<String, Vec<String>>,
+    (chatty_name, chatty_n): (&str, usize),
+    (terse_name, terse_n): (&str, usize),
+    items: Vec<(&String, f64)>,
+) {
+    if terse_n > 0 {
+        totals.insert(terse_name.to_string(), get_terse(items.clone(), terse_n));
+    }
+    if chatty_n > 0 {
+        totals.insert(chatty_name.to_string(), get_chatty(items, chatty_n));
+    }
 }
 
 /// A partir de un vector de items del tipo (string, ratio words/questions),
-/// devuelve un vector con las strings chatty (top 10 con mayor ratio words/questions).
+/// devuelve un vector con las strings chatty (las `top_n` con mayor ratio words/questions).
 /// Funciona para chatty_sites y chatty_tags.
-fn get_chatty(mut chatty_items: Vec<(&String, f64)>) -> Vec<String> {
-    chatty_items.sort_by(|item_1, item_2| match (item_2.1).total_cmp(&(item_1.1)) {
+fn get_chatty(chatty_items: Vec<(&String, f64)>, top_n: usize) -> Vec<String> {
+    top_by_value(chatty_items, top_n)
+}
+
+/// A partir de un vector de items del tipo (string, valor), devuelve las `top_n` strings con mayor valor. Los empates se ordenan por nombre.
+/// Es la base de los rankings chatty y de los de legibilidad.
+pub(crate) fn top_by_value(mut items: Vec<(&String, f64)>, top_n: usize) -> Vec<String> {
+    items.sort_by(|item_1, item_2| match (item_2.1).total_cmp(&(item_1.1)) {
         std::cmp::Ordering::Equal => item_1.0.cmp(item_2.0),
         other => other,
     });
-    if chatty_items.len() > 10 {
-        chatty_items = chatty_items[0..10].to_vec();
-    }
-    chatty_items
+    items.truncate(top_n);
+    items
+        .iter()
+        .map(|(name, _value)| name.to_string())
+        .collect()
+}
+
+/// Igual que get_chatty, pero devuelve las `bottom_n` strings con menor valor (terse). Los empates se ordenan por nombre.
+fn get_terse(mut terse_items: Vec<(&String, f64)>, bottom_n: usize) -> Vec<String> {
+    terse_items.sort_by(|item_1, item_2| match (item_1.1).total_cmp(&(item_2.1)) {
+        std::cmp::Ordering::Equal => item_1.0.cmp(item_2.0),
+        other => other,
+    });
+    terse_items.truncate(bottom_n);
+    terse_items
         .iter()
         .map(|(tag_name, _tag)| tag_name.to_string())
         .collect()
 }
 
-/// Obtiene los paths de los archivos JSON del directorio indicado por parámetro.
-pub fn get_json_paths(path: &str) -> Vec<PathBuf> {
-    read_dir(format!("{}{}", env!("CARGO_MANIFEST_DIR"), path))
-        .expect("[ERROR] No se pudieron obtener los paths de los archivos JSON a procesar.")
-        .flatten()
-        .map(|d| d.path())
-        .filter(|p| p.extension().map_or(false, |ext| ext == "jsonl"))
-        .collect::<Vec<PathBuf>>()
+/// Obtiene los paths de los archivos JSON a partir de las entradas indicadas en tiempo de ejecución, incluyendo los comprimidos (`.jsonl.gz` y `.jsonl.zst`).
+/// Cada entrada puede ser un directorio, un archivo individual o un patrón glob. Los directorios se recorren recursivamente si `recursive` es true.
+/// Los paths se devuelven ordenados y sin repetidos. Si alguna entrada no existe o no se puede recorrer, se devuelve un error.
+pub fn get_json_paths(inputs: &[String], recursive: bool) -> Result<Vec<PathBuf>, ProcessingError> {
+    let mut json_paths = vec![];
+    for input in inputs {
+        let paths =
+            expand_input(input, recursive).map_err(|source| ProcessingError::Directory {
+                input: input.to_string(),
+                source,
+            })?;
+        json_paths.extend(paths);
+    }
+    json_paths.sort();
+    json_paths.dedup();
+    Ok(json_paths)
 }
 
 /// Lee los archivos JSON pasados por parámetro y los va procesando concurrentemente línea por línea para obtener el conjunto de Sites procesados, junto con sus Tags. No se procesan los chatty_tags ni los totals.
-/// Se crean por cada línea objetos de tipo ProcessedSites en el map, y se van uniendo de a pares en el reduce.
-/// El resultado es un ProcessedSites que tiene tantos Sites como archivos JSON haya.
-pub fn process_sites(json_paths: Vec<PathBuf>) -> ProcessedSites {
-    let processed_sites = json_paths
+/// Los archivos sin comprimir se dividen en rangos de bytes alineados a líneas, de forma que varios threads pueden procesar un mismo archivo a la vez. Los rangos se procesan de mayor a menor.
+/// Los archivos comprimidos se descomprimen como stream mientras se leen, y sus líneas se reparten entre los threads.
+/// Cada thread acumula las líneas que procesa en su propio SitesAccumulator (fold), y recién al final se unen los acumuladores de a pares (reduce).
+/// Las líneas que no se pueden leer o parsear se manejan según la política de errores de las opciones. Si un archivo no se puede abrir, siempre se devuelve un error.
+/// Si las opciones piden deduplicar, antes se hace una pasada que busca las preguntas repetidas, y de cada grupo solo se suma la del menor archivo y número de línea, así que el resultado no depende de la cantidad de threads. Cada Site cuenta cuántas se descartaron.
+/// El resultado es un ProcessedSites que tiene tantos Sites como archivos JSON haya, con el reporte de errores de cada uno.
+pub fn process_sites(
+    json_paths: Vec<PathBuf>,
+    options: &ProcessOptions,
+) -> Result<ProcessedSites, ProcessingError> {
+    let site_names: Vec<String> = json_paths.iter().map(|p| get_site_name(p)).collect();
+    let duplicates = find_exact_duplicates(&json_paths, options)?;
+    let mut options = options.clone();
+    options.exclude(&duplicates);
+    let options = &options;
+    let accumulator = fold_lines(
+        &json_paths,
+        options.chunk_size,
+        |accumulator: SitesAccumulator, file, number, line| {
+            accumulator.add_line(&json_paths[file], &site_names[file], number, line, options)
+        },
+        SitesAccumulator::merge,
+    )?;
+
+    let mut processed_sites = accumulator.into_processed_sites();
+    duplicates.iter().for_each(|(path, lines)| {
+        let site = processed_sites
+            .sites
+            .entry(get_site_name(path))
+            .or_insert_with(|| Site::empty(options));
+        *site.removed_duplicates.get_or_insert(0) += lines.len();
+    });
+    processed_sites.excluded_questions = options.excluded_questions.clone();
+    processed_sites.tokenizer = options.tokenizer;
+    processed_sites.readability_language = options.readability.then_some(READABILITY_LANGUAGE);
+    processed_sites.prune_pairs(options.min_pair_count);
+    let errors = processed_sites.errors.get_or_insert_with(HashMap::new);
+    site_names.into_iter().for_each(|sitename| {
+        errors.entry(sitename).or_default();
+    });
+    Ok(processed_sites)
+}
+
+/// Lee concurrentemente todas las líneas de los archivos, acumulándolas con `add_line` en un acumulador por thread (fold) que recién al final se unen de a pares con `merge` (reduce).
+/// `add_line` recibe el acumulador, el índice del archivo en `json_paths`, el número de línea y la línea leída. Si un archivo no se puede abrir, se devuelve un error.
+pub(crate) fn fold_lines<A, F, M>(
+    json_paths: &[PathBuf],
+    chunk_size: u64,
+    add_line: F,
+    merge: M,
+) -> Result<A, ProcessingError>
+where
+    A: Default + Send,
+    F: Fn(A, usize, usize, io::Result<String>) -> Result<A, ProcessingError> + Sync,
+    M: Fn(A, A) -> A + Sync,
+{
+    let (ranges, streams): (Vec<Chunk>, Vec<Chunk>) = split_files(json_paths, chunk_size)?
+        .into_iter()
+        .partition(|chunk| chunk.range.is_some());
+
+    let ranges_accumulators = ranges
         .par_iter()
-        .flat_map(|path| {
-            let file = File::open(path);
-            let reader = BufReader::new(file.expect("[ERROR] No se pudo leer el archivo"));
-            let sitename = path
-                .file_name()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .replace(".jsonl", "");
-            reader
-                .lines()
-                .map(move |l| (sitename.clone(), l))
-                .par_bridge()
+        .try_fold(A::default, |accumulator, chunk| {
+            let reader = open_chunk(chunk, &json_paths[chunk.file])?;
+            read_lines(reader, chunk.first_line)
+                .try_fold(accumulator, |accumulator, (number, line)| {
+                    add_line(accumulator, chunk.file, number, line)
+                })
+        });
+    let streams_accumulators = streams
+        .par_iter()
+        .flat_map(|chunk| match open_chunk(chunk, &json_paths[chunk.file]) {
+            Ok(reader) => Either::Left(
+                read_lines(reader, chunk.first_line)
+                    .par_bridge()
+                    .map(move |(number, line)| Ok((chunk, number, line))),
+            ),
+            Err(error) => Either::Right(once(Err(error))),
         })
-        .map(|(filename, line)| {
-            let line_data: Line =
-                serde_json::from_str(&line.expect("[ERRROR] No se pudo leer la línea"))
-                    .expect("[ERRROR] No se pudo parsear la línea JSON a un struct Line");
-            let full_text = line_data.texts.join(" ");
-            let words = full_text.split_whitespace().count();
-            let mut tags = HashMap::new();
-            for tag in line_data.tags {
-                tags.insert(tag, Tag::new(1, words));
-            }
-            let chatty_tags = vec![];
-            let site = Site::new(1, words, tags, chatty_tags);
-            let mut hash_site: HashMap<String, Site> = HashMap::new();
-            hash_site.insert(filename, site);
-            ProcessedSites::new(
-                PADRON.to_string(),
-                hash_site,
-                HashMap::new(),
-                HashMap::new(),
-            )
+        .try_fold(A::default, |accumulator, item| {
+            let (chunk, number, line) = item?;
+            add_line(accumulator, chunk.file, number, line)
+        });
+    ranges_accumulators
+        .chain(streams_accumulators)
+        .try_reduce(A::default, |accumulator1, accumulator2| {
+            Ok(merge(accumulator1, accumulator2))
         })
-        .reduce(
-            || {
-                ProcessedSites::new(
-                    PADRON.to_string(),
-                    HashMap::new(),
-                    HashMap::new(),
-                    HashMap::new(),
-                )
-            },
-            |mut total_sites, mut processed_sites| {
-                processed_sites
-                    .sites
-                    .iter_mut()
-                    .for_each(|(site_name, site)| {
-                        total_sites
-                            .sites
-                            .entry(site_name.to_string())
-                            .and_modify(|s| s.add(site))
-                            .or_insert(site.clone());
-                        site.tags.iter().for_each(|(tag_name, tag)| {
-                            total_sites
-                                .tags
-                                .entry(tag_name.to_string())
-                                .and_modify(|t| t.add(tag))
-                                .or_insert(tag.clone());
-                        });
-                    });
-                total_sites
-            },
-        );
-    processed_sites
+}
+
+/// Abre el chunk del archivo en `path`, convirtiendo el error de apertura en ProcessingError.
+fn open_chunk(chunk: &Chunk, path: &Path) -> Result<Box<dyn BufRead + Send>, ProcessingError> {
+    chunk.open(path).map_err(|source| ProcessingError::Io {
+        path: path.to_path_buf(),
+        line: None,
+        source,
+    })
+}
+
+/// Itera las líneas del reader junto con su número, empezando en `first_line`.
+/// Si falla la lectura por algo distinto a una línea con UTF-8 inválido (por ejemplo un archivo comprimido corrupto), se devuelve ese error y se deja de leer el archivo.
+fn read_lines(
+    reader: Box<dyn BufRead + Send>,
+    first_line: usize,
+) -> impl Iterator<Item = (usize, io::Result<String>)> + Send {
+    let mut failed = false;
+    reader
+        .lines()
+        .enumerate()
+        .take_while(move |(_, line)| {
+            let keep = !failed;
+            failed = matches!(line, Err(e) if e.kind() != io::ErrorKind::InvalidData);
+            keep
+        })
+        .map(move |(i, line)| (first_line + i, line))
 }
 
 #[cfg(test)]
@@ -175,6 +401,16 @@ mod tests {
     use std::time::Instant;
 
     use super::*;
+    use crate::{
+        dedup::DedupScope,
+        error::ErrorPolicy,
+        input::open_reader,
+        top_words::{TopWords, TopWordsConfig, TopWordsMode, DEFAULT_STOPWORDS},
+        vocabulary::{Vocabulary, VocabularyMode},
+    };
+
+    const TEST_DATA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test_data");
+    const DATA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/data");
 
     #[test]
     fn create_processed_sites_correctly() {
@@ -247,7 +483,7 @@ mod tests {
 
         let mut processed_sites = ProcessedSites::new("106160".to_string(), sites, tags, totals);
 
-        processed_sites.process_chatty();
+        processed_sites.process_chatty(&ChattyConfig::default());
 
         assert_eq!(
             processed_sites.totals.get("chatty_sites").unwrap()[0],
@@ -273,6 +509,11 @@ mod tests {
             processed_sites.totals.get("chatty_tags").unwrap()[3],
             "tag4".to_string()
         );
+        assert_eq!(processed_sites.sites["site1"].chatty_tags, ["tag2", "tag1"]);
+
+        processed_sites.process_chatty(&ChattyConfig::default());
+        assert_eq!(processed_sites.sites["site1"].chatty_tags, ["tag2", "tag1"]);
+        assert_eq!(processed_sites.totals["chatty_sites"].len(), 2);
     }
 
     #[test]
@@ -324,7 +565,7 @@ mod tests {
             (&num6, 56.7970283287),
         ];
 
-        let result = get_chatty(items);
+        let result = get_chatty(items, DEFAULT_TOP_N);
 
         let correct_result = vec![
             "num2".to_string(),
@@ -342,13 +583,599 @@ mod tests {
         assert_eq!(result, correct_result);
     }
 
+    #[test]
+    fn get_chatty_with_custom_top_n() {
+        let num1 = "num1".to_string();
+        let num2 = "num2".to_string();
+        let num3 = "num3".to_string();
+        let items = vec![(&num1, 1.0), (&num2, 3.0), (&num3, 2.0)];
+
+        assert_eq!(
+            get_chatty(items.clone(), 2),
+            ["num2".to_string(), "num3".to_string()]
+        );
+        assert_eq!(get_chatty(items, 5).len(), 3);
+    }
+
+    #[test]
+    fn get_terse_correctly() {
+        let num1 = "num1".to_string();
+        let num2 = "num2".to_string();
+        let num3 = "num3".to_string();
+        let num4 = "num4".to_string();
+        let items = vec![(&num1, 3.0), (&num2, 1.0), (&num3, 2.0), (&num4, 1.0)];
+
+        assert_eq!(
+            get_terse(items, 3),
+            ["num2".to_string(), "num4".to_string(), "num3".to_string()]
+        );
+    }
+
+    #[test]
+    fn process_chatty_with_config() {
+        let json_paths = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
+        let mut processed_sites = process_sites(json_paths, &ProcessOptions::default()).unwrap();
+
+        processed_sites.process_chatty(&ChattyConfig {
+            metric: ChattyMetric::Questions,
+            chatty_sites: 1,
+            chatty_tags: 2,
+            site_chatty_tags: 3,
+            terse_sites: 1,
+            terse_tags: 0,
+            site_terse_tags: 2,
+            thresholds: ChattyThresholds::default(),
+        });
+
+        assert_eq!(processed_sites.totals.get("chatty_sites").unwrap().len(), 1);
+        assert_eq!(
+            *processed_sites.totals.get("chatty_tags").unwrap(),
+            ["computer-science".to_string(), "2.2-froyo".to_string()]
+        );
+        assert_eq!(processed_sites.totals.get("terse_sites").unwrap().len(), 1);
+        assert!(!processed_sites.totals.contains_key("terse_tags"));
+        let site_academia = processed_sites
+            .sites
+            .get("academia.stackexchange.com")
+            .unwrap();
+        assert_eq!(site_academia.chatty_tags.len(), 3);
+        assert_eq!(site_academia.chatty_tags[0], "computer-science");
+        assert_eq!(site_academia.terse_tags.len(), 2);
+    }
+
+    #[test]
+    fn thresholds_exclude_tags_with_few_questions() {
+        let mut site = Site::new(0, 0, HashMap::new(), vec![]);
+        site.add_question(&WordCounts::new(0, 2000).into(), vec!["rare".to_string()]);
+        (0..200).for_each(|_| {
+            site.add_question(&WordCounts::new(0, 100).into(), vec!["common".to_string()])
+        });
+        (0..200).for_each(|_| {
+            site.add_question(&WordCounts::new(0, 10).into(), vec!["short".to_string()])
+        });
+        let tags = site.tags.clone();
+        let sites = HashMap::from([("site".to_string(), site)]);
+        let mut processed_sites =
+            ProcessedSites::new(PADRON.to_string(), sites, tags, HashMap::new());
+
+        processed_sites.process_chatty(&ChattyConfig::default());
+        assert_eq!(
+            processed_sites.totals.get("chatty_tags").unwrap()[0],
+            "rare"
+        );
+        assert_eq!(processed_sites.thresholds, None);
+
+        let mut config = ChattyConfig::default();
+        config.thresholds.min_questions = 2;
+        processed_sites.process_chatty(&config);
+        assert_eq!(
+            *processed_sites.totals.get("chatty_tags").unwrap(),
+            ["common".to_string(), "short".to_string()]
+        );
+
+        config.thresholds = ChattyThresholds {
+            min_questions: 0,
+            smoothing: Some(10.0),
+        };
+        processed_sites.process_chatty(&config);
+        assert_eq!(
+            processed_sites.totals.get("chatty_tags").unwrap()[0],
+            "rare"
+        );
+        assert_eq!(
+            processed_sites.totals.get("chatty_tags").unwrap()[1],
+            "common"
+        );
+        let json = serde_json::to_value(&processed_sites).unwrap();
+        assert_eq!(json["thresholds"]["smoothing"], 10.0);
+        assert_eq!(json["thresholds"]["min_questions"], 0);
+
+        config.thresholds.smoothing = Some(100.0);
+        processed_sites.process_chatty(&config);
+        assert_eq!(
+            processed_sites.totals.get("chatty_tags").unwrap()[0],
+            "common"
+        );
+    }
+
+    #[test]
+    fn title_and_body_words_add_up() {
+        let json_paths = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
+        let mut processed_sites = process_sites(json_paths, &ProcessOptions::default()).unwrap();
+
+        processed_sites.sites.values().for_each(|site| {
+            assert_eq!(site.title_words + site.body_words, site.words);
+            assert!(site.title_words > 0 && site.title_words < site.body_words);
+            site.tags
+                .values()
+                .for_each(|tag| assert_eq!(tag.title_words + tag.body_words, tag.words));
+        });
+
+        processed_sites.process_chatty(&ChattyConfig {
+            metric: ChattyMetric::MeanTitleWords,
+            ..ChattyConfig::default()
+        });
+        let chatty_sites = processed_sites.totals.get("chatty_sites").unwrap();
+        let title_ratio = |name: &String| {
+            let site = processed_sites.sites.get(name).unwrap();
+            site.title_words as f64 / site.questions as f64
+        };
+        assert!(title_ratio(&chatty_sites[0]) >= title_ratio(&chatty_sites[1]));
+        assert!(title_ratio(&chatty_sites[1]) >= title_ratio(&chatty_sites[2]));
+    }
+
+    #[test]
+    fn tokenizer_is_applied_and_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("site.jsonl"),
+            "{\"texts\": [\"How to use `map`?\", \"I tried:\\n\\n    let x = v.iter().map(f);\\n\\nSee https://doc.rust-lang.org\"], \"tags\": [\"rust\"]}\n",
+        )
+        .unwrap();
+        let json_paths =
+            get_json_paths(&[dir.path().to_string_lossy().to_string()], false).unwrap();
+
+        let process = |tokenizer| {
+            process_sites(
+                json_paths.clone(),
+                &ProcessOptions {
+                    tokenizer,
+                    ..ProcessOptions::default()
+                },
+            )
+            .unwrap()
+        };
+        let whitespace = process(Tokenizer::Whitespace);
+        let prose = process(Tokenizer::Prose);
+
+        assert_eq!(whitespace.tokenizer, Tokenizer::Whitespace);
+        assert_eq!(whitespace.sites["site"].title_words, 4);
+        assert_eq!(whitespace.sites["site"].body_words, 8);
+        assert_eq!(prose.tokenizer, Tokenizer::Prose);
+        assert_eq!(prose.sites["site"].title_words, 3);
+        assert_eq!(prose.sites["site"].body_words, 3);
+        assert_eq!(prose.tags["rust"].words, 6);
+        assert_eq!(serde_json::to_value(&prose).unwrap()["tokenizer"], "prose");
+    }
+
+    #[test]
+    fn counts_code_per_site_and_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("site.jsonl"),
+            concat!(
+                "{\"texts\": [\"Why?\", \"Code:\\n```\\nlet x = 1;\\nx += 1;\\n```\"], \"tags\": [\"rust\", \"syntax\"]}\n",
+                "{\"texts\": [\"What about `map`?\", \"No code here\"], \"tags\": [\"rust\"]}\n",
+                "{\"texts\": [\"Plain\", \"Just text\"], \"tags\": [\"syntax\"]}\n",
+            ),
+        )
+        .unwrap();
+        let json_paths =
+            get_json_paths(&[dir.path().to_string_lossy().to_string()], false).unwrap();
+
+        let processed_sites = process_sites(json_paths, &ProcessOptions::default()).unwrap();
+
+        let site = &processed_sites.sites["site"];
+        assert_eq!(site.code_lines, 2);
+        assert_eq!(site.code_chars, 10 + 7 + 3);
+        assert_eq!(site.code_questions, 2);
+        let rust = &processed_sites.tags["rust"];
+        assert_eq!(
+            (rust.code_lines, rust.code_chars, rust.code_questions),
+            (2, 20, 2)
+        );
+        assert_eq!(rust.code_share(), 1.0);
+        assert_eq!(processed_sites.tags["syntax"].code_share(), 0.5);
+    }
+
+    #[test]
+    fn dedup_drops_repeated_questions_per_site_or_globally() {
+        let dir = tempfile::tempdir().unwrap();
+        let question = "{\"texts\": [\"Mismo  título\", \"cuerpo\"], \"tags\": [\"t\"]}";
+        let repeated = "{\"texts\": [\"mismo título\", \"Cuerpo\"], \"tags\": [\"t\"]}";
+        let other = "{\"texts\": [\"otro título\", \"cuerpo\"], \"tags\": [\"t\"]}";
+        let path_a = dir.path().join("a.stackexchange.com.jsonl");
+        let path_b = dir.path().join("b.stackexchange.com.jsonl");
+        std::fs::write(&path_a, [question, other, repeated, question].join("\n")).unwrap();
+        std::fs::write(&path_b, [repeated, other].join("\n")).unwrap();
+        let process = |dedup| {
+            process_sites(
+                vec![path_a.clone(), path_b.clone()],
+                &ProcessOptions {
+                    dedup,
+                    chunk_size: 16,
+                    ..ProcessOptions::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let processed_sites = process(None);
+        assert_eq!(processed_sites.sites["a.stackexchange.com"].questions, 4);
+        assert_eq!(
+            processed_sites.sites["a.stackexchange.com"].removed_duplicates,
+            None
+        );
+
+        let processed_sites = process(Some(DedupScope::Site));
+        let (site_a, site_b) = (
+            &processed_sites.sites["a.stackexchange.com"],
+            &processed_sites.sites["b.stackexchange.com"],
+        );
+        assert_eq!((site_a.questions, site_a.removed_duplicates), (2, Some(2)));
+        assert_eq!((site_b.questions, site_b.removed_duplicates), (2, Some(0)));
+        assert_eq!(processed_sites.tags["t"].questions, 4);
+        let json = serde_json::to_value(&processed_sites).unwrap();
+        assert_eq!(
+            json["sites"]["a.stackexchange.com"]["removed_duplicates"],
+            2
+        );
+
+        let processed_sites = process(Some(DedupScope::Global));
+        let removed: usize = processed_sites
+            .sites
+            .values()
+            .map(|site| site.removed_duplicates.unwrap())
+            .sum();
+        assert_eq!(removed, 4);
+        assert_eq!(processed_sites.tags["t"].questions, 2);
+    }
+
+    #[test]
+    fn sites_left_without_questions_stay_out_of_rankings() {
+        let dir = tempfile::tempdir().unwrap();
+        let question = "{\"texts\": [\"Mismo título\", \"cuerpo\"], \"tags\": [\"t\"]}";
+        let path_a = dir.path().join("a.stackexchange.com.jsonl");
+        let path_b = dir.path().join("b.stackexchange.com.jsonl");
+        std::fs::write(&path_a, question).unwrap();
+        std::fs::write(&path_b, question).unwrap();
+        let mut processed_sites = process_sites(
+            vec![path_b, path_a],
+            &ProcessOptions {
+                dedup: Some(DedupScope::Global),
+                ..ProcessOptions::default()
+            },
+        )
+        .unwrap();
+        let site_b = &processed_sites.sites["b.stackexchange.com"];
+        assert_eq!((site_b.questions, site_b.removed_duplicates), (0, Some(1)));
+        assert_eq!(site_b.code_share(), 0.0);
+        assert_eq!(site_b.metric(ChattyMetric::MeanWords), 0.0);
+
+        processed_sites.process_chatty(&ChattyConfig {
+            terse_sites: 10,
+            ..ChattyConfig::default()
+        });
+        assert_eq!(
+            processed_sites.totals["chatty_sites"],
+            ["a.stackexchange.com"]
+        );
+        assert_eq!(
+            processed_sites.totals["terse_sites"],
+            ["a.stackexchange.com"]
+        );
+        let json = serde_json::to_string(&processed_sites).unwrap();
+        assert!(!json.contains("NaN") && !json.contains("null"));
+    }
+
+    #[test]
+    fn breaks_down_sites_by_language() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mixed.stackexchange.com.jsonl");
+        std::fs::write(
+            &path,
+            [
+                "{\"texts\": [\"How do I read the file\", \"with the new API?\"], \"tags\": [\"io\"]}",
+                "{\"texts\": [\"¿Cómo puedo leer el archivo\", \"con la nueva API?\"], \"tags\": [\"io\"]}",
+                "{\"texts\": [\"ファイルを読む\", \"新しいAPIで\"], \"tags\": [\"io\"]}",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        let process = |languages| {
+            process_sites(
+                vec![path.clone()],
+                &ProcessOptions {
+                    languages,
+                    ..ProcessOptions::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let processed_sites = process(false);
+        let site = &processed_sites.sites["mixed.stackexchange.com"];
+        assert_eq!(site.languages, None);
+        assert_eq!(site.words, 10 + 9 + 2);
+
+        let processed_sites = process(true);
+        let site = &processed_sites.sites["mixed.stackexchange.com"];
+        let languages = site.languages.as_ref().unwrap();
+        assert_eq!(languages.len(), 3);
+        assert_eq!(languages[&Language::En].questions, 1);
+        assert_eq!(languages[&Language::Es].words, 9);
+        // El japonés se mide en caracteres: 7 del título y 5 del cuerpo, donde "API" cuenta como una palabra
+        assert_eq!(languages[&Language::Ja].words, 12);
+        assert_eq!(site.words, 10 + 9 + 12);
+        assert_eq!(processed_sites.tags["io"].words, 31);
+
+        // El vocabulario separa las palabras japonesas por caracteres, igual que su cantidad
+        let processed_sites = process_sites(
+            vec![path.clone()],
+            &ProcessOptions {
+                languages: true,
+                vocabulary: Some(VocabularyMode::Exact),
+                ..ProcessOptions::default()
+            },
+        )
+        .unwrap();
+        let vocabulary = processed_sites.sites["mixed.stackexchange.com"]
+            .vocabulary
+            .as_ref()
+            .unwrap();
+        assert_eq!(vocabulary.tokens(), 31);
+        // "api?" se repite en inglés y en español
+        assert_eq!(vocabulary.distinct(), 9 + 8 + 12);
+    }
+
+    #[test]
+    fn counts_readability_only_in_english() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mixed.stackexchange.com.jsonl");
+        std::fs::write(
+            &path,
+            [
+                "{\"texts\": [\"How do I read the file\", \"with the new API?\"], \"tags\": [\"io\"]}",
+                "{\"texts\": [\"¿Cómo puedo leer el archivo\", \"con la nueva API?\"], \"tags\": [\"io\"]}",
+                "{\"texts\": [\"ファイルを読む\", \"新しいAPIで\"], \"tags\": [\"io\"]}",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        // Solo cuenta la pregunta en inglés, aunque no se pida el desglose por idioma
+        let processed_sites = process_sites(
+            vec![path],
+            &ProcessOptions {
+                readability: true,
+                ..ProcessOptions::default()
+            },
+        )
+        .unwrap();
+        let readability = processed_sites.sites["mixed.stackexchange.com"]
+            .readability
+            .unwrap();
+        assert_eq!((readability.sentences, readability.words), (2, 10));
+        assert_eq!(processed_sites.readability_language, Some(Language::En));
+        let json = serde_json::to_value(&processed_sites).unwrap();
+        assert_eq!(json["readability_language"], "en");
+    }
+
+    #[test]
+    fn counts_tags_per_question_and_untagged() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("site.jsonl"),
+            concat!(
+                "{\"texts\": [\"a\", \"b\"], \"tags\": []}\n",
+                "{\"texts\": [\"a\", \"b\"], \"tags\": [\"x\", \"y\", \"x\"]}\n",
+                "{\"texts\": [\"a\", \"b\"], \"tags\": [\"x\", \"y\", \"z\", \"w\"]}\n",
+            ),
+        )
+        .unwrap();
+        let json_paths =
+            get_json_paths(&[dir.path().to_string_lossy().to_string()], false).unwrap();
+
+        let processed_sites = process_sites(json_paths, &ProcessOptions::default()).unwrap();
+
+        let site = &processed_sites.sites["site"];
+        assert_eq!(site.questions, 3);
+        assert_eq!(site.tags_per_question.untagged(), 1);
+        assert_eq!(site.tags_per_question.count(2), 1);
+        assert_eq!(site.tags_per_question.count(4), 1);
+        assert_eq!(site.tags_per_question.mean(), 2.0);
+        assert_eq!(processed_sites.tags["x"].questions, 2);
+        let json = serde_json::to_value(&processed_sites).unwrap();
+        assert_eq!(json["sites"]["site"]["tags_per_question"]["untagged"], 1);
+        assert_eq!(
+            json["sites"]["site"]["tags_per_question"]["histogram"],
+            serde_json::json!([1, 0, 1, 0, 1])
+        );
+    }
+
+    #[test]
+    fn vocabulary_sketch_is_close_to_exact() {
+        let json_paths = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
+        let process = |vocabulary| {
+            let options = ProcessOptions {
+                vocabulary,
+                chunk_size: 4096,
+                ..ProcessOptions::default()
+            };
+            process_sites(json_paths.clone(), &options).unwrap()
+        };
+        let without = process(None);
+        let exact = process(Some(VocabularyMode::Exact));
+        let sketch = process(Some(VocabularyMode::Sketch));
+        assert!(without.sites.values().all(|site| site.vocabulary.is_none()));
+
+        let relative_error = |sketch: &Option<Vocabulary>, exact: &Option<Vocabulary>| {
+            let (sketch, exact) = (sketch.as_ref().unwrap(), exact.as_ref().unwrap());
+            assert_eq!(sketch.tokens(), exact.tokens());
+            (sketch.distinct() as f64 - exact.distinct() as f64).abs() / exact.distinct() as f64
+        };
+        exact.sites.iter().for_each(|(name, site)| {
+            let vocabulary = site.vocabulary.as_ref().unwrap();
+            assert_eq!(vocabulary.tokens(), site.words);
+            assert!(vocabulary.distinct() < site.words);
+            assert!(relative_error(&sketch.sites[name].vocabulary, &site.vocabulary) < 0.02);
+        });
+        exact.tags.iter().for_each(|(name, tag)| {
+            assert_eq!(tag.vocabulary.as_ref().unwrap().tokens(), tag.words);
+            assert!(relative_error(&sketch.tags[name].vocabulary, &tag.vocabulary) < 0.02);
+        });
+        let json = serde_json::to_value(&sketch).unwrap();
+        let site = &json["sites"]["academia.stackexchange.com"]["vocabulary"];
+        assert_eq!(site["exact"], false);
+        assert!(site["type_token_ratio"].as_f64().unwrap() > 0.0);
+    }
+
+    #[test]
+    fn top_words_sketch_bounds_exact_counts() {
+        let json_paths = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
+        let process = |mode| {
+            let options = ProcessOptions {
+                tokenizer: Tokenizer::Unicode,
+                top_words: Some(TopWordsConfig::new(3, mode)),
+                chunk_size: 4096,
+                ..ProcessOptions::default()
+            };
+            process_sites(json_paths.clone(), &options).unwrap()
+        };
+        let exact = process(TopWordsMode::Exact);
+        let sketch = process(TopWordsMode::Sketch);
+
+        let check = |exact: &Option<TopWords>, sketch: &Option<TopWords>| {
+            let (exact, sketch) = (exact.as_ref().unwrap(), sketch.as_ref().unwrap());
+            exact.top().iter().for_each(|frequency| {
+                assert!(!DEFAULT_STOPWORDS.contains(&frequency.word.as_str()));
+                let (lower, upper) = sketch.count_bounds(&frequency.word);
+                assert!(lower <= frequency.count && frequency.count <= upper);
+            });
+            sketch.top().iter().for_each(|frequency| {
+                let (real, _) = exact.count_bounds(&frequency.word);
+                let (lower, upper) = sketch.count_bounds(&frequency.word);
+                assert!(lower <= real && real <= upper);
+            });
+        };
+        exact.sites.iter().for_each(|(name, site)| {
+            assert_eq!(site.top_words.as_ref().unwrap().top().len(), 3);
+            check(&site.top_words, &sketch.sites[name].top_words);
+        });
+        exact
+            .tags
+            .iter()
+            .for_each(|(name, tag)| check(&tag.top_words, &sketch.tags[name].top_words));
+        let json = serde_json::to_value(&sketch).unwrap();
+        let top_words = json["sites"]["academia.stackexchange.com"]["top_words"]
+            .as_array()
+            .unwrap();
+        assert_eq!(top_words.len(), 3);
+        assert!(top_words[0]["count"].as_u64().unwrap() > 0);
+        assert!(json["tags"]
+            .as_object()
+            .unwrap()
+            .values()
+            .all(|tag| tag["top_words"].is_array()));
+    }
+
+    #[test]
+    fn counts_cooccurrence_per_site_and_globally() {
+        let json_paths = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
+        let processed_sites =
+            process_sites(json_paths.clone(), &ProcessOptions::default()).unwrap();
+        assert!(processed_sites.cooccurrence.is_none());
+        assert!(serde_json::to_value(&processed_sites)
+            .unwrap()
+            .get("cooccurrence")
+            .is_none());
+
+        let options = ProcessOptions {
+            cooccurrence: true,
+            chunk_size: 2048,
+            ..ProcessOptions::default()
+        };
+        let processed_sites = process_sites(json_paths.clone(), &options).unwrap();
+        let global = processed_sites.cooccurrence.as_ref().unwrap();
+        let mut expected = TagPairs::default();
+        processed_sites.sites.values().for_each(|site| {
+            let pairs = site.cooccurrence.as_ref().unwrap();
+            assert!(!pairs.is_empty());
+            expected.add(pairs);
+        });
+        assert_eq!(*global, expected);
+        global.edges().iter().for_each(|(tag_1, tag_2, count)| {
+            assert!(tag_1 < tag_2);
+            assert!(*count <= processed_sites.tags[*tag_1].questions);
+            assert!(*count <= processed_sites.tags[*tag_2].questions);
+        });
+
+        let pruned = process_sites(
+            json_paths,
+            &ProcessOptions {
+                min_pair_count: 2,
+                ..options
+            },
+        )
+        .unwrap();
+        let pruned_global = pruned.cooccurrence.as_ref().unwrap();
+        assert!(pruned_global.len() < global.len());
+        pruned_global
+            .edges()
+            .iter()
+            .for_each(|(tag_1, tag_2, count)| {
+                assert!(*count >= 2);
+                assert_eq!(global.count(tag_1, tag_2), *count);
+            });
+    }
+
+    #[test]
+    fn median_ranking_uses_word_lengths() {
+        let mut site1 = Site::new(0, 0, HashMap::new(), vec![]);
+        [1, 1, 100]
+            .iter()
+            .for_each(|words| site1.add_question(&WordCounts::new(0, *words).into(), vec![]));
+        let mut site2 = Site::new(0, 0, HashMap::new(), vec![]);
+        [10, 10, 10]
+            .iter()
+            .for_each(|words| site2.add_question(&WordCounts::new(0, *words).into(), vec![]));
+        let sites = HashMap::from([("site1".to_string(), site1), ("site2".to_string(), site2)]);
+        let mut processed_sites =
+            ProcessedSites::new(PADRON.to_string(), sites, HashMap::new(), HashMap::new());
+
+        processed_sites.process_chatty(&ChattyConfig::default());
+        assert_eq!(
+            processed_sites.totals.get("chatty_sites").unwrap()[0],
+            "site1"
+        );
+
+        processed_sites.process_chatty(&ChattyConfig {
+            metric: ChattyMetric::MedianWords,
+            ..ChattyConfig::default()
+        });
+        assert_eq!(
+            processed_sites.totals.get("chatty_sites").unwrap()[0],
+            "site2"
+        );
+    }
+
     #[test]
     fn get_correct_sites() {
-        let json_paths = get_json_paths("/test_data");
+        let json_paths = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
 
-        let mut processed_sites = process_sites(json_paths);
+        let mut processed_sites = process_sites(json_paths, &ProcessOptions::default()).unwrap();
 
-        processed_sites.process_chatty();
+        processed_sites.process_chatty(&ChattyConfig::default());
 
         let site_academia = processed_sites
             .sites
@@ -365,11 +1192,11 @@ mod tests {
 
     #[test]
     fn get_correct_total_tags() {
-        let json_paths = get_json_paths("/test_data");
+        let json_paths = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
 
-        let mut processed_sites = process_sites(json_paths);
+        let mut processed_sites = process_sites(json_paths, &ProcessOptions::default()).unwrap();
 
-        processed_sites.process_chatty();
+        processed_sites.process_chatty(&ChattyConfig::default());
 
         let tag_computer_science = processed_sites.tags.get("computer-science").unwrap();
 
@@ -381,11 +1208,11 @@ mod tests {
 
     #[test]
     fn get_correct_chatty_tags_chatty_sites() {
-        let json_paths = get_json_paths("/test_data");
+        let json_paths = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
 
-        let mut processed_sites = process_sites(json_paths);
+        let mut processed_sites = process_sites(json_paths, &ProcessOptions::default()).unwrap();
 
-        processed_sites.process_chatty();
+        processed_sites.process_chatty(&ChattyConfig::default());
 
         let chatty_sites = processed_sites.totals.get("chatty_sites").unwrap();
         assert_eq!(
@@ -415,6 +1242,385 @@ mod tests {
         );
     }
 
+    #[test]
+    fn compressed_inputs_match_uncompressed() {
+        let json_paths = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
+        let processed_sites =
+            process_sites(json_paths.clone(), &ProcessOptions::default()).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let compressed_paths: Vec<PathBuf> = json_paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let contents = std::fs::read(path).unwrap();
+                let file_name = path.file_name().unwrap().to_str().unwrap();
+                if i % 2 == 0 {
+                    let compressed_path = dir.path().join(format!("{}.gz", file_name));
+                    let mut encoder = flate2::write::GzEncoder::new(
+                        std::fs::File::create(&compressed_path).unwrap(),
+                        flate2::Compression::default(),
+                    );
+                    std::io::Write::write_all(&mut encoder, &contents).unwrap();
+                    encoder.finish().unwrap();
+                    compressed_path
+                } else {
+                    let compressed_path = dir.path().join(format!("{}.zst", file_name));
+                    std::fs::write(
+                        &compressed_path,
+                        zstd::encode_all(contents.as_slice(), 0).unwrap(),
+                    )
+                    .unwrap();
+                    compressed_path
+                }
+            })
+            .collect();
+        let processed_compressed =
+            process_sites(compressed_paths, &ProcessOptions::default()).unwrap();
+
+        assert_eq!(
+            processed_sites.sites.len(),
+            processed_compressed.sites.len()
+        );
+        assert_eq!(processed_sites.tags.len(), processed_compressed.tags.len());
+        processed_sites.sites.iter().for_each(|(site_name, site)| {
+            let compressed_site = processed_compressed.sites.get(site_name).unwrap();
+            assert_eq!(site.questions, compressed_site.questions);
+            assert_eq!(site.words, compressed_site.words);
+            assert_eq!(site.tags.len(), compressed_site.tags.len());
+        });
+        processed_sites.tags.iter().for_each(|(tag_name, tag)| {
+            let compressed_tag = processed_compressed.tags.get(tag_name).unwrap();
+            assert_eq!(tag.questions, compressed_tag.questions);
+            assert_eq!(tag.words, compressed_tag.words);
+        });
+    }
+
+    /// Escribe un archivo con una línea válida, una con JSON inválido y una sin tags.
+    fn write_invalid_file(dir: &Path) -> PathBuf {
+        let path = dir.join("invalid.stackexchange.com.jsonl");
+        std::fs::write(
+            &path,
+            "{\"texts\": [\"a b\", \"c\"], \"tags\": [\"t\"]}\nno es json\n{\"texts\": [\"a\"]}\n",
+        )
+        .unwrap();
+        path
+    }
+
+    fn skip_and_count() -> ProcessOptions {
+        ProcessOptions {
+            error_policy: ErrorPolicy::SkipAndCount,
+            ..ProcessOptions::default()
+        }
+    }
+
+    #[test]
+    fn fail_fast_returns_the_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_invalid_file(dir.path());
+
+        let result = process_sites(vec![path], &ProcessOptions::default());
+
+        assert!(matches!(
+            result,
+            Err(ProcessingError::Json { line: 2, .. })
+                | Err(ProcessingError::MissingField {
+                    line: 3,
+                    field: "tags",
+                    ..
+                })
+        ));
+    }
+
+    #[test]
+    fn skip_policies_count_invalid_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_invalid_file(dir.path());
+
+        for error_policy in [ErrorPolicy::SkipAndCount, ErrorPolicy::SkipAndLog] {
+            let processed_sites = process_sites(
+                vec![path.clone()],
+                &ProcessOptions {
+                    error_policy,
+                    ..ProcessOptions::default()
+                },
+            )
+            .unwrap();
+
+            let site = processed_sites
+                .sites
+                .get("invalid.stackexchange.com")
+                .unwrap();
+            assert_eq!(site.questions, 1);
+            assert_eq!(site.words, 3);
+            assert_eq!(processed_sites.skipped_lines, 2);
+
+            let site_errors = processed_sites
+                .errors
+                .as_ref()
+                .unwrap()
+                .get("invalid.stackexchange.com")
+                .unwrap();
+            assert_eq!(site_errors.parse_errors, 1);
+            assert_eq!(site_errors.missing_tags, 1);
+            assert_eq!(site_errors.missing_texts, 0);
+            assert_eq!(site_errors.unreadable_lines, 0);
+            assert_eq!(site_errors.sample_lines, [2, 3]);
+        }
+    }
+
+    #[test]
+    fn truncated_file_is_a_file_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("truncated.stackexchange.com.jsonl.gz");
+        let line = "{\"texts\": [\"a b\"], \"tags\": [\"t\"]}\n";
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, line.repeat(1000).as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        std::fs::write(&path, &compressed[..compressed.len() / 2]).unwrap();
+
+        assert!(process_sites(vec![path.clone()], &ProcessOptions::default()).is_err());
+        let processed_sites = process_sites(vec![path], &skip_and_count()).unwrap();
+
+        assert_eq!(processed_sites.skipped_lines, 0);
+        assert_eq!(processed_sites.unreadable_files, 1);
+        let site_errors = &processed_sites.errors.as_ref().unwrap()["truncated.stackexchange.com"];
+        assert_eq!(site_errors.total(), 0);
+        assert_eq!(site_errors.file_errors.len(), 1);
+        let questions = processed_sites.sites["truncated.stackexchange.com"].questions;
+        assert!(questions > 0 && questions < 1000);
+    }
+
+    #[test]
+    fn error_report_includes_sites_without_errors() {
+        let json_paths = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
+
+        let processed_sites = process_sites(json_paths, &skip_and_count()).unwrap();
+
+        let errors = processed_sites.errors.unwrap();
+        assert_eq!(errors.len(), 3);
+        errors
+            .values()
+            .for_each(|site_errors| assert_eq!(*site_errors, SiteErrors::default()));
+    }
+
+    #[test]
+    fn small_chunks_give_the_same_result() {
+        let json_paths = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
+
+        let mut processed_sites =
+            process_sites(json_paths.clone(), &ProcessOptions::default()).unwrap();
+        let mut processed_sites_chunks = process_sites(
+            json_paths,
+            &ProcessOptions {
+                chunk_size: 100,
+                ..ProcessOptions::default()
+            },
+        )
+        .unwrap();
+        processed_sites.process_chatty(&ChattyConfig::default());
+        processed_sites_chunks.process_chatty(&ChattyConfig::default());
+
+        assert_same_sites_and_tags(&processed_sites, &processed_sites_chunks);
+        assert_eq!(processed_sites.totals, processed_sites_chunks.totals);
+    }
+
+    #[test]
+    fn small_chunks_keep_line_numbers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_invalid_file(dir.path());
+
+        let processed_sites = process_sites(
+            vec![path],
+            &ProcessOptions {
+                chunk_size: 1,
+                ..skip_and_count()
+            },
+        )
+        .unwrap();
+
+        let site_errors = processed_sites
+            .errors
+            .as_ref()
+            .unwrap()
+            .get("invalid.stackexchange.com")
+            .unwrap();
+        assert_eq!(site_errors.sample_lines, [2, 3]);
+    }
+
+    #[test]
+    fn missing_inputs_return_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing_path = dir.path().join("missing.jsonl");
+
+        assert!(matches!(
+            get_json_paths(&[missing_path.to_str().unwrap().to_string()], false),
+            Err(ProcessingError::Directory { .. })
+        ));
+        assert!(matches!(
+            process_sites(vec![missing_path], &skip_and_count()),
+            Err(ProcessingError::Io { line: None, .. })
+        ));
+    }
+
+    /// Implementación anterior de process_sites, que crea un ProcessedSites por línea y los une de a pares clonando Sites y Tags.
+    /// Se usa como referencia para verificar que el fold por thread da el mismo resultado, y para comparar los tiempos.
+    fn process_sites_per_line(json_paths: Vec<PathBuf>) -> ProcessedSites {
+        json_paths
+            .par_iter()
+            .flat_map(|path| {
+                let sitename = get_site_name(path);
+                open_reader(path)
+                    .unwrap()
+                    .lines()
+                    .map(move |l| (sitename.clone(), l))
+                    .par_bridge()
+            })
+            .map(|(sitename, line)| {
+                let line_data = crate::line::Line::parse(&line.unwrap()).unwrap();
+                let words = line_data.texts.join(" ").split_whitespace().count();
+                let mut tags = HashMap::new();
+                for tag in line_data.tags {
+                    tags.insert(tag, Tag::new(1, words));
+                }
+                let site = Site::new(1, words, tags, vec![]);
+                ProcessedSites::new(
+                    PADRON.to_string(),
+                    HashMap::from([(sitename, site)]),
+                    HashMap::new(),
+                    HashMap::new(),
+                )
+            })
+            .reduce(
+                || {
+                    ProcessedSites::new(
+                        PADRON.to_string(),
+                        HashMap::new(),
+                        HashMap::new(),
+                        HashMap::new(),
+                    )
+                },
+                |mut total_sites, processed_sites| {
+                    processed_sites.sites.iter().for_each(|(site_name, site)| {
+                        total_sites
+                            .sites
+                            .entry(site_name.to_string())
+                            .and_modify(|s| s.add(site))
+                            .or_insert(site.clone());
+                        site.tags.iter().for_each(|(tag_name, tag)| {
+                            total_sites
+                                .tags
+                                .entry(tag_name.to_string())
+                                .and_modify(|t| t.add(tag))
+                                .or_insert(tag.clone());
+                        });
+                    });
+                    total_sites
+                },
+            )
+    }
+
+    /// Escribe en `dir` los archivos de test_data repetidos `times` veces, agregando una línea con un tag repetido.
+    fn write_repeated_test_data(dir: &Path, times: usize) -> Vec<PathBuf> {
+        get_json_paths(&[TEST_DATA_PATH.to_string()], false)
+            .unwrap()
+            .iter()
+            .map(|path| {
+                let contents = std::fs::read_to_string(path)
+                    .unwrap()
+                    .trim_end()
+                    .to_string()
+                    + "\n";
+                let repeated_path = dir.join(path.file_name().unwrap());
+                std::fs::write(
+                    &repeated_path,
+                    contents.repeat(times)
+                        + "{\"texts\": [\"a b\", \"c\"], \"tags\": [\"dup\", \"dup\"]}\n",
+                )
+                .unwrap();
+                repeated_path
+            })
+            .collect()
+    }
+
+    /// Verifica que dos ProcessedSites tengan los mismos Sites y Tags, con las mismas cantidades.
+    fn assert_same_sites_and_tags(
+        processed_sites1: &ProcessedSites,
+        processed_sites2: &ProcessedSites,
+    ) {
+        assert_eq!(processed_sites1.sites.len(), processed_sites2.sites.len());
+        processed_sites1
+            .sites
+            .iter()
+            .for_each(|(site_name, site1)| {
+                let site2 = processed_sites2.sites.get(site_name).unwrap();
+                assert_eq!(site1.questions, site2.questions);
+                assert_eq!(site1.words, site2.words);
+                assert_eq!(site1.tags.len(), site2.tags.len());
+                site1.tags.iter().for_each(|(tag_name, tag1)| {
+                    let tag2 = site2.tags.get(tag_name).unwrap();
+                    assert_eq!(tag1.questions, tag2.questions);
+                    assert_eq!(tag1.words, tag2.words);
+                });
+            });
+        assert_eq!(processed_sites1.tags.len(), processed_sites2.tags.len());
+        processed_sites1.tags.iter().for_each(|(tag_name, tag1)| {
+            let tag2 = processed_sites2.tags.get(tag_name).unwrap();
+            assert_eq!(tag1.questions, tag2.questions);
+            assert_eq!(tag1.words, tag2.words);
+        });
+    }
+
+    #[test]
+    fn fold_matches_per_line_processing() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_paths = write_repeated_test_data(dir.path(), 20);
+
+        let mut processed_sites_fold =
+            process_sites(json_paths.clone(), &ProcessOptions::default()).unwrap();
+        let mut processed_sites_per_line = process_sites_per_line(json_paths);
+        processed_sites_fold.process_chatty(&ChattyConfig::default());
+        processed_sites_per_line.process_chatty(&ChattyConfig::default());
+
+        assert_same_sites_and_tags(&processed_sites_fold, &processed_sites_per_line);
+        assert_eq!(processed_sites_fold.totals, processed_sites_per_line.totals);
+        assert_eq!(
+            processed_sites_fold
+                .sites
+                .get("anime.stackexchange.com")
+                .unwrap()
+                .tags
+                .get("dup")
+                .unwrap()
+                .questions,
+            1
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn fold_is_faster_than_per_line_processing() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_paths = write_repeated_test_data(dir.path(), 20_000);
+
+        let start_per_line = Instant::now();
+        let processed_sites_per_line = process_sites_per_line(json_paths.clone());
+        let time_per_line = start_per_line.elapsed();
+
+        let start_fold = Instant::now();
+        let processed_sites_fold = process_sites(json_paths, &ProcessOptions::default()).unwrap();
+        let time_fold = start_fold.elapsed();
+
+        println!(
+            "Por línea: {:?}, fold por thread: {:?} ({:.2}x)",
+            time_per_line,
+            time_fold,
+            time_per_line.as_secs_f64() / time_fold.as_secs_f64()
+        );
+        assert_same_sites_and_tags(&processed_sites_fold, &processed_sites_per_line);
+        assert!(time_fold < time_per_line);
+    }
+
     #[test]
     #[ignore]
     fn processes_faster_with_more_threads() {
@@ -426,9 +1632,10 @@ mod tests {
                 |thread| thread.run(),
                 |pool| {
                     pool.install(|| {
-                        let json_paths1 = get_json_paths("/data");
-                        let mut processed_sites1 = process_sites(json_paths1);
-                        processed_sites1.process_chatty();
+                        let json_paths1 = get_json_paths(&[DATA_PATH.to_string()], false).unwrap();
+                        let mut processed_sites1 =
+                            process_sites(json_paths1, &ProcessOptions::default()).unwrap();
+                        processed_sites1.process_chatty(&ChattyConfig::default());
                     })
                 },
             )
@@ -443,9 +1650,10 @@ mod tests {
                 |thread| thread.run(),
                 |pool| {
                     pool.install(|| {
-                        let json_paths4 = get_json_paths("/data");
-                        let mut processed_sites4 = process_sites(json_paths4);
-                        processed_sites4.process_chatty();
+                        let json_paths4 = get_json_paths(&[DATA_PATH.to_string()], false).unwrap();
+                        let mut processed_sites4 =
+                            process_sites(json_paths4, &ProcessOptions::default()).unwrap();
+                        processed_sites4.process_chatty(&ChattyConfig::default());
                     })
                 },
             )
@@ -462,9 +1670,9 @@ mod tests {
             .num_threads(1)
             .build()
             .expect("[ERROR] No se pudo iniciar Rayon con la cantidad de threads indicada");
-        let json_paths1 = get_json_paths("/test_data");
-        let mut processed_sites1 = process_sites(json_paths1);
-        processed_sites1.process_chatty();
+        let json_paths1 = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
+        let mut processed_sites1 = process_sites(json_paths1, &ProcessOptions::default()).unwrap();
+        processed_sites1.process_chatty(&ChattyConfig::default());
         drop(threadpool_1_thread);
 
         // Process with 4 threads
@@ -472,9 +1680,9 @@ mod tests {
             .num_threads(8)
             .build()
             .expect("[ERROR] No se pudo iniciar Rayon con la cantidad de threads indicada");
-        let json_paths4 = get_json_paths("/test_data");
-        let mut processed_sites4 = process_sites(json_paths4);
-        processed_sites4.process_chatty();
+        let json_paths4 = get_json_paths(&[TEST_DATA_PATH.to_string()], false).unwrap();
+        let mut processed_sites4 = process_sites(json_paths4, &ProcessOptions::default()).unwrap();
+        processed_sites4.process_chatty(&ChattyConfig::default());
 
         let totals_1_thread = processed_sites1.totals;
         let totals_4_threads = processed_sites4.totals;
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use crate::{
+    code::CodeCounts,
+    language::{for_each_cjk_word, Language},
+    line::Line,
+    readability::{Readability, READABILITY_LANGUAGE},
+    tokenizer::Tokenizer,
+    words::WordCounts,
+};
+
+/// Lo que se mide de cada pregunta antes de sumarla a su Site y a sus Tags.
+/// Las palabras distintas de la pregunta, en minúsculas y con la cantidad de veces que aparecen, solo se guardan si se cuenta el vocabulario o las palabras más frecuentes, el idioma solo se detecta si se pide el desglose por idioma, y la legibilidad solo se cuenta si se pide.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Question {
+    pub words: WordCounts,
+    pub code: CodeCounts,
+    pub tokens: Vec<(String, usize)>,
+    pub language: Option<Language>,
+    pub readability: Option<Readability>,
+}
+
+impl Question {
+    /// Mide una línea, contando las palabras con el tokenizer indicado.
+    pub fn from_line(line: &Line, tokenizer: Tokenizer) -> Question {
+        Question {
+            words: WordCounts::from_line(line, tokenizer),
+            code: CodeCounts::from_texts(&line.texts),
+            tokens: vec![],
+            language: None,
+            readability: None,
+        }
+    }
+
+    /// Guarda las palabras distintas de la línea y cuántas veces aparece cada una, para sumarlas al vocabulario y a las palabras más frecuentes de su Site y sus Tags.
+    /// Si ya se detectó que la línea es de un idioma CJK, las palabras se separan igual que en `with_language`, para que estén en la misma unidad que su cantidad.
+    pub fn with_tokens(mut self, line: &Line, tokenizer: Tokenizer) -> Question {
+        self.tokens = count_tokens(line, tokenizer, self.language);
+        self
+    }
+
+    /// Detecta el idioma de la línea. Si es un idioma CJK, las palabras se vuelven a medir en caracteres, porque no se separan con espacios.
+    /// Se tiene que llamar antes que `with_tokens`.
+    pub fn with_language(mut self, line: &Line) -> Question {
+        let language = Language::detect(&line.texts);
+        if language.is_cjk() {
+            self.words = WordCounts::from_line_cjk(line);
+        }
+        self.language = Some(language);
+        self
+    }
+
+    /// Pregunta con un solo text y sin tags, con sus palabras separadas por espacios. Para los tests del vocabulario y las palabras más frecuentes.
+    #[cfg(test)]
+    pub(crate) fn from_text(text: &str) -> Question {
+        let line = Line {
+            texts: vec![text.to_string()],
+            tags: vec![],
+        };
+        Question::from_line(&line, Tokenizer::Whitespace).with_tokens(&line, Tokenizer::Whitespace)
+    }
+
+    /// Cuenta las oraciones, palabras y sílabas de la línea, para los índices de legibilidad de su Site y sus Tags.
+    /// Solo se cuentan si la línea es de READABILITY_LANGUAGE, usando el idioma ya detectado si lo hay; si no, la legibilidad queda vacía.
+    pub fn with_readability(mut self, line: &Line) -> Question {
+        let language = self
+            .language
+            .unwrap_or_else(|| Language::detect(&line.texts));
+        self.readability = Some(if language == READABILITY_LANGUAGE {
+            Readability::from_texts(&line.texts)
+        } else {
+            Readability::default()
+        });
+        self
+    }
+}
+
+/// Cuenta las palabras distintas de la línea, en minúsculas, y cuántas veces aparece cada una.
+/// Si la línea es de un idioma CJK, las palabras se separan por caracteres como en `cjk_length`; si no, con el tokenizer.
+pub fn count_tokens(
+    line: &Line,
+    tokenizer: Tokenizer,
+    language: Option<Language>,
+) -> Vec<(String, usize)> {
+    match language {
+        Some(language) if language.is_cjk() => {
+            count_words(line, |text, f| for_each_cjk_word(text, f))
+        }
+        _ => count_words(line, |text, f| tokenizer.for_each_word(text, f)),
+    }
+}
+
+/// Cuenta las palabras distintas de la línea, en minúsculas, separándolas con `for_each_word`.
+fn count_words(
+    line: &Line,
+    for_each_word: impl Fn(&str, &mut dyn FnMut(&str)),
+) -> Vec<(String, usize)> {
+    let mut tokens: HashMap<String, usize> = HashMap::new();
+    line.texts.iter().for_each(|text| {
+        for_each_word(text, &mut |word| {
+            *tokens.entry(word.to_lowercase()).or_insert(0) += 1;
+        })
+    });
+    tokens.into_iter().collect()
+}
+
+impl From<WordCounts> for Question {
+    /// Una pregunta sin código con las palabras indicadas.
+    fn from(words: WordCounts) -> Self {
+        Question {
+            words,
+            code: CodeCounts::default(),
+            tokens: vec![],
+            language: None,
+            readability: None,
+        }
+    }
+}
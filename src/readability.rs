@@ -0,0 +1,339 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    language::Language,
+    processed_sites::{top_by_value, ProcessedSites, DEFAULT_TOP_N},
+    tag::Tag,
+    tokenizer::for_each_prose_token,
+};
+
+/// Idioma de las únicas preguntas con las que se calcula la legibilidad, porque las sílabas se estiman con sus reglas y los índices de Flesch están pensados para él.
+pub const READABILITY_LANGUAGE: Language = Language::En;
+
+/// Abreviaturas frecuentes que terminan en punto sin terminar la oración. Las de letras sueltas separadas por puntos (`e.g.`, `U.S.`) se reconocen aparte.
+const ABBREVIATIONS: [&str; 12] = [
+    "mr", "mrs", "ms", "dr", "vs", "cf", "eg", "ie", "approx", "fig", "no", "resp",
+];
+
+/// Cantidad de oraciones, palabras y sílabas de un conjunto de preguntas, a partir de las que se calculan los índices de legibilidad de Flesch.
+/// Como son sumas, unir las de dos conjuntos es sumarlas, sin importar el orden.
+/// Se serializa como un resumen (ReadabilitySummary) con las cantidades y los índices.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "ReadabilitySummary", from = "ReadabilitySummary")]
+pub struct Readability {
+    pub sentences: usize,
+    pub words: usize,
+    pub syllables: usize,
+}
+
+/// Resumen serializado de la legibilidad.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadabilitySummary {
+    pub sentences: usize,
+    pub words: usize,
+    pub syllables: usize,
+    pub flesch_reading_ease: f64,
+    pub flesch_kincaid_grade: f64,
+    pub average_sentence_length: f64,
+}
+
+impl Readability {
+    /// Cuenta las oraciones, palabras y sílabas de los texts de una pregunta, mirando solo la prosa: sin bloques de código, código inline, URLs ni markup, igual que el tokenizer Prose.
+    /// Las palabras son las separadas por espacios que tienen alguna letra. Cada text con palabras tiene al menos una oración, más una por cada palabra que termina una oración antes de la última.
+    pub fn from_texts(texts: &[String]) -> Readability {
+        let mut readability = Readability::default();
+        texts.iter().for_each(|text| {
+            let mut open_sentence = false;
+            for_each_prose_token(text, |token| {
+                if token.chars().any(char::is_alphabetic) {
+                    readability.words += 1;
+                    readability.syllables += count_syllables(token);
+                    open_sentence = true;
+                }
+                if open_sentence && ends_sentence(token) {
+                    readability.sentences += 1;
+                    open_sentence = false;
+                }
+            });
+            readability.sentences += open_sentence as usize;
+        });
+        readability
+    }
+
+    /// Suma otra legibilidad a sí misma, in-place.
+    pub fn add(&mut self, other: &Readability) {
+        self.sentences += other.sentences;
+        self.words += other.words;
+        self.syllables += other.syllables;
+    }
+
+    /// Promedio de palabras por oración. Es 0 si no hay oraciones.
+    pub fn average_sentence_length(&self) -> f64 {
+        if self.sentences == 0 {
+            return 0.0;
+        }
+        self.words as f64 / self.sentences as f64
+    }
+
+    /// Promedio de sílabas por palabra. Es 0 si no hay palabras.
+    fn syllables_per_word(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        self.syllables as f64 / self.words as f64
+    }
+
+    /// Índice de facilidad de lectura de Flesch: más alto es más fácil, y los textos normales están entre 0 y 100. Es 0 si no hay palabras.
+    pub fn flesch_reading_ease(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        206.835 - 1.015 * self.average_sentence_length() - 84.6 * self.syllables_per_word()
+    }
+
+    /// Grado escolar de Flesch-Kincaid: aproximadamente los años de escolaridad necesarios para entender el texto. Es 0 si no hay palabras.
+    pub fn flesch_kincaid_grade(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        0.39 * self.average_sentence_length() + 11.8 * self.syllables_per_word() - 15.59
+    }
+}
+
+impl From<Readability> for ReadabilitySummary {
+    fn from(readability: Readability) -> Self {
+        ReadabilitySummary {
+            sentences: readability.sentences,
+            words: readability.words,
+            syllables: readability.syllables,
+            flesch_reading_ease: readability.flesch_reading_ease(),
+            flesch_kincaid_grade: readability.flesch_kincaid_grade(),
+            average_sentence_length: readability.average_sentence_length(),
+        }
+    }
+}
+
+impl From<ReadabilitySummary> for Readability {
+    fn from(summary: ReadabilitySummary) -> Self {
+        Readability {
+            sentences: summary.sentences,
+            words: summary.words,
+            syllables: summary.syllables,
+        }
+    }
+}
+
+/// Suma la legibilidad de `other` a la de `readability`, si la tiene.
+pub fn merge_readability(readability: &mut Option<Readability>, other: &Option<Readability>) {
+    if let Some(other) = other {
+        readability
+            .get_or_insert_with(Readability::default)
+            .add(other);
+    }
+}
+
+/// Indica si la palabra termina una oración: termina en `!` o `?`, o en `.` sin ser una abreviatura. Se ignoran las comillas y paréntesis que cierran.
+fn ends_sentence(token: &str) -> bool {
+    let token = token.trim_end_matches(['"', '\'', ')', ']', '”', '’']);
+    if token.ends_with(['!', '?']) {
+        return true;
+    }
+    token
+        .strip_suffix('.')
+        .is_some_and(|stem| !is_abbreviation(stem))
+}
+
+/// Indica si la palabra, sin el punto final, es una abreviatura: una de las frecuentes, o letras sueltas separadas por puntos (`e.g`, `U.S`).
+fn is_abbreviation(stem: &str) -> bool {
+    let stem = stem
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    let mut parts = stem.split('.');
+    let initials = stem.contains('.') && parts.all(|part| part.chars().count() == 1);
+    initials || ABBREVIATIONS.contains(&stem.as_str())
+}
+
+/// Estima las sílabas de una palabra en inglés: los grupos de vocales seguidas, sin la `e` muda final, y al menos una.
+fn count_syllables(word: &str) -> usize {
+    let letters: Vec<char> = word
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let is_vowel = |c: &char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let mut syllables = letters
+        .iter()
+        .enumerate()
+        .filter(|(i, c)| is_vowel(c) && (*i == 0 || !is_vowel(&letters[i - 1])))
+        .count();
+    if syllables > 1 && letters.ends_with(&['e']) && !letters.ends_with(&['l', 'e']) {
+        syllables -= 1;
+    }
+    syllables.max(1)
+}
+
+/// Configuración de los rankings de legibilidad: cuántos Sites y Tags más difíciles de leer tiene cada lista, y cuántas preguntas tienen que tener como mínimo.
+/// Una lista de tamaño 0 no se genera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadabilityConfig {
+    pub hardest_sites: usize,
+    pub hardest_tags: usize,
+    pub site_hardest_tags: usize,
+    pub min_questions: usize,
+}
+
+impl ReadabilityConfig {
+    /// Crea una configuración con `top_n` elementos en cada lista y sin mínimo de preguntas.
+    pub fn with_top_n(top_n: usize) -> ReadabilityConfig {
+        ReadabilityConfig {
+            hardest_sites: top_n,
+            hardest_tags: top_n,
+            site_hardest_tags: top_n,
+            min_questions: 1,
+        }
+    }
+}
+
+impl Default for ReadabilityConfig {
+    fn default() -> Self {
+        ReadabilityConfig::with_top_n(DEFAULT_TOP_N)
+    }
+}
+
+/// Grado de Flesch-Kincaid con el que se ordena un Site o Tag, o None si no tiene legibilidad, palabras o la cantidad mínima de preguntas.
+fn hardness(
+    questions: usize,
+    readability: &Option<Readability>,
+    min_questions: usize,
+) -> Option<f64> {
+    readability
+        .filter(|readability| questions >= min_questions && readability.words > 0)
+        .map(|readability| readability.flesch_kincaid_grade())
+}
+
+/// Items (nombre, grado) de los Tags que entran en los rankings de legibilidad.
+fn tags_hardness<'a>(
+    tags: impl Iterator<Item = (&'a String, &'a Tag)>,
+    min_questions: usize,
+) -> Vec<(&'a String, f64)> {
+    tags.filter_map(|(name, tag)| {
+        hardness(tag.questions, &tag.readability, min_questions).map(|grade| (name, grade))
+    })
+    .collect()
+}
+
+impl ProcessedSites {
+    /// Genera los rankings de los Sites y Tags más difíciles de leer, como los de chattys pero ordenando por el grado de Flesch-Kincaid, de mayor a menor.
+    /// Agrega a los totals `hardest_sites` y `hardest_tags`, y a cada Site sus `hardest_tags`. Solo entran los Sites y Tags con legibilidad calculada.
+    pub fn process_readability(&mut self, config: &ReadabilityConfig) {
+        let min_questions = config.min_questions;
+        if config.hardest_sites > 0 {
+            let sites: Vec<(&String, f64)> = self
+                .sites
+                .iter()
+                .filter_map(|(name, site)| {
+                    hardness(site.questions, &site.readability, min_questions)
+                        .map(|grade| (name, grade))
+                })
+                .collect();
+            self.totals.insert(
+                "hardest_sites".to_string(),
+                top_by_value(sites, config.hardest_sites),
+            );
+        }
+        if config.hardest_tags > 0 {
+            let tags = tags_hardness(self.tags.iter(), min_questions);
+            self.totals.insert(
+                "hardest_tags".to_string(),
+                top_by_value(tags, config.hardest_tags),
+            );
+        }
+        if config.site_hardest_tags > 0 {
+            self.sites.values_mut().for_each(|site| {
+                let tags = tags_hardness(site.tags.iter(), min_questions);
+                site.hardest_tags = top_by_value(tags, config.site_hardest_tags);
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn readability(text: &str) -> Readability {
+        Readability::from_texts(&[text.to_string()])
+    }
+
+    #[test]
+    fn counts_syllables() {
+        assert_eq!(count_syllables("cat"), 1);
+        assert_eq!(count_syllables("Table"), 2);
+        assert_eq!(count_syllables("make"), 1);
+        assert_eq!(count_syllables("reading,"), 2);
+        assert_eq!(count_syllables("readability"), 5);
+        assert_eq!(count_syllables("the"), 1);
+        assert_eq!(count_syllables("rhythm"), 1);
+    }
+
+    #[test]
+    fn counts_sentences_and_words() {
+        let question = Readability::from_texts(&[
+            "How do I sort a list?".to_string(),
+            "I tried this. It fails!  Any idea\n`x = 1`".to_string(),
+            "".to_string(),
+        ]);
+        assert_eq!(question.sentences, 4);
+        // El código inline no cuenta
+        assert_eq!(question.words, 13);
+        assert_eq!(readability("1 + 2").sentences, 0);
+    }
+
+    #[test]
+    fn abbreviations_do_not_end_sentences() {
+        assert_eq!(readability("e.g. this").sentences, 1);
+        assert_eq!(
+            readability("Use a map, i.e. a dict. It works.").sentences,
+            2
+        );
+        assert_eq!(readability("Ask Dr. Smith (see fig. 2).").sentences, 1);
+        assert_eq!(readability("I read file.txt. Then I quit.").sentences, 2);
+        assert_eq!(readability("He said \"stop.\" Then left.").sentences, 2);
+    }
+
+    #[test]
+    fn ignores_code_and_urls() {
+        let question = Readability::from_texts(&[
+            "Why does this fail?\n\n    let x = vec![1, 2, 3];\n    x.iter().map(f).collect();\n\nSee https://doc.rust-lang.org/std/ for details.".to_string(),
+            "```\nfn main() { println!(\"hi\"); }\n```\nIt prints <b>nothing</b>.".to_string(),
+        ]);
+        assert_eq!(question.words, 4 + 3 + 3);
+        assert_eq!(question.sentences, 3);
+    }
+
+    #[test]
+    fn computes_flesch_scores_and_merges() {
+        let mut simple = readability("The cat sat on the mat.");
+        assert_eq!(
+            (simple.sentences, simple.words, simple.syllables),
+            (1, 6, 6)
+        );
+        assert!((simple.flesch_reading_ease() - 116.145).abs() < 1e-9);
+        assert!((simple.flesch_kincaid_grade() - -1.45).abs() < 1e-9);
+
+        let hard =
+            readability("Unquestionably, institutional accountability necessitates transparency.");
+        assert!(hard.flesch_kincaid_grade() > simple.flesch_kincaid_grade());
+        assert!(hard.flesch_reading_ease() < simple.flesch_reading_ease());
+
+        simple.add(&hard);
+        assert_eq!(simple.sentences, 2);
+        assert_eq!(simple.average_sentence_length(), 5.5);
+        assert_eq!(Readability::default().flesch_reading_ease(), 0.0);
+
+        let json = serde_json::to_value(simple).unwrap();
+        assert_eq!(json["average_sentence_length"], 5.5);
+        let parsed: Readability = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, simple);
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+
+use crate::{processed_sites::ProcessedSites, site::Site};
+
+/// Cantidad por defecto de Sites más parecidos que se listan en cada Site.
+pub const DEFAULT_NEAREST_SITES: usize = 5;
+
+/// Medida de similitud entre dos Sites, según los tags que aparecen en cada uno.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimilarityMeasure {
+    /// Similitud de Jaccard entre los conjuntos de tags: tags en común sobre tags en alguno de los dos.
+    #[default]
+    Jaccard,
+    /// Similitud coseno entre los vectores de cantidad de preguntas por tag.
+    Cosine,
+}
+
+/// Matrices de similitud entre todos los pares de Sites. La fila y columna `i` de cada matriz corresponden a `sites[i]`, y los Sites están ordenados por nombre.
+/// `measure` es la medida con la que se eligieron los `nearest_sites` de cada Site.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SiteSimilarity {
+    pub measure: SimilarityMeasure,
+    pub sites: Vec<String>,
+    pub jaccard: Vec<Vec<f64>>,
+    pub cosine: Vec<Vec<f64>>,
+}
+
+/// Tags de un Site ordenados por nombre, con su cantidad de preguntas, y la norma del vector de cantidades.
+struct TagVector<'a> {
+    tags: Vec<(&'a str, usize)>,
+    norm: f64,
+}
+
+impl<'a> TagVector<'a> {
+    fn new(site: &'a Site) -> TagVector<'a> {
+        let mut tags: Vec<(&str, usize)> = site
+            .tags
+            .iter()
+            .map(|(name, tag)| (name.as_str(), tag.questions))
+            .collect();
+        tags.sort_unstable();
+        let norm = tags
+            .iter()
+            .map(|(_, questions)| (questions * questions) as f64)
+            .sum::<f64>()
+            .sqrt();
+        TagVector { tags, norm }
+    }
+
+    /// Calcula la similitud de Jaccard y la coseno con otro Site, recorriendo los dos vectores ordenados a la vez.
+    /// Si alguno de los dos Sites no tiene tags, ambas similitudes son 0.
+    fn similarity(&self, other: &TagVector) -> (f64, f64) {
+        let (mut i, mut j) = (0, 0);
+        let (mut common, mut dot) = (0, 0);
+        while i < self.tags.len() && j < other.tags.len() {
+            let ((tag_1, questions_1), (tag_2, questions_2)) = (self.tags[i], other.tags[j]);
+            match tag_1.cmp(tag_2) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    common += 1;
+                    dot += questions_1 * questions_2;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        let union = self.tags.len() + other.tags.len() - common;
+        let jaccard = if union == 0 {
+            0.0
+        } else {
+            common as f64 / union as f64
+        };
+        let cosine = if self.norm == 0.0 || other.norm == 0.0 {
+            0.0
+        } else {
+            dot as f64 / (self.norm * other.norm)
+        };
+        (jaccard, cosine)
+    }
+}
+
+impl SiteSimilarity {
+    /// Calcula las matrices de similitud de los Sites, una fila por Site en paralelo.
+    pub fn compute(sites: &HashMap<String, Site>, measure: SimilarityMeasure) -> SiteSimilarity {
+        let mut names: Vec<&String> = sites.keys().collect();
+        names.sort_unstable();
+        let vectors: Vec<TagVector> = names
+            .iter()
+            .map(|name| TagVector::new(&sites[*name]))
+            .collect();
+        let (jaccard, cosine): (Vec<Vec<f64>>, Vec<Vec<f64>>) = vectors
+            .par_iter()
+            .map(|vector_1| {
+                vectors
+                    .iter()
+                    .map(|vector_2| vector_1.similarity(vector_2))
+                    .unzip()
+            })
+            .unzip();
+        SiteSimilarity {
+            measure,
+            sites: names.into_iter().cloned().collect(),
+            jaccard,
+            cosine,
+        }
+    }
+
+    /// Matriz de la medida indicada.
+    pub fn matrix(&self, measure: SimilarityMeasure) -> &[Vec<f64>] {
+        match measure {
+            SimilarityMeasure::Jaccard => &self.jaccard,
+            SimilarityMeasure::Cosine => &self.cosine,
+        }
+    }
+
+    /// Similitud entre dos Sites según la medida indicada, o None si alguno no está en la matriz.
+    pub fn get(&self, site_1: &str, site_2: &str, measure: SimilarityMeasure) -> Option<f64> {
+        let i = self.index(site_1)?;
+        let j = self.index(site_2)?;
+        Some(self.matrix(measure)[i][j])
+    }
+
+    /// Los `n` Sites más parecidos al indicado según `measure`, de mayor a menor similitud, sin incluirse a sí mismo. Los empates se ordenan por nombre.
+    pub fn nearest(&self, site: &str, n: usize) -> Vec<String> {
+        let Some(i) = self.index(site) else {
+            return vec![];
+        };
+        let row = &self.matrix(self.measure)[i];
+        let mut neighbors: Vec<(&String, f64)> = self
+            .sites
+            .iter()
+            .zip(row.iter().copied())
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, neighbor)| neighbor)
+            .collect();
+        neighbors.sort_by(|(name_1, value_1), (name_2, value_2)| {
+            value_2.total_cmp(value_1).then(name_1.cmp(name_2))
+        });
+        neighbors
+            .into_iter()
+            .take(n)
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    fn index(&self, site: &str) -> Option<usize> {
+        self.sites
+            .binary_search_by(|name| name.as_str().cmp(site))
+            .ok()
+    }
+}
+
+impl ProcessedSites {
+    /// Calcula la similitud entre todos los pares de Sites y guarda en cada Site sus `nearest_n` Sites más parecidos según `measure`.
+    /// Las matrices quedan registradas en `similarity`.
+    pub fn process_similarity(&mut self, measure: SimilarityMeasure, nearest_n: usize) {
+        let similarity = SiteSimilarity::compute(&self.sites, measure);
+        self.sites.iter_mut().for_each(|(name, site)| {
+            site.nearest_sites = similarity.nearest(name, nearest_n);
+        });
+        self.similarity = Some(similarity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tag::Tag;
+
+    fn site(tags: &[(&str, usize)]) -> Site {
+        let tags = tags
+            .iter()
+            .map(|(name, questions)| (name.to_string(), Tag::new(*questions, 0)))
+            .collect();
+        Site::new(0, 0, tags, vec![])
+    }
+
+    fn sites() -> HashMap<String, Site> {
+        HashMap::from([
+            ("a".to_string(), site(&[("x", 1), ("y", 1)])),
+            ("b".to_string(), site(&[("x", 2), ("y", 2), ("z", 1)])),
+            ("c".to_string(), site(&[("y", 10), ("w", 1)])),
+            ("d".to_string(), site(&[])),
+        ])
+    }
+
+    #[test]
+    fn computes_jaccard_and_cosine() {
+        let similarity = SiteSimilarity::compute(&sites(), SimilarityMeasure::Jaccard);
+
+        assert_eq!(similarity.sites, ["a", "b", "c", "d"]);
+        assert_eq!(
+            similarity.get("a", "b", SimilarityMeasure::Jaccard),
+            Some(2.0 / 3.0)
+        );
+        assert_eq!(
+            similarity.get("a", "c", SimilarityMeasure::Jaccard),
+            Some(1.0 / 3.0)
+        );
+        let cosine = similarity.get("a", "b", SimilarityMeasure::Cosine).unwrap();
+        assert!((cosine - 4.0 / (2.0_f64.sqrt() * 3.0)).abs() < 1e-12);
+        assert!((similarity.get("a", "a", SimilarityMeasure::Cosine).unwrap() - 1.0).abs() < 1e-12);
+        assert_eq!(
+            similarity.get("a", "d", SimilarityMeasure::Cosine),
+            Some(0.0)
+        );
+        assert_eq!(
+            similarity.get("d", "d", SimilarityMeasure::Jaccard),
+            Some(0.0)
+        );
+        assert_eq!(similarity.get("a", "e", SimilarityMeasure::Jaccard), None);
+        (0..4).for_each(|i| {
+            (0..4).for_each(|j| {
+                assert_eq!(similarity.jaccard[i][j], similarity.jaccard[j][i]);
+                assert_eq!(similarity.cosine[i][j], similarity.cosine[j][i]);
+            })
+        });
+    }
+
+    #[test]
+    fn nearest_depends_on_the_measure() {
+        let sites = HashMap::from([
+            ("q".to_string(), site(&[("x", 100), ("y", 1)])),
+            ("s1".to_string(), site(&[("x", 1), ("z", 1), ("w", 1)])),
+            ("s2".to_string(), site(&[("y", 1)])),
+            ("s3".to_string(), site(&[("v", 1)])),
+        ]);
+        let jaccard = SiteSimilarity::compute(&sites, SimilarityMeasure::Jaccard);
+        let cosine = SiteSimilarity::compute(&sites, SimilarityMeasure::Cosine);
+
+        assert_eq!(jaccard.nearest("q", 3), ["s2", "s1", "s3"]);
+        assert_eq!(cosine.nearest("q", 3), ["s1", "s2", "s3"]);
+        assert_eq!(cosine.nearest("q", 1), ["s1"]);
+        assert_eq!(jaccard.nearest("s3", 2), ["q", "s1"]);
+        assert!(jaccard.nearest("e", 3).is_empty());
+    }
+}
@@ -1,16 +1,77 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::tag::Tag;
+use crate::{
+    chatty::ChattyMetric,
+    cooccurrence::TagPairs,
+    distinctive_terms::DistinctiveTerm,
+    language::{merge_languages, Language, LanguageStats},
+    lengths::LengthStats,
+    options::ProcessOptions,
+    question::Question,
+    readability::{merge_readability, Readability},
+    tag::Tag,
+    tag_counts::TagsPerQuestion,
+    top_words::{merge_top_words, TopWords},
+    vocabulary::{merge_vocabularies, Vocabulary},
+    words::WordCounts,
+};
 
-/// Estructura que contiene la cantidad de preguntas y palabras de un Site, los tags que aparecen en él y los 10 con mayor ratio palabras/preguntas.
-#[derive(Debug, Serialize, Clone)]
+/// Estructura que contiene la cantidad de preguntas y palabras de un Site, los tags que aparecen en él, los de mayor ratio palabras/preguntas (chatty) y, si se piden, los de menor ratio (terse), los más difíciles de leer y los Sites con tags más parecidos.
+/// Los campos opcionales solo están si se pidió calcularlos.
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Site {
     pub questions: usize,
     pub words: usize,
+    #[serde(default)]
+    pub title_words: usize,
+    #[serde(default)]
+    pub body_words: usize,
+    #[serde(default)]
+    pub code_lines: usize,
+    #[serde(default)]
+    pub code_chars: usize,
+    #[serde(default)]
+    pub code_questions: usize,
     pub tags: HashMap<String, Tag>,
+    /// Histograma de tags por pregunta, incluidas las preguntas sin tags.
+    #[serde(default)]
+    pub tags_per_question: TagsPerQuestion,
     pub chatty_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub terse_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nearest_sites: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hardest_tags: Vec<String>,
+    /// Distribución de la cantidad de palabras por pregunta. Solo se serializa si tiene estadísticas detalladas.
+    #[serde(default, skip_serializing_if = "LengthStats::is_sketch_only")]
+    pub lengths: LengthStats,
+    /// Pares de tags que aparecen juntos.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooccurrence: Option<TagPairs>,
+    /// Cantidad de palabras y de palabras distintas.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub vocabulary: Option<Vocabulary>,
+    /// Palabras más frecuentes.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub top_words: Option<TopWords>,
+    /// Términos distintivos respecto de todos los Sites.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub distinctive_terms: Vec<DistinctiveTerm>,
+    /// Cantidad de preguntas repetidas que se descartaron.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed_duplicates: Option<usize>,
+    /// Cantidad de preguntas casi duplicadas que se descartaron.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed_near_duplicates: Option<usize>,
+    /// Cantidad de preguntas y palabras de cada idioma detectado.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub languages: Option<BTreeMap<Language, LanguageStats>>,
+    /// Oraciones, palabras y sílabas de sus preguntas en inglés, para los índices de legibilidad.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readability: Option<Readability>,
 }
 
 impl Site {
@@ -24,27 +85,153 @@ impl Site {
         Site {
             questions,
             words,
+            title_words: 0,
+            body_words: 0,
+            code_lines: 0,
+            code_chars: 0,
+            code_questions: 0,
             tags,
+            tags_per_question: TagsPerQuestion::default(),
             chatty_tags,
+            terse_tags: vec![],
+            nearest_sites: vec![],
+            hardest_tags: vec![],
+            lengths: LengthStats::default(),
+            cooccurrence: None,
+            vocabulary: None,
+            top_words: None,
+            distinctive_terms: vec![],
+            removed_duplicates: None,
+            removed_near_duplicates: None,
+            languages: None,
+            readability: None,
         }
     }
 
-    /// Suma un Site a sí mismo in-place, sin duplicar Tags, sino que se suman los contenidos de aquellos que estén repetidos.
-    pub fn add(&mut self, site: &Site) {
-        self.questions += site.questions;
-        self.words += site.words;
-        site.tags.iter().for_each(|(tag_name, tag)| {
+    /// Construye un Site sin preguntas, que guarda las estadísticas opcionales que piden las opciones: estadísticas detalladas de palabras, vocabulario y palabras más frecuentes (también en sus Tags), pares de tags, preguntas repetidas descartadas, desglose por idioma y legibilidad (también en sus Tags).
+    pub fn empty(options: &ProcessOptions) -> Site {
+        Site {
+            lengths: LengthStats::new(options.length_stats),
+            cooccurrence: options.cooccurrence.then(TagPairs::default),
+            vocabulary: options.vocabulary.map(Vocabulary::new),
+            top_words: options.top_words.as_ref().map(TopWords::new),
+            removed_duplicates: options.dedup.map(|_| 0),
+            languages: options.languages.then(BTreeMap::new),
+            readability: options.readability.then(Readability::default),
+            ..Site::new(0, 0, HashMap::new(), vec![])
+        }
+    }
+
+    /// Suma una pregunta con los tags indicados, in-place. Los tags repetidos en la pregunta se cuentan una sola vez.
+    pub fn add_question(&mut self, question: &Question, mut tags: Vec<String>) {
+        let words = question.words;
+        self.questions += 1;
+        self.words += words.total();
+        self.title_words += words.title;
+        self.body_words += words.body;
+        self.code_lines += question.code.lines;
+        self.code_chars += question.code.chars;
+        self.code_questions += question.code.has_code() as usize;
+        self.lengths.add(words.total());
+        if let Some(vocabulary) = &mut self.vocabulary {
+            vocabulary.add_question(question);
+        }
+        if let Some(top_words) = &mut self.top_words {
+            top_words.add_question(question);
+        }
+        if let (Some(languages), Some(language)) = (&mut self.languages, question.language) {
+            languages.entry(language).or_default().add(words.total());
+        }
+        if let (Some(readability), Some(other)) = (&mut self.readability, &question.readability) {
+            readability.add(other);
+        }
+        tags.sort_unstable();
+        tags.dedup();
+        self.tags_per_question.add(tags.len());
+        if let Some(cooccurrence) = &mut self.cooccurrence {
+            cooccurrence.add_question(&tags);
+        }
+        tags.into_iter().for_each(|tag_name| {
             self.tags
-                .entry(tag_name.to_string())
-                .and_modify(|t| *t += *tag)
-                .or_insert(*tag);
+                .entry(tag_name)
+                .or_insert_with(|| {
+                    Tag::empty(
+                        self.lengths.is_detailed(),
+                        self.vocabulary.as_ref().map(Vocabulary::mode),
+                        self.top_words.as_ref(),
+                        self.readability.is_some(),
+                    )
+                })
+                .add_question(question);
         });
     }
+
+    /// Une otro Site a sí mismo consumiéndolo, de forma que sus Tags se mueven en vez de clonarse.
+    pub fn merge(&mut self, site: Site) {
+        self.questions += site.questions;
+        self.words += site.words;
+        self.title_words += site.title_words;
+        self.body_words += site.body_words;
+        self.code_lines += site.code_lines;
+        self.code_chars += site.code_chars;
+        self.code_questions += site.code_questions;
+        self.lengths.merge(&site.lengths);
+        self.tags_per_question.merge(&site.tags_per_question);
+        merge_vocabularies(&mut self.vocabulary, &site.vocabulary);
+        merge_top_words(&mut self.top_words, &site.top_words);
+        if let Some(removed) = site.removed_duplicates {
+            *self.removed_duplicates.get_or_insert(0) += removed;
+        }
+        if let Some(removed) = site.removed_near_duplicates {
+            *self.removed_near_duplicates.get_or_insert(0) += removed;
+        }
+        merge_languages(&mut self.languages, &site.languages);
+        merge_readability(&mut self.readability, &site.readability);
+        match (&mut self.cooccurrence, site.cooccurrence) {
+            (Some(cooccurrence), Some(other)) => cooccurrence.merge(other),
+            (None, Some(other)) => self.cooccurrence = Some(other),
+            _ => {}
+        }
+        site.tags
+            .into_iter()
+            .for_each(|(tag_name, tag)| match self.tags.get_mut(&tag_name) {
+                Some(t) => *t += tag,
+                None => {
+                    self.tags.insert(tag_name, tag);
+                }
+            });
+    }
+
+    /// Suma un Site a sí mismo in-place, sin duplicar Tags, sino que se suman los contenidos de aquellos que estén repetidos.
+    /// Clona el otro Site y lo une con `merge`, para que los campos se sumen en un solo lugar.
+    pub fn add(&mut self, site: &Site) {
+        self.merge(site.clone());
+    }
+
+    /// Proporción de las preguntas del Site que tienen código. Es 0 si no hay preguntas.
+    pub fn code_share(&self) -> f64 {
+        if self.questions == 0 {
+            0.0
+        } else {
+            self.code_questions as f64 / self.questions as f64
+        }
+    }
+
+    /// Valor del Site según la métrica indicada, para los rankings de chattys.
+    pub fn metric(&self, metric: ChattyMetric) -> f64 {
+        metric.value(
+            self.questions,
+            self.words,
+            WordCounts::new(self.title_words, self.body_words),
+            self.lengths.sketch(),
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::code::CodeCounts;
 
     #[test]
     fn create_site_correctly() {
@@ -97,4 +284,139 @@ mod tests {
         assert_eq!(site2.tags.get("tag_2").unwrap().questions, 1);
         assert_eq!(site2.tags.get("tag_2").unwrap().words, 5);
     }
+
+    #[test]
+    fn add_question_in_place() {
+        let mut site = Site::new(0, 0, HashMap::new(), vec![]);
+        site.add_question(
+            &WordCounts::new(2, 8).into(),
+            vec!["tag_1".to_string(), "tag_2".to_string()],
+        );
+        site.add_question(
+            &WordCounts::new(1, 4).into(),
+            vec!["tag_1".to_string(), "tag_1".to_string()],
+        );
+
+        assert_eq!(site.questions, 2);
+        assert_eq!(site.words, 15);
+        assert_eq!(site.title_words, 3);
+        assert_eq!(site.body_words, 12);
+        assert_eq!(site.tags.get("tag_1").unwrap().title_words, 3);
+        assert_eq!(site.tags.get("tag_2").unwrap().body_words, 8);
+        assert_eq!(site.tags.get("tag_1").unwrap().questions, 2);
+        assert_eq!(site.tags.get("tag_1").unwrap().words, 15);
+        assert_eq!(site.tags.get("tag_2").unwrap().questions, 1);
+        assert_eq!(site.tags.get("tag_2").unwrap().words, 10);
+    }
+
+    #[test]
+    fn length_stats_are_kept_for_site_and_tags() {
+        let options = ProcessOptions {
+            length_stats: true,
+            ..ProcessOptions::default()
+        };
+        let mut site = Site::empty(&options);
+        site.add_question(&WordCounts::new(0, 10).into(), vec!["tag_1".to_string()]);
+        let mut other = Site::empty(&options);
+        other.add_question(
+            &WordCounts::new(0, 30).into(),
+            vec!["tag_1".to_string(), "tag_2".to_string()],
+        );
+        site.merge(other);
+
+        let summary = site.lengths.summary().unwrap();
+        assert_eq!((summary.count, summary.min, summary.max), (2, 10, 30));
+        assert_eq!(summary.mean, 20.0);
+        assert_eq!(summary.variance, 100.0);
+        let tag_summary = site.tags.get("tag_1").unwrap().lengths.summary().unwrap();
+        assert_eq!(tag_summary.count, 2);
+        assert_eq!(
+            site.tags
+                .get("tag_2")
+                .unwrap()
+                .lengths
+                .summary()
+                .unwrap()
+                .min,
+            30
+        );
+    }
+
+    #[test]
+    fn merge_sites_in_place() {
+        let mut site1 = Site::new(
+            2,
+            10,
+            HashMap::from([("tag_1".to_string(), Tag::new(2, 10))]),
+            vec![],
+        );
+        let site2 = Site::new(
+            1,
+            5,
+            HashMap::from([
+                ("tag_1".to_string(), Tag::new(1, 5)),
+                ("tag_2".to_string(), Tag::new(1, 5)),
+            ]),
+            vec![],
+        );
+        site1.merge(site2);
+
+        assert_eq!(site1.questions, 3);
+        assert_eq!(site1.words, 15);
+        assert_eq!(site1.tags.get("tag_1").unwrap().questions, 3);
+        assert_eq!(site1.tags.get("tag_1").unwrap().words, 15);
+        assert_eq!(site1.tags.get("tag_2").unwrap().questions, 1);
+    }
+
+    #[test]
+    fn cooccurrence_is_optional_and_merged() {
+        let tags = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        let mut site = Site::empty(&ProcessOptions::default());
+        site.add_question(&WordCounts::new(0, 1).into(), tags(&["a", "b"]));
+        assert_eq!(site.cooccurrence, None);
+
+        let options = ProcessOptions {
+            cooccurrence: true,
+            ..ProcessOptions::default()
+        };
+        let mut site = Site::empty(&options);
+        site.add_question(&WordCounts::new(0, 1).into(), tags(&["b", "a", "b"]));
+        let mut other = Site::empty(&options);
+        other.add_question(&WordCounts::new(0, 1).into(), tags(&["a", "b", "c"]));
+        let mut added = site.clone();
+        added.add(&other);
+        site.merge(other);
+
+        let cooccurrence = site.cooccurrence.unwrap();
+        assert_eq!(cooccurrence.count("a", "b"), 2);
+        assert_eq!(cooccurrence.count("b", "c"), 1);
+        assert_eq!(cooccurrence.len(), 3);
+        assert_eq!(added.cooccurrence.unwrap(), cooccurrence);
+    }
+
+    #[test]
+    fn code_is_merged_like_words() {
+        let code_question = Question {
+            words: WordCounts::new(1, 9),
+            code: CodeCounts {
+                lines: 2,
+                chars: 20,
+            },
+            ..Question::default()
+        };
+        let mut site1 = Site::new(0, 0, HashMap::new(), vec![]);
+        site1.add_question(&code_question, vec!["tag_1".to_string()]);
+        let mut site2 = Site::new(0, 0, HashMap::new(), vec![]);
+        site2.add_question(&WordCounts::new(1, 9).into(), vec!["tag_1".to_string()]);
+        site2.add_question(&code_question, vec!["tag_2".to_string()]);
+        site1.add(&site2);
+
+        assert_eq!(site1.code_lines, 4);
+        assert_eq!(site1.code_chars, 40);
+        assert_eq!(site1.code_questions, 2);
+        assert!((site1.code_share() - 2.0 / 3.0).abs() < 1e-9);
+        let tag_1 = site1.tags.get("tag_1").unwrap();
+        assert_eq!((tag_1.code_lines, tag_1.code_questions), (2, 1));
+        assert_eq!(tag_1.code_share(), 0.5);
+    }
 }
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+/// Error relativo máximo de los cuantiles estimados.
+const RELATIVE_ACCURACY: f64 = 0.01;
+
+/// Sketch de cuantiles que se puede unir con otros, para estimar la mediana y percentiles de la cantidad de palabras por pregunta sin guardar cada valor.
+/// Los valores positivos se agrupan en buckets logarítmicos (como en DDSketch), de forma que el cuantil estimado tiene un error relativo de a lo sumo RELATIVE_ACCURACY.
+/// Unir dos sketches es sumar sus buckets, así que el resultado no depende del orden en que se unan.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuantileSketch {
+    zeros: u64,
+    buckets: BTreeMap<i32, u64>,
+}
+
+impl QuantileSketch {
+    /// Base de los buckets logarítmicos.
+    fn gamma() -> f64 {
+        (1.0 + RELATIVE_ACCURACY) / (1.0 - RELATIVE_ACCURACY)
+    }
+
+    /// Agrega un valor al sketch.
+    pub fn add(&mut self, value: usize) {
+        if value == 0 {
+            self.zeros += 1;
+        } else {
+            let bucket = ((value as f64).ln() / Self::gamma().ln()).ceil() as i32;
+            *self.buckets.entry(bucket).or_insert(0) += 1;
+        }
+    }
+
+    /// Une otro sketch a sí mismo, in-place.
+    pub fn merge(&mut self, other: &QuantileSketch) {
+        self.zeros += other.zeros;
+        other.buckets.iter().for_each(|(bucket, count)| {
+            *self.buckets.entry(*bucket).or_insert(0) += count;
+        });
+    }
+
+    /// Cantidad de valores agregados.
+    pub fn count(&self) -> u64 {
+        self.zeros + self.buckets.values().sum::<u64>()
+    }
+
+    /// Estima el cuantil `q` (entre 0 y 1) de los valores agregados. Devuelve None si el sketch está vacío.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = (q.clamp(0.0, 1.0) * (count - 1) as f64).floor() as u64;
+        if rank < self.zeros {
+            return Some(0.0);
+        }
+        let gamma = Self::gamma();
+        let mut accumulated = self.zeros;
+        self.buckets.iter().find_map(|(bucket, bucket_count)| {
+            accumulated += bucket_count;
+            (rank < accumulated).then(|| 2.0 * gamma.powi(*bucket) / (gamma + 1.0))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_sketch_has_no_quantiles() {
+        let sketch = QuantileSketch::default();
+
+        assert_eq!(sketch.count(), 0);
+        assert_eq!(sketch.quantile(0.5), None);
+    }
+
+    #[test]
+    fn quantiles_are_within_relative_accuracy() {
+        let mut sketch = QuantileSketch::default();
+        (1..=1000).for_each(|value| sketch.add(value));
+
+        for (q, expected) in [(0.0, 1.0), (0.5, 500.0), (0.9, 900.0), (1.0, 1000.0)] {
+            let estimated = sketch.quantile(q).unwrap();
+            assert!((estimated - expected).abs() <= expected * RELATIVE_ACCURACY);
+        }
+    }
+
+    #[test]
+    fn merge_is_the_same_as_adding_everything() {
+        let mut sketch = QuantileSketch::default();
+        let mut sketch1 = QuantileSketch::default();
+        let mut sketch2 = QuantileSketch::default();
+        (0..100).for_each(|value| {
+            sketch.add(value);
+            if value % 2 == 0 {
+                sketch1.add(value);
+            } else {
+                sketch2.add(value);
+            }
+        });
+        sketch1.merge(&sketch2);
+
+        assert_eq!(sketch, sketch1);
+        assert_eq!(sketch1.count(), 100);
+        assert_eq!(sketch1.quantile(0.0), Some(0.0));
+    }
+}
@@ -1,40 +1,163 @@
 use std::ops::AddAssign;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-/// Estructura que contiene la cantidad de preguntas en las que aparece un Tag, y la cantidad de palabras de todas las preguntas en las que aparece ese Tag.
-#[derive(Debug, Serialize, Clone, Copy)]
+use crate::{
+    chatty::ChattyMetric,
+    distinctive_terms::DistinctiveTerm,
+    lengths::LengthStats,
+    question::Question,
+    readability::{merge_readability, Readability},
+    top_words::{merge_top_words, TopWords},
+    vocabulary::{merge_vocabularies, Vocabulary, VocabularyMode},
+    words::WordCounts,
+};
+
+/// Estructura que contiene la cantidad de preguntas en las que aparece un Tag, y la cantidad de palabras de todas las preguntas en las que aparece ese Tag, en total y separadas en títulos y cuerpos.
+/// También cuenta las líneas y caracteres de código de esas preguntas, y cuántas tienen código.
+/// Los campos opcionales solo están si se pidió calcularlos.
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Tag {
     pub questions: usize,
     pub words: usize,
+    #[serde(default)]
+    pub title_words: usize,
+    #[serde(default)]
+    pub body_words: usize,
+    #[serde(default)]
+    pub code_lines: usize,
+    #[serde(default)]
+    pub code_chars: usize,
+    #[serde(default)]
+    pub code_questions: usize,
+    /// Distribución de la cantidad de palabras por pregunta. Solo se serializa si tiene estadísticas detalladas.
+    #[serde(default, skip_serializing_if = "LengthStats::is_sketch_only")]
+    pub lengths: LengthStats,
+    /// Cantidad de palabras y de palabras distintas.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub vocabulary: Option<Vocabulary>,
+    /// Palabras más frecuentes.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub top_words: Option<TopWords>,
+    /// Términos distintivos respecto de su Site.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub distinctive_terms: Vec<DistinctiveTerm>,
+    /// Oraciones, palabras y sílabas de sus preguntas en inglés, para los índices de legibilidad.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readability: Option<Readability>,
 }
 
 impl Tag {
-    /// Construye un nuevo tag con la cantidad de preguntas y palabras indicadas.
+    /// Construye un nuevo tag con la cantidad de preguntas y palabras indicadas, con la distribución de palabras vacía.
     pub fn new(questions: usize, words: usize) -> Tag {
-        Tag { questions, words }
+        Tag {
+            questions,
+            words,
+            title_words: 0,
+            body_words: 0,
+            code_lines: 0,
+            code_chars: 0,
+            code_questions: 0,
+            lengths: LengthStats::default(),
+            vocabulary: None,
+            top_words: None,
+            distinctive_terms: vec![],
+            readability: None,
+        }
+    }
+
+    /// Construye un Tag sin preguntas.
+    /// Guarda estadísticas detalladas de palabras si `length_stats` es true, su vocabulario en el modo indicado, sus palabras más frecuentes con la misma configuración que `top_words`, y su legibilidad si `readability` es true.
+    pub fn empty(
+        length_stats: bool,
+        vocabulary: Option<VocabularyMode>,
+        top_words: Option<&TopWords>,
+        readability: bool,
+    ) -> Tag {
+        Tag {
+            questions: 0,
+            words: 0,
+            title_words: 0,
+            body_words: 0,
+            code_lines: 0,
+            code_chars: 0,
+            code_questions: 0,
+            lengths: LengthStats::new(length_stats),
+            vocabulary: vocabulary.map(Vocabulary::new),
+            top_words: top_words.map(|top_words| TopWords::new(top_words.config())),
+            distinctive_terms: vec![],
+            readability: readability.then(Readability::default),
+        }
+    }
+
+    /// Suma una pregunta, in-place.
+    pub fn add_question(&mut self, question: &Question) {
+        let words = question.words;
+        self.questions += 1;
+        self.words += words.total();
+        self.title_words += words.title;
+        self.body_words += words.body;
+        self.code_lines += question.code.lines;
+        self.code_chars += question.code.chars;
+        self.code_questions += question.code.has_code() as usize;
+        self.lengths.add(words.total());
+        if let Some(vocabulary) = &mut self.vocabulary {
+            vocabulary.add_question(question);
+        }
+        if let Some(top_words) = &mut self.top_words {
+            top_words.add_question(question);
+        }
+        if let (Some(readability), Some(other)) = (&mut self.readability, &question.readability) {
+            readability.add(other);
+        }
     }
 
     /// Suma otro Tag a sí mismo, in-place.
     pub fn add(&mut self, site: &Tag) {
         self.questions += site.questions;
         self.words += site.words;
+        self.title_words += site.title_words;
+        self.body_words += site.body_words;
+        self.code_lines += site.code_lines;
+        self.code_chars += site.code_chars;
+        self.code_questions += site.code_questions;
+        self.lengths.merge(&site.lengths);
+        merge_vocabularies(&mut self.vocabulary, &site.vocabulary);
+        merge_top_words(&mut self.top_words, &site.top_words);
+        merge_readability(&mut self.readability, &site.readability);
+    }
+
+    /// Proporción de las preguntas del Tag que tienen código. Es 0 si no hay preguntas.
+    pub fn code_share(&self) -> f64 {
+        if self.questions == 0 {
+            0.0
+        } else {
+            self.code_questions as f64 / self.questions as f64
+        }
+    }
+
+    /// Valor del Tag según la métrica indicada, para los rankings de chattys.
+    pub fn metric(&self, metric: ChattyMetric) -> f64 {
+        metric.value(
+            self.questions,
+            self.words,
+            WordCounts::new(self.title_words, self.body_words),
+            self.lengths.sketch(),
+        )
     }
 }
 
 impl AddAssign for Tag {
     /// Implementa el método += para el Tag.
     fn add_assign(&mut self, other: Self) {
-        *self = Self {
-            questions: self.questions + other.questions,
-            words: self.words + other.words,
-        };
+        self.add(&other);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::code::CodeCounts;
 
     #[test]
     fn create_tag_correctly() {
@@ -58,11 +181,77 @@ mod tests {
         assert_eq!(tag2.words, 5);
     }
 
+    #[test]
+    fn add_question_in_place() {
+        let mut tag = Tag::new(2, 10);
+        tag.add_question(&WordCounts::new(2, 3).into());
+
+        assert_eq!(tag.questions, 3);
+        assert_eq!(tag.words, 15);
+        assert_eq!(tag.title_words, 2);
+        assert_eq!(tag.body_words, 3);
+    }
+
+    #[test]
+    fn add_question_counts_code() {
+        let mut tag = Tag::new(0, 0);
+        tag.add_question(&Question {
+            words: WordCounts::new(2, 10),
+            code: CodeCounts {
+                lines: 3,
+                chars: 40,
+            },
+            ..Question::default()
+        });
+        tag.add_question(&WordCounts::new(2, 5).into());
+        let mut other = Tag::new(0, 0);
+        other.add_question(&Question {
+            words: WordCounts::new(1, 1),
+            code: CodeCounts { lines: 0, chars: 4 },
+            ..Question::default()
+        });
+        tag += other;
+
+        assert_eq!(tag.code_lines, 3);
+        assert_eq!(tag.code_chars, 44);
+        assert_eq!(tag.code_questions, 2);
+        assert!((tag.code_share() - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn add_merges_word_lengths() {
+        let mut tag1 = Tag::new(0, 0);
+        tag1.add_question(&WordCounts::new(0, 10).into());
+        let mut tag2 = Tag::new(0, 0);
+        tag2.add_question(&WordCounts::new(0, 20).into());
+        tag2.add_question(&WordCounts::new(0, 30).into());
+        tag1 += tag2;
+
+        assert_eq!(tag1.lengths.sketch().count(), 3);
+        assert!((tag1.metric(ChattyMetric::MedianWords) - 20.0).abs() <= 0.2);
+        assert_eq!(tag1.metric(ChattyMetric::MeanWords), 20.0);
+    }
+
+    #[test]
+    fn serializes_length_stats_only_if_detailed() {
+        let mut tag = Tag::empty(false, None, None, false);
+        tag.add_question(&WordCounts::new(0, 10).into());
+        assert!(serde_json::to_value(&tag).unwrap().get("lengths").is_none());
+
+        let mut tag = Tag::empty(true, None, None, false);
+        tag.add_question(&WordCounts::new(0, 10).into());
+        tag.add_question(&WordCounts::new(0, 20).into());
+        let json = serde_json::to_value(&tag).unwrap();
+        assert_eq!(json["lengths"]["min"], 10);
+        assert_eq!(json["lengths"]["max"], 20);
+        assert_eq!(json["lengths"]["variance"], 25.0);
+    }
+
     #[test]
     fn add_assign_method_for_tags() {
         let mut tag1 = Tag::new(2, 10);
         let tag2 = Tag::new(1, 5);
-        tag1 += tag2;
+        tag1 += tag2.clone();
 
         // Se sumaron las words y questions al tag 1
         assert_eq!(tag1.questions, 3);
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+
+/// Histograma de la cantidad de tags distintos por pregunta de un Site: `histogram[i]` es la cantidad de preguntas con `i` tags.
+/// Las preguntas sin tags (`histogram[0]`) no suman a ningún Tag, así que solo quedan registradas acá.
+/// Se serializa como un resumen (TagsPerQuestionSummary) con el histograma, las preguntas sin tags y el promedio de tags por pregunta.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "TagsPerQuestionSummary", from = "TagsPerQuestionSummary")]
+pub struct TagsPerQuestion {
+    histogram: Vec<usize>,
+}
+
+/// Resumen serializado del histograma de tags por pregunta.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagsPerQuestionSummary {
+    pub histogram: Vec<usize>,
+    pub untagged: usize,
+    pub mean: f64,
+}
+
+impl TagsPerQuestion {
+    /// Agrega una pregunta con la cantidad de tags indicada.
+    pub fn add(&mut self, tags: usize) {
+        if self.histogram.len() <= tags {
+            self.histogram.resize(tags + 1, 0);
+        }
+        self.histogram[tags] += 1;
+    }
+
+    /// Une otro histograma a sí mismo, in-place.
+    pub fn merge(&mut self, other: &TagsPerQuestion) {
+        if self.histogram.len() < other.histogram.len() {
+            self.histogram.resize(other.histogram.len(), 0);
+        }
+        self.histogram
+            .iter_mut()
+            .zip(other.histogram.iter())
+            .for_each(|(count, other_count)| *count += other_count);
+    }
+
+    /// Cantidad de preguntas con `tags` tags.
+    pub fn count(&self, tags: usize) -> usize {
+        self.histogram.get(tags).copied().unwrap_or(0)
+    }
+
+    /// Cantidad de preguntas sin tags.
+    pub fn untagged(&self) -> usize {
+        self.count(0)
+    }
+
+    /// Cantidad de preguntas agregadas.
+    pub fn questions(&self) -> usize {
+        self.histogram.iter().sum()
+    }
+
+    /// Promedio de tags por pregunta, contando las preguntas sin tags. Es 0 si no hay preguntas.
+    pub fn mean(&self) -> f64 {
+        let questions = self.questions();
+        if questions == 0 {
+            return 0.0;
+        }
+        let tags: usize = self
+            .histogram
+            .iter()
+            .enumerate()
+            .map(|(tags, count)| tags * count)
+            .sum();
+        tags as f64 / questions as f64
+    }
+}
+
+impl From<TagsPerQuestion> for TagsPerQuestionSummary {
+    fn from(tags_per_question: TagsPerQuestion) -> Self {
+        TagsPerQuestionSummary {
+            untagged: tags_per_question.untagged(),
+            mean: tags_per_question.mean(),
+            histogram: tags_per_question.histogram,
+        }
+    }
+}
+
+impl From<TagsPerQuestionSummary> for TagsPerQuestion {
+    /// El histograma alcanza para reconstruir todo, el resto del resumen se deriva de él.
+    fn from(summary: TagsPerQuestionSummary) -> Self {
+        TagsPerQuestion {
+            histogram: summary.histogram,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_tags_per_question() {
+        let mut tags_per_question = TagsPerQuestion::default();
+        [0, 2, 2, 3, 0, 5]
+            .iter()
+            .for_each(|tags| tags_per_question.add(*tags));
+
+        assert_eq!(tags_per_question.questions(), 6);
+        assert_eq!(tags_per_question.untagged(), 2);
+        assert_eq!(tags_per_question.count(2), 2);
+        assert_eq!(tags_per_question.count(4), 0);
+        assert_eq!(tags_per_question.count(9), 0);
+        assert_eq!(tags_per_question.mean(), 2.0);
+        assert_eq!(TagsPerQuestion::default().mean(), 0.0);
+    }
+
+    #[test]
+    fn merge_is_the_same_as_adding_everything() {
+        let mut all = TagsPerQuestion::default();
+        let mut short = TagsPerQuestion::default();
+        let mut long = TagsPerQuestion::default();
+        [1, 1, 0].iter().for_each(|tags| {
+            all.add(*tags);
+            short.add(*tags);
+        });
+        [4, 2].iter().for_each(|tags| {
+            all.add(*tags);
+            long.add(*tags);
+        });
+        short.merge(&long);
+
+        assert_eq!(short, all);
+    }
+
+    #[test]
+    fn serializes_the_summary() {
+        let mut tags_per_question = TagsPerQuestion::default();
+        [0, 1, 3, 4]
+            .iter()
+            .for_each(|tags| tags_per_question.add(*tags));
+
+        let json = serde_json::to_value(&tags_per_question).unwrap();
+        assert_eq!(json["histogram"], serde_json::json!([1, 1, 0, 1, 1]));
+        assert_eq!(json["untagged"], 1);
+        assert_eq!(json["mean"], 2.0);
+        let deserialized: TagsPerQuestion = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized, tags_per_question);
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::code::{BlockLine, BlockScanner};
+
+/// Forma de contar las palabras de cada text. Se registra en la salida, porque cambia todas las cantidades de palabras.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tokenizer {
+    /// Cuenta los fragmentos separados por espacios, tal como vienen.
+    #[default]
+    Whitespace,
+    /// Cuenta las palabras según la segmentación de Unicode (UAX #29), ignorando la puntuación.
+    Unicode,
+    /// Quita los bloques de código, el código inline, las URLs y el markup HTML/Markdown, y cuenta las palabras que quedan según la segmentación de Unicode.
+    Prose,
+}
+
+impl Tokenizer {
+    /// Cuenta las palabras del texto.
+    pub fn count_words(&self, text: &str) -> usize {
+        match self {
+            Tokenizer::Whitespace => text.split_whitespace().count(),
+            Tokenizer::Unicode => text.unicode_words().count(),
+            Tokenizer::Prose => {
+                let mut words = 0;
+                for_each_prose_token(text, |token| words += token.unicode_words().count());
+                words
+            }
+        }
+    }
+
+    /// Llama a `f` con cada una de las palabras del texto, las mismas que cuenta `count_words`.
+    pub fn for_each_word(&self, text: &str, mut f: impl FnMut(&str)) {
+        match self {
+            Tokenizer::Whitespace => text.split_whitespace().for_each(f),
+            Tokenizer::Unicode => text.unicode_words().for_each(f),
+            Tokenizer::Prose => {
+                for_each_prose_token(text, |token| token.unicode_words().for_each(&mut f))
+            }
+        }
+    }
+}
+
+/// Llama a `f` con cada fragmento separado por espacios del texto en prosa, sin código, URLs ni markup, como lo ve el modo Prose antes de separar las palabras.
+pub fn for_each_prose_token(text: &str, f: impl FnMut(&str)) {
+    strip_markup(text)
+        .split_whitespace()
+        .filter(|token| !is_url(token))
+        .for_each(f);
+}
+
+/// Quita del texto los bloques de código (con fences o indentados, detectados igual que en CodeCounts), los bloques `<pre>` y `<code>`, el código inline, los destinos de los links de Markdown y los tags HTML.
+fn strip_markup(text: &str) -> String {
+    let text = remove_html_blocks(text, "pre");
+    let text = remove_html_blocks(&text, "code");
+    let mut prose = String::with_capacity(text.len());
+    let mut scanner = BlockScanner::default();
+    text.lines().for_each(|line| {
+        if let BlockLine::Prose(line) = scanner.classify(line) {
+            prose.push_str(&strip_inline(line));
+            prose.push('\n');
+        }
+    });
+    prose
+}
+
+/// Quita los bloques `<tag ...>...</tag>` completos, sin distinguir mayúsculas. Si un bloque no se cierra, se quita hasta el final.
+fn remove_html_blocks(text: &str, tag: &str) -> String {
+    let lowercase = text.to_ascii_lowercase();
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut result = String::with_capacity(text.len());
+    let mut position = 0;
+    while let Some(start) = lowercase[position..].find(&open).map(|i| position + i) {
+        let after_name = lowercase[start + open.len()..].chars().next();
+        if !matches!(after_name, Some('>') | Some(' ') | Some('\t') | Some('\n')) {
+            result.push_str(&text[position..start + open.len()]);
+            position = start + open.len();
+            continue;
+        }
+        result.push_str(&text[position..start]);
+        result.push(' ');
+        position = lowercase[start..]
+            .find(&close)
+            .map_or(text.len(), |end| start + end + close.len());
+    }
+    result.push_str(&text[position..]);
+    result
+}
+
+/// Quita de una línea el código inline, los destinos de los links de Markdown (`[texto](url)` queda como `[texto]`) y los tags HTML.
+fn strip_inline(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '`' => match line[i + 1..].find('`') {
+                Some(end) => {
+                    result.push(' ');
+                    skip_to(&mut chars, i + 1 + end);
+                }
+                None => result.push(c),
+            },
+            ']' if line[i + 1..].starts_with('(') => {
+                result.push(']');
+                match line[i + 1..].find(')') {
+                    Some(end) => skip_to(&mut chars, i + 1 + end),
+                    None => skip_to(&mut chars, line.len()),
+                }
+            }
+            '<' if is_html_tag_start(&line[i + 1..]) => match line[i + 1..].find('>') {
+                Some(end) => {
+                    result.push(' ');
+                    skip_to(&mut chars, i + 1 + end);
+                }
+                None => result.push(c),
+            },
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Avanza el iterador hasta pasar el byte `end` de la línea.
+fn skip_to(chars: &mut std::iter::Peekable<std::str::CharIndices>, end: usize) {
+    while chars.next_if(|(j, _)| *j <= end).is_some() {}
+}
+
+/// Indica si lo que sigue a un `<` parece un tag HTML (`<p>`, `</div>`, `<!-- ... -->`), y no una comparación como `a < b`.
+fn is_html_tag_start(rest: &str) -> bool {
+    rest.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!')
+}
+
+/// Indica si un fragmento del texto es una URL.
+fn is_url(token: &str) -> bool {
+    let token = token.trim_start_matches(|c: char| !c.is_alphanumeric());
+    token.contains("://") || token.starts_with("www.") || token.starts_with("mailto:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_counts_every_fragment() {
+        assert_eq!(
+            Tokenizer::Whitespace.count_words("Hello, world! `x = 1` - ok"),
+            7
+        );
+    }
+
+    #[test]
+    fn unicode_ignores_punctuation() {
+        assert_eq!(Tokenizer::Unicode.count_words("Hello, world! - it's ok"), 4);
+        assert_eq!(Tokenizer::Unicode.count_words("café über naïve"), 3);
+    }
+
+    #[test]
+    fn prose_strips_code_blocks() {
+        let text = "Why does this fail?\n\n```rust\nfn main() { let x = 1; }\n```\n\n    indented code here\n\nAny ideas";
+
+        assert_eq!(Tokenizer::Prose.count_words(text), 6);
+        assert_eq!(
+            Tokenizer::Prose.count_words("- item\n    continues\n~~~\n```\ncode\n~~~"),
+            2
+        );
+    }
+
+    #[test]
+    fn prose_strips_inline_code_urls_and_markup() {
+        let text = "Call `foo(bar)` as <b>shown</b> in [the docs](https://example.com/docs) or https://example.com, see <pre>int x;</pre> if a < b";
+
+        assert_eq!(
+            Tokenizer::Prose.count_words(text),
+            Tokenizer::Unicode.count_words("Call as shown in the docs or see if a b")
+        );
+    }
+
+    #[test]
+    fn for_each_word_matches_count_words() {
+        let text =
+            "Call `foo(bar)` as <b>shown</b>, see https://example.com\n\n    code\n\nit's ok";
+        for tokenizer in [Tokenizer::Whitespace, Tokenizer::Unicode, Tokenizer::Prose] {
+            let mut words = vec![];
+            tokenizer.for_each_word(text, |word| words.push(word.to_string()));
+            assert_eq!(words.len(), tokenizer.count_words(text));
+        }
+        let mut words = vec![];
+        Tokenizer::Prose.for_each_word(text, |word| words.push(word.to_string()));
+        assert_eq!(words, ["Call", "as", "shown", "see", "it's", "ok"]);
+    }
+
+    #[test]
+    fn prose_keeps_unclosed_markers() {
+        assert_eq!(Tokenizer::Prose.count_words("a ` b <c"), 3);
+        assert_eq!(Tokenizer::Prose.count_words("a <code>b c"), 1);
+    }
+}
@@ -0,0 +1,580 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::question::Question;
+
+/// Cantidad por defecto de palabras más frecuentes que se listan en cada Site y Tag.
+pub const DEFAULT_TOP_WORDS: usize = 10;
+/// Cantidad de contadores que guarda el sketch por cada palabra que se lista. Con más contadores el conteo es más preciso, pero ocupa más memoria.
+const COUNTERS_PER_WORD: usize = 10;
+
+/// Stopwords en inglés que se descartan por defecto de las palabras más frecuentes.
+pub const DEFAULT_STOPWORDS: &[&str] = &[
+    "a",
+    "about",
+    "above",
+    "after",
+    "again",
+    "against",
+    "all",
+    "am",
+    "an",
+    "and",
+    "any",
+    "are",
+    "as",
+    "at",
+    "be",
+    "because",
+    "been",
+    "before",
+    "being",
+    "below",
+    "between",
+    "both",
+    "but",
+    "by",
+    "can",
+    "could",
+    "did",
+    "do",
+    "does",
+    "doing",
+    "don't",
+    "down",
+    "during",
+    "each",
+    "few",
+    "for",
+    "from",
+    "further",
+    "had",
+    "has",
+    "have",
+    "having",
+    "he",
+    "her",
+    "here",
+    "hers",
+    "herself",
+    "him",
+    "himself",
+    "his",
+    "how",
+    "i",
+    "i'm",
+    "if",
+    "in",
+    "into",
+    "is",
+    "it",
+    "it's",
+    "its",
+    "itself",
+    "just",
+    "me",
+    "more",
+    "most",
+    "my",
+    "myself",
+    "no",
+    "nor",
+    "not",
+    "now",
+    "of",
+    "off",
+    "on",
+    "once",
+    "only",
+    "or",
+    "other",
+    "our",
+    "ours",
+    "ourselves",
+    "out",
+    "over",
+    "own",
+    "same",
+    "she",
+    "should",
+    "so",
+    "some",
+    "such",
+    "than",
+    "that",
+    "the",
+    "their",
+    "theirs",
+    "them",
+    "themselves",
+    "then",
+    "there",
+    "these",
+    "they",
+    "this",
+    "those",
+    "through",
+    "to",
+    "too",
+    "under",
+    "until",
+    "up",
+    "very",
+    "was",
+    "we",
+    "were",
+    "what",
+    "when",
+    "where",
+    "which",
+    "while",
+    "who",
+    "whom",
+    "why",
+    "will",
+    "with",
+    "would",
+    "you",
+    "your",
+    "yours",
+    "yourself",
+    "yourselves",
+];
+
+/// Forma de contar las palabras más frecuentes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TopWordsMode {
+    /// Cuenta con el algoritmo Space-Saving, que usa una cantidad fija de contadores.
+    #[default]
+    Sketch,
+    /// Cuenta todas las palabras. Es exacto, pero solo sirve para entradas chicas.
+    Exact,
+}
+
+/// Configuración de las palabras más frecuentes: cuántas se listan, cómo se cuentan y qué palabras se descartan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopWordsConfig {
+    pub top_n: usize,
+    pub mode: TopWordsMode,
+    /// Palabras que no se cuentan, en minúsculas.
+    pub stopwords: Arc<HashSet<String>>,
+}
+
+impl TopWordsConfig {
+    /// Configuración que lista `top_n` palabras, en el modo indicado y sin las stopwords por defecto.
+    pub fn new(top_n: usize, mode: TopWordsMode) -> TopWordsConfig {
+        TopWordsConfig {
+            top_n,
+            mode,
+            stopwords: Arc::new(
+                DEFAULT_STOPWORDS
+                    .iter()
+                    .map(|word| word.to_string())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Reemplaza las stopwords. Se pasan a minúsculas, igual que las palabras que se cuentan.
+    pub fn with_stopwords<I, S>(mut self, stopwords: I) -> TopWordsConfig
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.stopwords = Arc::new(
+            stopwords
+                .into_iter()
+                .map(|word| word.as_ref().to_lowercase())
+                .collect(),
+        );
+        self
+    }
+}
+
+impl Default for TopWordsConfig {
+    fn default() -> Self {
+        TopWordsConfig::new(DEFAULT_TOP_WORDS, TopWordsMode::default())
+    }
+}
+
+/// Palabras más frecuentes de un Site o Tag, sin las stopwords.
+/// En modo sketch cada conteo puede sobreestimar el real en a lo sumo su `error`, y se puede unir con otro sin importar el orden (es un resumen mergeable), así que se combina en el reduce paralelo.
+/// Solo se serializa la lista de las `top_n` más frecuentes, así que no se puede volver a leer de un JSON.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(into = "Vec<WordFrequency>")]
+pub struct TopWords {
+    config: TopWordsConfig,
+    counters: Counters,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Counters {
+    Sketch(SpaceSaving),
+    Exact(HashMap<String, usize>),
+}
+
+/// Space-Saving: conteo y error máximo de a lo sumo `capacity` palabras.
+/// Las palabras también se agrupan por conteo en buckets ordenados (stream-summary), así que la de menor conteo se encuentra y se reemplaza en tiempo logarítmico, sin recorrer todos los contadores.
+#[derive(Debug, Clone, PartialEq)]
+struct SpaceSaving {
+    capacity: usize,
+    counters: HashMap<String, Counter>,
+    buckets: BTreeMap<usize, BTreeSet<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Counter {
+    count: usize,
+    error: usize,
+}
+
+/// Una palabra con la cantidad de veces que aparece.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordFrequency {
+    pub word: String,
+    pub count: usize,
+}
+
+impl TopWords {
+    /// Crea un conteo vacío con la configuración indicada.
+    pub fn new(config: &TopWordsConfig) -> TopWords {
+        let counters = match config.mode {
+            TopWordsMode::Sketch => {
+                Counters::Sketch(SpaceSaving::new(config.top_n * COUNTERS_PER_WORD))
+            }
+            TopWordsMode::Exact => Counters::Exact(HashMap::new()),
+        };
+        TopWords {
+            config: config.clone(),
+            counters,
+        }
+    }
+
+    /// Configuración con la que cuenta.
+    pub fn config(&self) -> &TopWordsConfig {
+        &self.config
+    }
+
+    /// Suma las palabras de una pregunta que no son stopwords.
+    pub fn add_question(&mut self, question: &Question) {
+        question
+            .tokens
+            .iter()
+            .filter(|(token, _)| !self.config.stopwords.contains(token))
+            .for_each(|(token, count)| match &mut self.counters {
+                Counters::Sketch(sketch) => sketch.add(token, *count),
+                Counters::Exact(counts) => match counts.get_mut(token) {
+                    Some(total) => *total += count,
+                    None => {
+                        counts.insert(token.to_string(), *count);
+                    }
+                },
+            });
+    }
+
+    /// Une otro conteo a sí mismo, in-place. Si alguno de los dos es un sketch, el resultado también.
+    /// Para que los conteos del sketch sigan acotando a los reales, a las palabras que no están en un sketch lleno se les suma su mínimo, que es lo más que podrían haber aparecido en él.
+    pub fn merge(&mut self, other: &TopWords) {
+        match (&mut self.counters, &other.counters) {
+            (Counters::Exact(counts), Counters::Exact(other_counts)) => {
+                other_counts.iter().for_each(|(word, count)| {
+                    *counts.entry(word.to_string()).or_insert(0) += count;
+                })
+            }
+            (Counters::Sketch(sketch), Counters::Exact(other_counts)) => other_counts
+                .iter()
+                .for_each(|(word, count)| sketch.add(word, *count)),
+            (Counters::Exact(counts), Counters::Sketch { .. }) => {
+                let mut merged = other.clone();
+                counts.iter().for_each(|(word, count)| {
+                    if let Counters::Sketch(sketch) = &mut merged.counters {
+                        sketch.add(word, *count);
+                    }
+                });
+                *self = merged;
+            }
+            (Counters::Sketch(sketch), Counters::Sketch(other_sketch)) => {
+                let min = sketch.min();
+                let other_min = other_sketch.min();
+                let mut merged: HashMap<String, Counter> = HashMap::new();
+                sketch.counters.drain().for_each(|(word, counter)| {
+                    let other_counter =
+                        other_sketch
+                            .counters
+                            .get(&word)
+                            .copied()
+                            .unwrap_or(Counter {
+                                count: other_min,
+                                error: other_min,
+                            });
+                    merged.insert(
+                        word,
+                        Counter {
+                            count: counter.count + other_counter.count,
+                            error: counter.error + other_counter.error,
+                        },
+                    );
+                });
+                other_sketch.counters.iter().for_each(|(word, counter)| {
+                    merged.entry(word.to_string()).or_insert(Counter {
+                        count: counter.count + min,
+                        error: counter.error + min,
+                    });
+                });
+                let mut merged: Vec<(String, Counter)> = merged.into_iter().collect();
+                merged.sort_by(|(word_1, counter_1), (word_2, counter_2)| {
+                    counter_2
+                        .count
+                        .cmp(&counter_1.count)
+                        .then(word_1.cmp(word_2))
+                });
+                merged.truncate(sketch.capacity);
+                let mut result = SpaceSaving::new(sketch.capacity);
+                merged
+                    .into_iter()
+                    .for_each(|(word, counter)| result.insert(word, counter));
+                *sketch = result;
+            }
+        }
+    }
+
+    /// Las `top_n` palabras más frecuentes, de mayor a menor cantidad. Los empates se ordenan alfabéticamente.
+    pub fn top(&self) -> Vec<WordFrequency> {
+        let mut words: Vec<WordFrequency> = match &self.counters {
+            Counters::Sketch(sketch) => sketch
+                .counters
+                .iter()
+                .map(|(word, counter)| WordFrequency {
+                    word: word.to_string(),
+                    count: counter.count,
+                })
+                .collect(),
+            Counters::Exact(counts) => counts
+                .iter()
+                .map(|(word, count)| WordFrequency {
+                    word: word.to_string(),
+                    count: *count,
+                })
+                .collect(),
+        };
+        words.sort_by(|word_1, word_2| {
+            word_2
+                .count
+                .cmp(&word_1.count)
+                .then(word_1.word.cmp(&word_2.word))
+        });
+        words.truncate(self.config.top_n);
+        words
+    }
+
+    /// Cotas de la cantidad real de veces que apareció una palabra. En modo exacto son iguales.
+    /// En modo sketch, si la palabra tiene contador apareció entre `count - error` y `count` veces, y si no, a lo sumo tantas veces como el menor contador.
+    pub fn count_bounds(&self, word: &str) -> (usize, usize) {
+        match &self.counters {
+            Counters::Sketch(sketch) => match sketch.counters.get(word) {
+                Some(counter) => (counter.count - counter.error, counter.count),
+                None => (0, sketch.min()),
+            },
+            Counters::Exact(counts) => {
+                let count = counts.get(word).copied().unwrap_or(0);
+                (count, count)
+            }
+        }
+    }
+}
+
+impl SpaceSaving {
+    /// Crea un sketch vacío con `capacity` contadores.
+    fn new(capacity: usize) -> SpaceSaving {
+        SpaceSaving {
+            capacity,
+            counters: HashMap::new(),
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    /// Suma `count` apariciones de una palabra. Si la palabra no tiene contador y el sketch está lleno, reemplaza a la de menor conteo (la primera alfabéticamente si hay empates) y hereda su conteo como error.
+    fn add(&mut self, word: &str, count: usize) {
+        if let Some(counter) = self.counters.get_mut(word) {
+            let word = take_from_bucket(&mut self.buckets, counter.count, word);
+            counter.count += count;
+            self.buckets.entry(counter.count).or_default().insert(word);
+            return;
+        }
+        let mut counter = Counter { count, error: 0 };
+        if self.counters.len() >= self.capacity {
+            let Some(min_counter) = self.pop_min() else {
+                return;
+            };
+            counter.count += min_counter.count;
+            counter.error += min_counter.count;
+        }
+        self.insert(word.to_string(), counter);
+    }
+
+    /// Agrega el contador de una palabra que no tiene contador.
+    fn insert(&mut self, word: String, counter: Counter) {
+        self.buckets
+            .entry(counter.count)
+            .or_default()
+            .insert(word.clone());
+        self.counters.insert(word, counter);
+    }
+
+    /// Saca el contador de menor conteo, y devuelve None si no hay ninguno.
+    fn pop_min(&mut self) -> Option<Counter> {
+        let mut bucket = self.buckets.first_entry()?;
+        let word = bucket.get_mut().pop_first()?;
+        if bucket.get().is_empty() {
+            bucket.remove();
+        }
+        self.counters.remove(&word)
+    }
+
+    /// Menor conteo si el sketch está lleno, o 0 si todavía tiene contadores libres.
+    fn min(&self) -> usize {
+        if self.counters.len() < self.capacity {
+            return 0;
+        }
+        self.buckets.keys().next().copied().unwrap_or(0)
+    }
+}
+
+/// Saca la palabra del bucket de su conteo, borrando el bucket si queda vacío, y la devuelve sin volver a copiarla.
+fn take_from_bucket(
+    buckets: &mut BTreeMap<usize, BTreeSet<String>>,
+    count: usize,
+    word: &str,
+) -> String {
+    let Some(bucket) = buckets.get_mut(&count) else {
+        return word.to_string();
+    };
+    let taken = bucket.take(word);
+    if bucket.is_empty() {
+        buckets.remove(&count);
+    }
+    taken.unwrap_or_else(|| word.to_string())
+}
+
+impl From<TopWords> for Vec<WordFrequency> {
+    fn from(top_words: TopWords) -> Self {
+        top_words.top()
+    }
+}
+
+/// Une un conteo opcional a otro. Si solo el otro tiene conteo, se copia.
+pub fn merge_top_words(top_words: &mut Option<TopWords>, other: &Option<TopWords>) {
+    match (top_words, other) {
+        (Some(top_words), Some(other)) => top_words.merge(other),
+        (top_words @ None, Some(other)) => *top_words = Some(other.clone()),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(top_words: &TopWords) -> Vec<(String, usize)> {
+        top_words
+            .top()
+            .into_iter()
+            .map(|frequency| (frequency.word, frequency.count))
+            .collect()
+    }
+
+    #[test]
+    fn counts_words_without_stopwords() {
+        let mut top_words = TopWords::new(&TopWordsConfig::new(2, TopWordsMode::Exact));
+        top_words.add_question(&Question::from_text(
+            "The rust compiler and the Rust borrow checker",
+        ));
+        top_words.add_question(&Question::from_text("a borrow of a borrow"));
+
+        assert_eq!(
+            words(&top_words),
+            [("borrow".to_string(), 3), ("rust".to_string(), 2)]
+        );
+        assert_eq!(top_words.count_bounds("rust"), (2, 2));
+        assert_eq!(top_words.count_bounds("the"), (0, 0));
+    }
+
+    #[test]
+    fn stopwords_are_configurable() {
+        let config = TopWordsConfig::new(3, TopWordsMode::Exact).with_stopwords(["Rust"]);
+        let mut top_words = TopWords::new(&config);
+        top_words.add_question(&Question::from_text("the rust the Rust a"));
+
+        assert_eq!(
+            words(&top_words),
+            [("the".to_string(), 2), ("a".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn sketch_keeps_heavy_hitters_with_bounded_counters() {
+        let config =
+            TopWordsConfig::new(2, TopWordsMode::Sketch).with_stopwords(Vec::<&str>::new());
+        let mut sketch = TopWords::new(&config);
+        let mut exact = TopWords::new(&TopWordsConfig {
+            mode: TopWordsMode::Exact,
+            ..config.clone()
+        });
+        (0..200).for_each(|i| {
+            let text = format!("heavy heavy common rare{} common heavy", i);
+            sketch.add_question(&Question::from_text(&text));
+            exact.add_question(&Question::from_text(&text));
+        });
+
+        assert_eq!(words(&sketch), words(&exact));
+        assert_eq!(sketch.count_bounds("heavy"), (600, 600));
+        let (lower, upper) = sketch.count_bounds("rare0");
+        assert!(lower <= 1 && 1 <= upper);
+        if let Counters::Sketch(space_saving) = &sketch.counters {
+            assert_eq!(space_saving.counters.len(), space_saving.capacity);
+            let bucketed: usize = space_saving.buckets.values().map(BTreeSet::len).sum();
+            assert_eq!(bucketed, space_saving.capacity);
+        }
+    }
+
+    #[test]
+    fn merged_sketch_bounds_the_real_counts() {
+        let config =
+            TopWordsConfig::new(2, TopWordsMode::Sketch).with_stopwords(Vec::<&str>::new());
+        let mut exact = TopWords::new(&TopWordsConfig {
+            mode: TopWordsMode::Exact,
+            ..config.clone()
+        });
+        let mut sketch_1 = TopWords::new(&config);
+        let mut sketch_2 = TopWords::new(&config);
+        (0..100).for_each(|i| {
+            let text = format!("alpha beta w{} w{}", i % 13, i % 7);
+            exact.add_question(&Question::from_text(&text));
+            if i % 3 == 0 {
+                sketch_1.add_question(&Question::from_text(&text));
+            } else {
+                sketch_2.add_question(&Question::from_text(&text));
+            }
+        });
+        let mut merged = sketch_1.clone();
+        merged.merge(&sketch_2);
+        sketch_2.merge(&sketch_1);
+
+        assert_eq!(words(&merged), words(&sketch_2));
+        assert_eq!(
+            words(&merged),
+            [("alpha".to_string(), 100), ("beta".to_string(), 100)]
+        );
+        if let Counters::Exact(exact_counts) = &exact.counters {
+            exact_counts.iter().for_each(|(word, real)| {
+                let (lower, upper) = merged.count_bounds(word);
+                assert!(lower <= *real && *real <= upper);
+            });
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    io::{self, BufRead},
+    path::PathBuf,
+};
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{error::ProcessingError, input::open_reader, line::Line};
+
+/// Cantidad máxima de números de línea inválidos que se guardan por archivo.
+const MAX_INVALID_SAMPLE: usize = 10;
+
+/// Resultado de validar un archivo JSON: cuántas líneas tiene, cuántas no se pudieron leer o parsear a un Line, y los números de las primeras.
+#[derive(Debug)]
+pub struct FileValidation {
+    pub path: PathBuf,
+    pub lines: usize,
+    pub invalid_lines: usize,
+    pub invalid_sample: Vec<usize>,
+}
+
+impl FileValidation {
+    /// Indica si todas las líneas del archivo son válidas.
+    pub fn is_valid(&self) -> bool {
+        self.invalid_lines == 0
+    }
+}
+
+/// Valida concurrentemente los archivos JSON pasados por parámetro, verificando que cada línea se pueda parsear a un Line.
+/// Los archivos que no se pueden abrir se informan con un error en vez de validarse.
+pub fn validate_files(json_paths: Vec<PathBuf>) -> Vec<Result<FileValidation, ProcessingError>> {
+    json_paths.into_par_iter().map(validate_file).collect()
+}
+
+/// Valida un archivo JSON línea por línea.
+/// Si falla la lectura por algo distinto a una línea con UTF-8 inválido (por ejemplo un archivo comprimido truncado o corrupto), se deja de leer y se devuelve ese error para todo el archivo.
+fn validate_file(path: PathBuf) -> Result<FileValidation, ProcessingError> {
+    let reader = match open_reader(&path) {
+        Ok(reader) => reader,
+        Err(source) => {
+            return Err(ProcessingError::Io {
+                path,
+                line: None,
+                source,
+            })
+        }
+    };
+    let mut validation = FileValidation {
+        path,
+        lines: 0,
+        invalid_lines: 0,
+        invalid_sample: vec![],
+    };
+    for (i, line) in reader.lines().enumerate() {
+        let line = match line {
+            Err(source) if source.kind() != io::ErrorKind::InvalidData => {
+                return Err(ProcessingError::Io {
+                    path: validation.path,
+                    line: Some(i + 1),
+                    source,
+                })
+            }
+            line => line,
+        };
+        validation.lines += 1;
+        let valid = line.is_ok_and(|l| Line::parse(&l).is_ok());
+        if !valid {
+            validation.invalid_lines += 1;
+            if validation.invalid_sample.len() < MAX_INVALID_SAMPLE {
+                validation.invalid_sample.push(i + 1);
+            }
+        }
+    }
+    Ok(validation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_test_data() {
+        let json_paths = vec![PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_data/academia.stackexchange.com.jsonl"
+        ))];
+        let validations = validate_files(json_paths);
+
+        let validation = validations[0].as_ref().unwrap();
+        assert_eq!(validation.lines, 5);
+        assert!(validation.is_valid());
+    }
+
+    #[test]
+    fn reports_invalid_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("invalid.jsonl");
+        std::fs::write(
+            &path,
+            "{\"texts\": [\"a b\"], \"tags\": [\"t\"]}\nno es json\n{\"texts\": [\"a\"]}\n",
+        )
+        .unwrap();
+        let missing_path = dir.path().join("missing.jsonl");
+
+        let validations = validate_files(vec![path, missing_path]);
+
+        let validation = validations[0].as_ref().unwrap();
+        assert_eq!(validation.lines, 3);
+        assert_eq!(validation.invalid_lines, 2);
+        assert_eq!(validation.invalid_sample, [2, 3]);
+        assert!(validations[1].is_err());
+    }
+
+    #[test]
+    fn stops_on_truncated_compressed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("truncated.jsonl.gz");
+        let line = "{\"texts\": [\"a b\"], \"tags\": [\"t\"]}\n";
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        io::Write::write_all(&mut encoder, line.repeat(1000).as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        std::fs::write(&path, &compressed[..compressed.len() / 2]).unwrap();
+
+        let validations = validate_files(vec![path]);
+
+        assert!(matches!(
+            validations[0],
+            Err(ProcessingError::Io { line: Some(_), .. })
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{hyperloglog::HyperLogLog, question::Question};
+
+/// Forma de contar las palabras distintas de cada Site y Tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VocabularyMode {
+    /// Estima las palabras distintas con un sketch HyperLogLog, de tamaño acotado.
+    #[default]
+    Sketch,
+    /// Guarda todas las palabras distintas en un conjunto. Es exacto, pero solo sirve para entradas chicas.
+    Exact,
+}
+
+/// Vocabulario de un Site o Tag: la cantidad de palabras distintas (en minúsculas) de sus preguntas, estimada o exacta según el modo, y la cantidad total de palabras.
+/// Se puede unir con otro vocabulario sin importar el orden, así que se combina correctamente en el reduce paralelo.
+/// Solo se serializa el resumen (VocabularySummary), así que no se puede volver a leer de un JSON.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(into = "VocabularySummary")]
+pub struct Vocabulary {
+    tokens: usize,
+    distinct: DistinctTokens,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DistinctTokens {
+    Sketch(HyperLogLog),
+    Exact(HashSet<String>),
+}
+
+/// Resumen serializado de un vocabulario. `type_token_ratio` es la cantidad de palabras distintas sobre el total de palabras.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VocabularySummary {
+    pub distinct: usize,
+    pub tokens: usize,
+    pub type_token_ratio: f64,
+    pub exact: bool,
+}
+
+impl Vocabulary {
+    /// Crea un vocabulario vacío en el modo indicado.
+    pub fn new(mode: VocabularyMode) -> Vocabulary {
+        Vocabulary {
+            tokens: 0,
+            distinct: match mode {
+                VocabularyMode::Sketch => DistinctTokens::Sketch(HyperLogLog::default()),
+                VocabularyMode::Exact => DistinctTokens::Exact(HashSet::new()),
+            },
+        }
+    }
+
+    /// Modo en el que cuenta las palabras distintas.
+    pub fn mode(&self) -> VocabularyMode {
+        match self.distinct {
+            DistinctTokens::Sketch(_) => VocabularyMode::Sketch,
+            DistinctTokens::Exact(_) => VocabularyMode::Exact,
+        }
+    }
+
+    /// Agrega las palabras de una pregunta.
+    pub fn add_question(&mut self, question: &Question) {
+        self.tokens += question.words.total();
+        match &mut self.distinct {
+            DistinctTokens::Sketch(sketch) => question
+                .tokens
+                .iter()
+                .for_each(|(token, _)| sketch.add(token)),
+            DistinctTokens::Exact(set) => {
+                set.extend(question.tokens.iter().map(|(token, _)| token.to_string()))
+            }
+        }
+    }
+
+    /// Une otro vocabulario a sí mismo, in-place. Si los modos son distintos, el resultado queda en modo sketch.
+    pub fn merge(&mut self, other: &Vocabulary) {
+        self.tokens += other.tokens;
+        match (&mut self.distinct, &other.distinct) {
+            (DistinctTokens::Sketch(sketch), DistinctTokens::Sketch(other_sketch)) => {
+                sketch.merge(other_sketch)
+            }
+            (DistinctTokens::Exact(set), DistinctTokens::Exact(other_set)) => {
+                set.extend(other_set.iter().cloned())
+            }
+            (DistinctTokens::Sketch(sketch), DistinctTokens::Exact(other_set)) => {
+                other_set.iter().for_each(|token| sketch.add(token))
+            }
+            (DistinctTokens::Exact(set), DistinctTokens::Sketch(other_sketch)) => {
+                let mut sketch = other_sketch.clone();
+                set.iter().for_each(|token| sketch.add(token));
+                self.distinct = DistinctTokens::Sketch(sketch);
+            }
+        }
+    }
+
+    /// Cantidad de palabras distintas, estimada en modo sketch.
+    pub fn distinct(&self) -> usize {
+        match &self.distinct {
+            DistinctTokens::Sketch(sketch) => sketch.estimate(),
+            DistinctTokens::Exact(set) => set.len(),
+        }
+    }
+
+    /// Cantidad total de palabras.
+    pub fn tokens(&self) -> usize {
+        self.tokens
+    }
+
+    /// Palabras distintas sobre el total de palabras. Es 0 si no hay palabras.
+    pub fn type_token_ratio(&self) -> f64 {
+        if self.tokens == 0 {
+            0.0
+        } else {
+            self.distinct() as f64 / self.tokens as f64
+        }
+    }
+}
+
+impl From<Vocabulary> for VocabularySummary {
+    fn from(vocabulary: Vocabulary) -> Self {
+        VocabularySummary {
+            distinct: vocabulary.distinct(),
+            tokens: vocabulary.tokens,
+            type_token_ratio: vocabulary.type_token_ratio(),
+            exact: vocabulary.mode() == VocabularyMode::Exact,
+        }
+    }
+}
+
+/// Une un vocabulario opcional a otro. Si solo el otro tiene vocabulario, se copia.
+pub fn merge_vocabularies(vocabulary: &mut Option<Vocabulary>, other: &Option<Vocabulary>) {
+    match (vocabulary, other) {
+        (Some(vocabulary), Some(other)) => vocabulary.merge(other),
+        (vocabulary @ None, Some(other)) => *vocabulary = Some(other.clone()),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_distinct_lowercase_tokens() {
+        let mut vocabulary = Vocabulary::new(VocabularyMode::Exact);
+        vocabulary.add_question(&Question::from_text("the cat and The dog"));
+        vocabulary.add_question(&Question::from_text("a dog"));
+
+        assert_eq!(vocabulary.tokens(), 7);
+        assert_eq!(vocabulary.distinct(), 5);
+        assert_eq!(vocabulary.type_token_ratio(), 5.0 / 7.0);
+        assert_eq!(
+            Vocabulary::new(VocabularyMode::Sketch).type_token_ratio(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn sketch_matches_exact_on_small_vocabularies() {
+        let mut exact = Vocabulary::new(VocabularyMode::Exact);
+        let mut sketch = Vocabulary::new(VocabularyMode::Sketch);
+        [
+            "the cat and The dog",
+            "a dog",
+            "one two three four five six",
+        ]
+        .iter()
+        .for_each(|text| {
+            exact.add_question(&Question::from_text(text));
+            sketch.add_question(&Question::from_text(text));
+        });
+
+        assert_eq!(sketch.distinct(), exact.distinct());
+        assert_eq!(sketch.tokens(), exact.tokens());
+    }
+
+    #[test]
+    fn merge_keeps_distinct_tokens() {
+        let mut vocabulary = Some(Vocabulary::new(VocabularyMode::Exact));
+        let mut other = Vocabulary::new(VocabularyMode::Exact);
+        vocabulary
+            .as_mut()
+            .unwrap()
+            .add_question(&Question::from_text("a b c"));
+        other.add_question(&Question::from_text("b c d"));
+        merge_vocabularies(&mut vocabulary, &Some(other.clone()));
+        assert_eq!(vocabulary.as_ref().unwrap().distinct(), 4);
+        assert_eq!(vocabulary.as_ref().unwrap().tokens(), 6);
+
+        let mut sketch = Vocabulary::new(VocabularyMode::Sketch);
+        sketch.add_question(&Question::from_text("c e"));
+        let mut mixed = vocabulary.clone().unwrap();
+        mixed.merge(&sketch);
+        assert_eq!(mixed.mode(), VocabularyMode::Sketch);
+        assert_eq!(mixed.distinct(), 5);
+
+        let mut none = None;
+        merge_vocabularies(&mut none, &Some(other));
+        assert_eq!(none.unwrap().distinct(), 3);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{language::cjk_length, line::Line, tokenizer::Tokenizer};
+
+/// Cantidad de palabras del título y del cuerpo de una pregunta, o las sumas de varias preguntas.
+/// En el dataset el primer text es el título y el segundo el cuerpo. Si hubiera más texts, se cuentan como parte del cuerpo.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WordCounts {
+    pub title: usize,
+    pub body: usize,
+}
+
+impl WordCounts {
+    /// Construye las cantidades de palabras del título y el cuerpo indicadas.
+    pub fn new(title: usize, body: usize) -> WordCounts {
+        WordCounts { title, body }
+    }
+
+    /// Cuenta las palabras del título y del cuerpo de una línea con el tokenizer indicado.
+    pub fn from_line(line: &Line, tokenizer: Tokenizer) -> WordCounts {
+        WordCounts::count(line, |text| tokenizer.count_words(text))
+    }
+
+    /// Mide el título y el cuerpo de una línea en un idioma CJK, contando cada caracter CJK como una palabra.
+    pub fn from_line_cjk(line: &Line) -> WordCounts {
+        WordCounts::count(line, |text| cjk_length(text))
+    }
+
+    /// Mide el título y el cuerpo de una línea con la función indicada.
+    fn count(line: &Line, count: impl Fn(&String) -> usize) -> WordCounts {
+        let mut texts = line.texts.iter();
+        WordCounts {
+            title: texts.next().map_or(0, &count),
+            body: texts.map(count).sum(),
+        }
+    }
+
+    /// Cantidad total de palabras.
+    pub fn total(&self) -> usize {
+        self.title + self.body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_title_and_body_separately() {
+        let line = Line::parse(
+            "{\"texts\": [\"A short title\", \"The body  of\\nthe question\", \"more\"], \"tags\": []}",
+        )
+        .unwrap();
+
+        let words = WordCounts::from_line(&line, Tokenizer::Whitespace);
+
+        assert_eq!(words, WordCounts::new(3, 6));
+        assert_eq!(words.total(), 9);
+        assert_eq!(
+            WordCounts::from_line(&line, Tokenizer::Unicode),
+            WordCounts::new(3, 6)
+        );
+    }
+
+    #[test]
+    fn measures_cjk_lines_by_characters() {
+        let line = Line::parse("{\"texts\": [\"ファイルを読む\", \"Rust で 読む\"], \"tags\": []}")
+            .unwrap();
+
+        assert_eq!(WordCounts::from_line_cjk(&line), WordCounts::new(7, 4));
+        assert_eq!(
+            WordCounts::from_line(&line, Tokenizer::Whitespace),
+            WordCounts::new(1, 3)
+        );
+    }
+
+    #[test]
+    fn lines_without_texts_have_no_words() {
+        let line = Line::parse("{\"texts\": [], \"tags\": []}").unwrap();
+
+        assert_eq!(
+            WordCounts::from_line(&line, Tokenizer::Whitespace),
+            WordCounts::default()
+        );
+    }
+}